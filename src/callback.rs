@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use hyper::header::{HeaderValue, CONNECTION};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::http::FetchResult;
+
+/// context key of the listener url, e.g. {{$callback_url}}
+pub const CALLBACK_URL_KEY: &str = "$callback_url";
+/// json path prefix of values extracted from the inbound request
+pub const CALLBACK_PATH_PREFIX: &str = "$.callback";
+/// prefix of the expectations checked against the inbound request, e.g. CALLBACK:JSON_INCLUDE(...),
+/// the other ones being checked against the http response
+pub const CALLBACK_EXPECT_PREFIX: &str = "CALLBACK";
+
+/// Temporary http listener receiving webhooks called back by the tested api
+pub struct CallbackListener {
+    pub url: String,
+    receiver: mpsc::UnboundedReceiver<Value>,
+    server: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// Inbound request as a json document, the body being kept as json when possible
+async fn as_document(request: Request<Body>) -> anyhow::Result<Value> {
+    let (parts, body) = request.into_parts();
+    let body = String::from_utf8_lossy(&hyper::body::to_bytes(body).await?).into_owned();
+    let query = parts
+        .uri
+        .query()
+        .map(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .into_owned()
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+    let headers = parts
+        .headers
+        .iter()
+        .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+        .collect::<HashMap<_, _>>();
+    Ok(json!({
+        "callback": {
+            "method": parts.method.as_str(),
+            "path": parts.uri.path(),
+            "query": query,
+            "headers": headers,
+            "body": serde_json::from_str::<Value>(&body).unwrap_or(Value::String(body)),
+        }
+    }))
+}
+
+/// Address of the interface reaching other machines, no packet is sent
+fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    // documentation address, only used to pick a route
+    socket.connect("192.0.2.1:80").ok()?;
+    socket.local_addr().ok().map(|a| a.ip())
+}
+
+/// Host the tested api can call back: the configured one,
+/// or the address of this machine when listening on all interfaces
+fn advertised_host(addr: &str, bound: &SocketAddr) -> String {
+    if bound.ip().is_unspecified() {
+        return local_ip()
+            .filter(|ip| !ip.is_unspecified())
+            .map(|ip| ip.to_string())
+            .unwrap_or("localhost".to_string());
+    }
+    match addr.rsplit_once(':') {
+        Some((host, _)) if !host.is_empty() => host.to_string(),
+        _ => bound.ip().to_string(),
+    }
+}
+
+impl CallbackListener {
+    /// Listen on addr, e.g. :9000 for all interfaces, 127.0.0.1:9000 or my-laptop.lan:9000
+    pub async fn listen(addr: &str) -> anyhow::Result<Self> {
+        let socket_addr: SocketAddr = match addr.strip_prefix(':') {
+            Some(port) => SocketAddr::from(([0, 0, 0, 0], port.parse()?)),
+            None => addr
+                .to_socket_addrs()?
+                .next()
+                .ok_or(anyhow::anyhow!("Cannot resolve {}", addr))?,
+        };
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let make_service = make_service_fn(move |_| {
+            let sender = sender.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let sender = sender.clone();
+                    async move {
+                        if let Ok(document) = as_document(request).await {
+                            let _ = sender.send(document);
+                        }
+                        // no kept alive connection outliving the listener
+                        let mut response = Response::new(Body::empty());
+                        response
+                            .headers_mut()
+                            .insert(CONNECTION, HeaderValue::from_static("close"));
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+        let server = Server::try_bind(&socket_addr)?.serve(make_service);
+        let url = format!(
+            "http://{}:{}",
+            advertised_host(addr, &socket_addr),
+            server.local_addr().port()
+        );
+        let server = tokio::spawn(async move {
+            let _ = server.await;
+        });
+        Ok(CallbackListener {
+            url,
+            receiver,
+            server: Some(server),
+        })
+    }
+
+    /// Wait for the next inbound request, returned as a successful fetch result
+    pub async fn wait(&mut self, timeout: Duration) -> anyhow::Result<FetchResult> {
+        let start = Instant::now();
+        match tokio::time::timeout(timeout, self.receiver.recv()).await {
+            Ok(Some(document)) => Ok(FetchResult {
+                response: document.to_string(),
+                status: 200,
+                duration: start.elapsed(),
+            }),
+            _ => anyhow::bail!("No callback received on {} within {:?}", self.url, timeout),
+        }
+    }
+
+    /// Stop listening, the address being free again once returned, e.g. for the next step
+    pub async fn close(mut self) {
+        if let Some(server) = self.server.take() {
+            server.abort();
+            let _ = server.await;
+        }
+    }
+}
+
+impl Drop for CallbackListener {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_callback() {
+        let mut listener = CallbackListener::listen("127.0.0.1:0").await.unwrap();
+        assert!(listener.url.starts_with("http://127.0.0.1:"));
+        let url = format!("{}/hooks/order?event=paid", listener.url);
+        tokio::spawn(async move {
+            reqwest::Client::new()
+                .post(url)
+                .header("X-Signature", "abc")
+                .body(r#"{"id": 42}"#)
+                .send()
+                .await
+        });
+
+        let result = listener.wait(Duration::from_secs(5)).await.unwrap();
+        let document: Value = serde_json::from_str(&result.response).unwrap();
+        assert_eq!(document["callback"]["method"], "POST");
+        assert_eq!(document["callback"]["path"], "/hooks/order");
+        assert_eq!(document["callback"]["query"]["event"], "paid");
+        assert_eq!(document["callback"]["headers"]["x-signature"], "abc");
+        assert_eq!(document["callback"]["body"]["id"], 42);
+
+        assert!(listener.wait(Duration::from_millis(100)).await.is_err());
+
+        // the address is free again once closed
+        let addr = listener.url.trim_start_matches("http://").to_string();
+        listener.close().await;
+        assert!(CallbackListener::listen(&addr).await.is_ok());
+    }
+
+    #[test]
+    fn test_advertised_host() {
+        let bound = SocketAddr::from(([192, 168, 1, 5], 9000));
+        assert_eq!(advertised_host("192.168.1.5:9000", &bound), "192.168.1.5");
+        assert_eq!(
+            advertised_host("my-laptop.lan:9000", &bound),
+            "my-laptop.lan"
+        );
+        let all = SocketAddr::from(([0, 0, 0, 0], 9000));
+        assert_ne!(advertised_host(":9000", &all), "0.0.0.0");
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use crate::http::FetchResult;
+
+/// Record http exchanges to a cassette or replay them without network
+#[derive(Args, Debug, Clone, Default)]
+pub struct CassetteArgs {
+    /// record http exchanges to a cassette file
+    #[arg(long, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// answer http requests from a cassette file
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+
+    /// fail requests not found in the replayed cassette instead of sending them
+    #[arg(long, requires = "replay")]
+    pub strict: bool,
+
+    /// header that must match when replaying, method, url and body always match
+    #[arg(long, requires = "replay")]
+    pub match_header: Option<Vec<String>>,
+}
+
+impl CassetteArgs {
+    pub fn cassette(&self) -> anyhow::Result<Option<Cassette>> {
+        match (self.record.as_ref(), self.replay.as_ref()) {
+            (Some(path), _) => Ok(Some(Cassette::new(path.clone(), Mode::Record))),
+            (_, Some(path)) => {
+                let cassette = Cassette::new(
+                    path.clone(),
+                    Mode::Replay {
+                        strict: self.strict,
+                        match_headers: self
+                            .match_header
+                            .iter()
+                            .flatten()
+                            .map(|h| h.to_lowercase())
+                            .collect(),
+                    },
+                );
+                let content = std::fs::read_to_string(path)?;
+                *cassette.interactions.lock().unwrap() =
+                    serde_json::from_str::<CassetteFile>(&content)?.interactions;
+                Ok(Some(cassette))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// url with query params
+    pub url: String,
+    /// lowercase header names
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+}
+
+impl From<&reqwest::Request> for RecordedRequest {
+    fn from(request: &reqwest::Request) -> Self {
+        RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: request
+                .headers()
+                .iter()
+                .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+                .collect(),
+            body: request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .map(|b| String::from_utf8_lossy(b).into_owned()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RecordedResponse {
+    status: u16,
+    body: String,
+    duration_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+    #[serde(skip)]
+    replayed: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug)]
+enum Mode {
+    Record,
+    Replay {
+        strict: bool,
+        match_headers: Vec<String>,
+    },
+}
+
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl Cassette {
+    fn new(path: PathBuf, mode: Mode) -> Self {
+        Self {
+            path,
+            mode,
+            interactions: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Record)
+    }
+
+    fn matches(&self, recorded: &RecordedRequest, request: &RecordedRequest) -> bool {
+        let Mode::Replay { match_headers, .. } = &self.mode else {
+            return false;
+        };
+        recorded.method == request.method
+            && recorded.url == request.url
+            && recorded.body == request.body
+            && match_headers
+                .iter()
+                .all(|h| recorded.headers.get(h) == request.headers.get(h))
+    }
+
+    /// Recorded response of the request, identical requests are replayed in recording order.
+    /// None if the request must be sent, an error in strict mode
+    pub fn replay(&self, request: &RecordedRequest) -> anyhow::Result<Option<FetchResult>> {
+        let Mode::Replay { strict, .. } = self.mode else {
+            return Ok(None);
+        };
+        let mut interactions = self.interactions.lock().unwrap();
+        let matching = interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| self.matches(&i.request, request))
+            .map(|(index, i)| (index, i.replayed))
+            .collect::<Vec<_>>();
+        // first interaction not replayed yet, the last one otherwise
+        let found = matching
+            .iter()
+            .find(|(_, replayed)| !replayed)
+            .or(matching.last())
+            .map(|(index, _)| *index);
+        match found {
+            Some(index) => {
+                let interaction = &mut interactions[index];
+                interaction.replayed = true;
+                Ok(Some(FetchResult {
+                    response: interaction.response.body.clone(),
+                    status: interaction.response.status,
+                    duration: Duration::from_millis(interaction.response.duration_ms),
+                }))
+            }
+            None if strict => anyhow::bail!(
+                "No interaction of cassette {} matches {} {}",
+                self.path.display(),
+                request.method,
+                request.url
+            ),
+            None => Ok(None),
+        }
+    }
+
+    /// Append an exchange and save the cassette
+    pub fn record(&self, request: RecordedRequest, result: &FetchResult) -> anyhow::Result<()> {
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(Interaction {
+            request,
+            response: RecordedResponse {
+                status: result.status,
+                body: result.response.clone(),
+                duration_ms: result.duration.as_millis() as u64,
+            },
+            replayed: false,
+        });
+        let file = CassetteFile {
+            interactions: interactions.clone(),
+        };
+        std::fs::write(&self.path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str, token: &str) -> RecordedRequest {
+        RecordedRequest {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            headers: BTreeMap::from([("authorization".to_string(), token.to_string())]),
+            body: None,
+        }
+    }
+
+    fn result(response: &str) -> FetchResult {
+        FetchResult {
+            response: response.to_string(),
+            status: 200,
+            duration: Duration::from_millis(12),
+        }
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let path = std::env::temp_dir().join("apikrab_cassette.json");
+        let recorder = CassetteArgs {
+            record: Some(path.clone()),
+            ..Default::default()
+        }
+        .cassette()
+        .unwrap()
+        .unwrap();
+        let users = "https://api.example.com/users?page=1";
+        recorder
+            .record(request(users, "a"), &result("first"))
+            .unwrap();
+        recorder
+            .record(request(users, "a"), &result("second"))
+            .unwrap();
+
+        let replayer = CassetteArgs {
+            replay: Some(path.clone()),
+            match_header: Some(vec!["Authorization".to_string()]),
+            ..Default::default()
+        }
+        .cassette()
+        .unwrap()
+        .unwrap();
+        let replayed = |url, token| {
+            replayer
+                .replay(&request(url, token))
+                .unwrap()
+                .map(|r| r.response)
+        };
+        assert_eq!(replayed(users, "a").as_deref(), Some("first"));
+        assert_eq!(replayed(users, "a").as_deref(), Some("second"));
+        assert_eq!(replayed(users, "a").as_deref(), Some("second"));
+        assert_eq!(replayed(users, "b"), None);
+
+        let strict = CassetteArgs {
+            replay: Some(path),
+            strict: true,
+            ..Default::default()
+        }
+        .cassette()
+        .unwrap()
+        .unwrap();
+        assert!(strict
+            .replay(&request("https://api.example.com/users?page=2", "a"))
+            .is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::db::dto::{Action, History, Project, ProjectSource};
+use crate::db::file_storage::{compact_args, expand_args};
+
+/// version of the bundle format, bundles of newer versions are refused
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Projects, actions, test suites, context and optionally history of a database
+#[derive(Serialize, Deserialize, Default)]
+pub struct Bundle {
+    pub version: u32,
+    #[serde(default)]
+    pub projects: Vec<BundleProject>,
+    /// actions without project
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<BundleAction>,
+    #[serde(default)]
+    pub test_suites: Vec<BundleTestSuite>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<History>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BundleProject {
+    pub name: String,
+    pub main_url: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conf: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<ProjectSource>,
+    #[serde(default)]
+    pub actions: Vec<BundleAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BundleAction {
+    pub name: String,
+    /// run action args without their null and false fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_action_args: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_example: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_example: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BundleTestSuite {
+    pub name: String,
+    /// run action args of the steps
+    #[serde(default)]
+    pub steps: Vec<Value>,
+}
+
+impl BundleProject {
+    pub fn new(project: &Project, actions: Vec<BundleAction>) -> anyhow::Result<Self> {
+        Ok(BundleProject {
+            name: project.name.clone(),
+            main_url: project.main_url.clone(),
+            conf: project.get_project_conf()?.into_iter().collect(),
+            source: project.get_source()?,
+            actions,
+        })
+    }
+
+    pub fn to_project(&self, name: &str) -> anyhow::Result<Project> {
+        Ok(Project {
+            id: None,
+            name: name.to_string(),
+            main_url: self.main_url.clone(),
+            conf: Some(serde_json::to_string(&self.conf)?),
+            created_at: None,
+            updated_at: None,
+            source: self
+                .source
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<&Action> for BundleAction {
+    type Error = anyhow::Error;
+
+    fn try_from(action: &Action) -> anyhow::Result<Self> {
+        Ok(BundleAction {
+            name: action.name.clone().unwrap_or_default(),
+            run_action_args: action
+                .run_action_args
+                .as_deref()
+                .map(compact_args)
+                .transpose()?,
+            body_example: action.body_example.clone(),
+            response_example: action.response_example.clone(),
+        })
+    }
+}
+
+impl BundleAction {
+    /// action saved under a name, its run action args being renamed too
+    pub fn to_action(&self, name: &str, project_name: Option<&str>) -> anyhow::Result<Action> {
+        let run_action_args = match self.run_action_args.clone() {
+            Some(Value::Object(mut args)) => {
+                if args.contains_key("name") {
+                    args.insert("name".to_string(), Value::String(name.to_string()));
+                }
+                Some(expand_args(Value::Object(args))?)
+            }
+            Some(args) => Some(expand_args(args)?),
+            None => None,
+        };
+        Ok(Action {
+            id: None,
+            name: Some(name.to_string()),
+            run_action_args,
+            body_example: self.body_example.clone(),
+            response_example: self.response_example.clone(),
+            project_name: project_name.map(str::to_string),
+            created_at: None,
+            updated_at: None,
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colored::Colorize;
+
+use crate::commands::db::bundle::{
+    Bundle, BundleAction, BundleProject, BundleTestSuite, BUNDLE_VERSION,
+};
+use crate::db::db_handler::DBHandler;
+use crate::db::file_storage::compact_args;
+
+#[derive(Args)]
+pub struct DbExportArgs {
+    /// bundle file to write
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    pub path: PathBuf,
+
+    /// only export these projects, can be repeated
+    #[arg(short, long)]
+    pub project: Vec<String>,
+
+    /// only export these test suites, can be repeated
+    #[arg(short, long)]
+    pub test_suite: Vec<String>,
+
+    /// also export the history of the calls
+    #[arg(long)]
+    pub history: bool,
+}
+
+impl DbExportArgs {
+    fn is_filtered(&self) -> bool {
+        !self.project.is_empty() || !self.test_suite.is_empty()
+    }
+
+    /// Everything by default, only the selected projects and test suites
+    /// without the context when filtered
+    pub async fn build_bundle(&self, db_handler: &DBHandler) -> anyhow::Result<Bundle> {
+        let mut bundle = Bundle {
+            version: BUNDLE_VERSION,
+            ..Bundle::default()
+        };
+
+        let projects = match self.is_filtered() {
+            false => db_handler.get_projects().await?,
+            true => {
+                let mut projects = vec![];
+                for name in &self.project {
+                    projects.push(db_handler.get_project(name).await?);
+                }
+                projects
+            }
+        };
+        for project in &projects {
+            let actions = db_handler
+                .get_actions(Some(&project.name))
+                .await?
+                .iter()
+                .map(BundleAction::try_from)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            bundle.projects.push(BundleProject::new(project, actions)?);
+        }
+
+        let test_suites = match self.is_filtered() {
+            false => db_handler
+                .get_test_suites()
+                .await?
+                .into_iter()
+                .map(|ts| ts.name)
+                .collect(),
+            true => self.test_suite.clone(),
+        };
+        for name in test_suites {
+            let steps = db_handler
+                .get_test_suite_instance(&db_handler.get_test_suite(&name).await?.name)
+                .await?
+                .iter()
+                .map(|step| compact_args(&step.run_action_args))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            bundle.test_suites.push(BundleTestSuite { name, steps });
+        }
+
+        if !self.is_filtered() {
+            bundle.actions = db_handler
+                .get_actions(None)
+                .await?
+                .iter()
+                .map(BundleAction::try_from)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            if let Ok(context) = db_handler.get_conf().await {
+                bundle.context = context.get_value().into_iter().collect();
+            }
+        }
+
+        if self.history {
+            bundle.history = Some(db_handler.get_all_history().await?);
+        }
+        Ok(bundle)
+    }
+
+    pub async fn export(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let bundle = self.build_bundle(db_handler).await?;
+        std::fs::write(&self.path, serde_json::to_string_pretty(&bundle)?)?;
+        println!(
+            "Exported {} projects, {} test suites{} to {}",
+            bundle.projects.len(),
+            bundle.test_suites.len(),
+            bundle
+                .history
+                .as_ref()
+                .map(|h| format!(", {} history calls", h.len()))
+                .unwrap_or_default(),
+            self.path.display().to_string().green()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+
+use crate::commands::db::bundle::{Bundle, BundleAction, BUNDLE_VERSION};
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Context, History, TestSuite, TestSuiteInstance};
+use crate::db::file_storage::expand_args;
+
+/// What to do with a bundle project, action or test suite whose name already exists
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OnConflict {
+    /// prompt for each conflict
+    #[default]
+    Ask,
+    /// keep the current one, skipping the bundle one
+    Keep,
+    /// replace the current one by the bundle one
+    Overwrite,
+    /// import the bundle one as name-2, name-3...
+    Rename,
+}
+
+#[derive(Args)]
+pub struct DbImportArgs {
+    /// bundle file written by `db export`
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    pub path: PathBuf,
+
+    /// add the bundle to the current data, the default
+    #[arg(long, conflicts_with = "replace")]
+    pub merge: bool,
+
+    /// remove the current projects, actions, test suites and context first
+    #[arg(long)]
+    pub replace: bool,
+
+    /// how name conflicts are resolved when merging
+    #[arg(long, value_enum, default_value_t)]
+    pub on_conflict: OnConflict,
+}
+
+/// Decision for one conflicting name
+enum Resolved {
+    Skip,
+    Overwrite,
+    /// saved under its own name if free, under a renamed one otherwise
+    Name(String),
+}
+
+/// first name-2, name-3... not taken yet
+fn free_name(name: &str, taken: &HashSet<String>) -> String {
+    (2..)
+        .map(|i| format!("{}-{}", name, i))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap()
+}
+
+fn ask(question: &str) -> anyhow::Result<OnConflict> {
+    loop {
+        print!("{} [k]eep, [o]verwrite or [r]ename? ", question);
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        // nothing more to read, keep the current data
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(OnConflict::Keep);
+        }
+        match answer.trim() {
+            "k" | "keep" => return Ok(OnConflict::Keep),
+            "o" | "overwrite" => return Ok(OnConflict::Overwrite),
+            "r" | "rename" => return Ok(OnConflict::Rename),
+            _ => continue,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    projects: usize,
+    actions: usize,
+    test_suites: usize,
+    context: usize,
+    history: usize,
+    skipped: usize,
+}
+
+impl DbImportArgs {
+    fn resolve(&self, kind: &str, name: &str, taken: &HashSet<String>) -> anyhow::Result<Resolved> {
+        if !taken.contains(name) {
+            return Ok(Resolved::Name(name.to_string()));
+        }
+        let on_conflict = match self.on_conflict {
+            OnConflict::Ask => ask(&format!("{} {} already exists,", kind, name.yellow()))?,
+            on_conflict => on_conflict,
+        };
+        Ok(match on_conflict {
+            OnConflict::Overwrite => Resolved::Overwrite,
+            OnConflict::Rename => Resolved::Name(free_name(name, taken)),
+            _ => Resolved::Skip,
+        })
+    }
+
+    /// remove everything the bundle restores
+    async fn clear(&self, bundle: &Bundle, db_handler: &DBHandler) -> anyhow::Result<()> {
+        for project in db_handler.get_projects().await? {
+            db_handler.rm_project(&project.name).await?;
+        }
+        for action in db_handler.get_actions(None).await? {
+            if let Some(name) = action.name {
+                db_handler.rm_project_action(None, &name).await?;
+            }
+        }
+        for test_suite in db_handler.get_test_suites().await? {
+            db_handler.rm_test_suite(&test_suite.name).await?;
+        }
+        db_handler
+            .insert_conf(&Context {
+                value: "{}".to_string(),
+            })
+            .await?;
+        if bundle.history.is_some() {
+            db_handler.clear_history().await?;
+        }
+        Ok(())
+    }
+
+    async fn import_actions(
+        &self,
+        actions: &[BundleAction],
+        project_name: Option<&str>,
+        taken: &mut HashSet<String>,
+        db_handler: &DBHandler,
+        summary: &mut Summary,
+    ) -> anyhow::Result<()> {
+        for action in actions {
+            let name = match self.resolve("Action", &action.name, taken)? {
+                Resolved::Skip => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                Resolved::Overwrite => {
+                    // a null project never conflicts in sqlite, the upsert would add a copy
+                    db_handler
+                        .rm_project_action(project_name, &action.name)
+                        .await?;
+                    action.name.clone()
+                }
+                Resolved::Name(name) => name,
+            };
+            db_handler
+                .upsert_action(&action.to_action(&name, project_name)?)
+                .await?;
+            taken.insert(name);
+            summary.actions += 1;
+        }
+        Ok(())
+    }
+
+    async fn import_bundle(
+        &self,
+        bundle: &Bundle,
+        db_handler: &DBHandler,
+    ) -> anyhow::Result<Summary> {
+        if bundle.version > BUNDLE_VERSION {
+            anyhow::bail!(
+                "Bundle version {} is not supported, this apikrab reads up to version {}",
+                bundle.version,
+                BUNDLE_VERSION
+            );
+        }
+        if self.replace {
+            self.clear(bundle, db_handler).await?;
+        }
+        let mut summary = Summary::default();
+
+        let mut projects: HashSet<String> = db_handler
+            .get_projects()
+            .await?
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        for project in &bundle.projects {
+            let name = match self.resolve("Project", &project.name, &projects)? {
+                Resolved::Skip => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                Resolved::Overwrite => {
+                    db_handler.rm_project(&project.name).await?;
+                    project.name.clone()
+                }
+                Resolved::Name(name) => name,
+            };
+            db_handler
+                .upsert_project(&project.to_project(&name)?)
+                .await?;
+            // a new project has no action yet
+            self.import_actions(
+                &project.actions,
+                Some(&name),
+                &mut HashSet::new(),
+                db_handler,
+                &mut summary,
+            )
+            .await?;
+            projects.insert(name);
+            summary.projects += 1;
+        }
+
+        let mut actions: HashSet<String> = db_handler
+            .get_actions(None)
+            .await?
+            .into_iter()
+            .filter_map(|a| a.name)
+            .collect();
+        self.import_actions(
+            &bundle.actions,
+            None,
+            &mut actions,
+            db_handler,
+            &mut summary,
+        )
+        .await?;
+
+        let mut test_suites: HashSet<String> = db_handler
+            .get_test_suites()
+            .await?
+            .into_iter()
+            .map(|ts| ts.name)
+            .collect();
+        for test_suite in &bundle.test_suites {
+            let name = match self.resolve("Test suite", &test_suite.name, &test_suites)? {
+                Resolved::Skip => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                Resolved::Overwrite => {
+                    db_handler.rm_test_suite(&test_suite.name).await?;
+                    test_suite.name.clone()
+                }
+                Resolved::Name(name) => name,
+            };
+            db_handler
+                .upsert_test_suite(&TestSuite {
+                    id: None,
+                    name: name.clone(),
+                    created_at: None,
+                })
+                .await?;
+            for step in &test_suite.steps {
+                db_handler
+                    .upsert_test_suite_instance(&TestSuiteInstance {
+                        id: None,
+                        test_suite_name: name.clone(),
+                        run_action_args: expand_args(step.clone())?,
+                        created_at: None,
+                        updated_at: None,
+                    })
+                    .await?;
+            }
+            test_suites.insert(name);
+            summary.test_suites += 1;
+        }
+
+        if !bundle.context.is_empty() {
+            let mut context: HashMap<String, String> = db_handler
+                .get_conf()
+                .await
+                .map(|c| c.get_value())
+                .unwrap_or_default();
+            for (key, value) in &bundle.context {
+                let replace = match context.get(key) {
+                    None => true,
+                    Some(current) if current == value => false,
+                    Some(_) => match self.on_conflict {
+                        OnConflict::Ask => {
+                            ask(&format!("Context value {} already exists,", key.yellow()))?
+                                == OnConflict::Overwrite
+                        }
+                        on_conflict => on_conflict == OnConflict::Overwrite,
+                    },
+                };
+                if replace {
+                    context.insert(key.clone(), value.clone());
+                    summary.context += 1;
+                }
+            }
+            db_handler
+                .insert_conf(&Context {
+                    value: serde_json::to_string(&context)?,
+                })
+                .await?;
+        }
+
+        if let Some(history) = &bundle.history {
+            let known: HashSet<_> = db_handler
+                .get_all_history()
+                .await?
+                .into_iter()
+                .map(|h| (h.created_at, h.url, h.action_name))
+                .collect();
+            for call in history {
+                if known.contains(&(call.created_at, call.url.clone(), call.action_name.clone())) {
+                    continue;
+                }
+                db_handler
+                    .insert_history(&History {
+                        id: None,
+                        ..call.clone()
+                    })
+                    .await?;
+                summary.history += 1;
+            }
+        }
+        Ok(summary)
+    }
+
+    pub async fn import(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let bundle: Bundle = serde_json::from_str(&std::fs::read_to_string(&self.path)?)?;
+        let summary = self.import_bundle(&bundle, db_handler).await?;
+        println!(
+            "Imported {} projects, {} actions, {} test suites, {} context values, {} history calls",
+            summary.projects.to_string().green(),
+            summary.actions.to_string().green(),
+            summary.test_suites.to_string().green(),
+            summary.context.to_string().green(),
+            summary.history.to_string().green(),
+        );
+        if summary.skipped > 0 {
+            println!("{} kept as they were", summary.skipped.to_string().yellow());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::db::export::DbExportArgs;
+    use crate::commands::run::action::RunActionArgs;
+    use crate::db::dto::{Action, Project};
+    use crate::db::workspace::{Resolution, Workspace};
+
+    /// yaml files with the context in a database next to them, or only a database
+    async fn handler(root: &std::path::Path, files: bool) -> DBHandler {
+        let _ = std::fs::remove_dir_all(root);
+        let mut db_handler = DBHandler::default();
+        db_handler
+            .init_db(&Workspace {
+                resolution: Resolution::Override,
+                files: files.then(|| root.join("files")),
+                database: root.join("qapi.sqlite"),
+            })
+            .await
+            .unwrap();
+        db_handler
+    }
+
+    fn import_args(on_conflict: OnConflict, replace: bool) -> DbImportArgs {
+        DbImportArgs {
+            path: PathBuf::new(),
+            merge: !replace,
+            replace,
+            on_conflict,
+        }
+    }
+
+    async fn project_names(db_handler: &DBHandler) -> Vec<String> {
+        let mut names: Vec<String> = db_handler
+            .get_projects()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    async fn check_export_import_bundle(root: PathBuf, files: bool) {
+        let source = handler(&root.join("source"), files).await;
+        source
+            .upsert_project(&Project {
+                id: None,
+                name: "users".to_string(),
+                main_url: "https://api.example.com".to_string(),
+                conf: Some(r#"{"token": "abc"}"#.to_string()),
+                created_at: None,
+                updated_at: None,
+                source: None,
+            })
+            .await
+            .unwrap();
+        let args = RunActionArgs {
+            name: Some("get-user".to_string()),
+            url: Some("users/{id}".to_string()),
+            verb: Some("GET".to_string()),
+            ..Default::default()
+        };
+        source
+            .upsert_action(&Action {
+                name: Some("get-user".to_string()),
+                run_action_args: Some(serde_json::to_string(&args).unwrap()),
+                project_name: Some("users".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        source
+            .upsert_action(&Action {
+                name: Some("health".to_string()),
+                run_action_args: Some(serde_json::to_string(&args).unwrap()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        source
+            .upsert_test_suite(&TestSuite {
+                id: None,
+                name: "smoke".to_string(),
+                created_at: None,
+            })
+            .await
+            .unwrap();
+        source
+            .upsert_test_suite_instance(&TestSuiteInstance {
+                id: None,
+                test_suite_name: "smoke".to_string(),
+                run_action_args: serde_json::to_string(&args).unwrap(),
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+        source
+            .insert_conf(&Context {
+                value: r#"{"id": "1"}"#.to_string(),
+            })
+            .await
+            .unwrap();
+
+        let export = DbExportArgs {
+            path: PathBuf::new(),
+            project: vec![],
+            test_suite: vec![],
+            history: false,
+        };
+        let bundle = export.build_bundle(&source).await.unwrap();
+        assert_eq!(bundle.projects[0].actions[0].name, "get-user");
+        assert_eq!(bundle.test_suites[0].steps.len(), 1);
+        // the bundle round trips through json
+        let bundle: Bundle =
+            serde_json::from_str(&serde_json::to_string(&bundle).unwrap()).unwrap();
+
+        // importing twice renames the second copy, its action args too
+        let target = handler(&root.join("target"), files).await;
+        let rename = import_args(OnConflict::Rename, false);
+        rename.import_bundle(&bundle, &target).await.unwrap();
+        let summary = rename.import_bundle(&bundle, &target).await.unwrap();
+        assert_eq!((summary.projects, summary.test_suites), (1, 1));
+        assert_eq!(project_names(&target).await, vec!["users", "users-2"]);
+        let action = &target.get_actions(Some("users-2")).await.unwrap()[0];
+        let run_action_args = action.get_run_action_args().unwrap();
+        assert_eq!(run_action_args.url.as_deref(), Some("users/{id}"));
+        assert_eq!(run_action_args.name.as_deref(), Some("get-user"));
+        assert_eq!(
+            target
+                .get_test_suite_instance("smoke-2")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(target.get_conf().await.unwrap().get_value()["id"], "1");
+
+        // keeping skips everything already there
+        let summary = import_args(OnConflict::Keep, false)
+            .import_bundle(&bundle, &target)
+            .await
+            .unwrap();
+        assert_eq!((summary.projects, summary.skipped), (0, 3));
+
+        // replacing restores the bundle only
+        import_args(OnConflict::Ask, true)
+            .import_bundle(&bundle, &target)
+            .await
+            .unwrap();
+        assert_eq!(project_names(&target).await, vec!["users"]);
+        assert_eq!(target.get_test_suites().await.unwrap().len(), 1);
+
+        // overwriting replaces the actions without project instead of adding copies
+        import_args(OnConflict::Overwrite, false)
+            .import_bundle(&bundle, &target)
+            .await
+            .unwrap();
+        assert_eq!(project_names(&target).await, vec!["users"]);
+        assert_eq!(target.get_actions(None).await.unwrap().len(), 1);
+        assert_eq!(target.get_actions(Some("users")).await.unwrap().len(), 1);
+
+        let newer = Bundle {
+            version: BUNDLE_VERSION + 1,
+            ..Bundle::default()
+        };
+        assert!(rename.import_bundle(&newer, &target).await.is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_export_import_bundle() {
+        let root = std::env::temp_dir().join("apikrab_db_bundle_test");
+        check_export_import_bundle(root, true).await;
+    }
+
+    #[tokio::test]
+    async fn test_export_import_bundle_sqlite() {
+        let root = std::env::temp_dir().join("apikrab_db_bundle_sqlite_test");
+        check_export_import_bundle(root, false).await;
+    }
+}
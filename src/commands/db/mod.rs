@@ -0,0 +1,22 @@
+pub(crate) mod bundle;
+pub(crate) mod export;
+pub(crate) mod import;
+
+use clap::{Args, Subcommand};
+
+use crate::commands::db::export::DbExportArgs;
+use crate::commands::db::import::DbImportArgs;
+
+#[derive(Args)]
+pub struct Db {
+    #[command(subcommand)]
+    pub db_commands: DbCommands,
+}
+
+#[derive(Subcommand)]
+pub enum DbCommands {
+    /// Write projects, actions, test suites and context to a json bundle
+    Export(DbExportArgs),
+    /// Load a json bundle, merged with the current data by default
+    Import(DbImportArgs),
+}
@@ -0,0 +1,93 @@
+pub(crate) mod snippet;
+
+use std::collections::HashMap;
+
+use arboard::Clipboard;
+use clap::Args;
+use crossterm::style::Stylize;
+
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::domain::DomainAction;
+use snippet::{SnippetFormat, SnippetRequest};
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// action name
+    pub action_name: String,
+
+    /// tool or language of the snippet
+    #[arg(long = "as", value_enum, default_value_t)]
+    pub format: SnippetFormat,
+
+    /// keep {{vars}} instead of resolving them from the project conf and the context
+    #[arg(long)]
+    pub keep_vars: bool,
+
+    /// copy the snippet in the clipboard
+    #[arg(long)]
+    pub clipboard: bool,
+}
+
+/// Request of a stored action built as it would be run, {{vars}} missing from ctx are kept
+pub fn snippet_request(
+    action: &Action,
+    project: Option<&Project>,
+    ctx: &HashMap<String, String>,
+) -> anyhow::Result<SnippetRequest> {
+    let mut run_action_args = RunActionArgs {
+        name: action.name.clone(),
+        ..Default::default()
+    };
+    run_action_args.prepare()?;
+    let action_data = run_action_args.get_action_data();
+    let action_run_action_args = action.get_run_action_args()?;
+    let mut domain_action = action_data[0].to_domain_action(&action_run_action_args, project, ctx);
+    domain_action.run_action_args = Some(action_run_action_args);
+    SnippetRequest::from_domain_action(&domain_action)
+}
+
+/// Snippet running the action, {{vars}} being resolved if needed
+pub async fn export_action(
+    action_name: &str,
+    format: SnippetFormat,
+    resolve_vars: bool,
+    db_handler: &DBHandler,
+) -> anyhow::Result<String> {
+    let action = db_handler
+        .get_action(action_name)
+        .await
+        .map_err(|_| anyhow::anyhow!("Action {} not found", action_name))?;
+    let project = DomainAction::project_from_db(action_name, db_handler).await;
+    let mut ctx = HashMap::new();
+    if resolve_vars {
+        ctx = db_handler
+            .get_conf()
+            .await
+            .map(|ctx| ctx.get_value())
+            .unwrap_or_default();
+        ctx.extend(
+            project
+                .as_ref()
+                .and_then(|p| p.get_project_conf().ok())
+                .unwrap_or_default(),
+        );
+    }
+    Ok(snippet_request(&action, project.as_ref(), &ctx)?.render(format))
+}
+
+impl ExportArgs {
+    pub async fn export(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let snippet =
+            export_action(&self.action_name, self.format, !self.keep_vars, db_handler).await?;
+        println!("{}", snippet);
+        if self.clipboard {
+            match Clipboard::new().and_then(|mut c| c.set_text(snippet)) {
+                Ok(_) => eprintln!("{}", "Snippet copied to the clipboard".green()),
+                Err(e) => eprintln!("{}", format!("Error copying snippet: {}", e).red()),
+            }
+        }
+        Ok(())
+    }
+}
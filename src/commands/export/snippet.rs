@@ -0,0 +1,532 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use itertools::Itertools;
+
+use crate::domain::DomainAction;
+use crate::http::body_as_form_fields;
+
+const CONTENT_TYPE: &str = "Content-Type";
+/// boundary of multipart bodies in .http files
+const MULTIPART_BOUNDARY: &str = "WebAppBoundary";
+
+/// Language or tool of an exported action
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SnippetFormat {
+    #[default]
+    Curl,
+    Httpie,
+    PythonRequests,
+    JsFetch,
+    RustReqwest,
+    /// JetBrains / VS Code .http request
+    Http,
+}
+
+/// Body of an exported request
+#[derive(Debug, PartialEq)]
+pub enum SnippetBody {
+    None,
+    Raw(String),
+    /// url encoded fields
+    Form(Vec<(String, String)>),
+    /// multipart fields, @path values are file uploads
+    Multipart(Vec<(String, String)>),
+}
+
+/// Request of an action, independent of the tool it is exported to
+#[derive(Debug)]
+pub struct SnippetRequest {
+    pub verb: String,
+    pub url: String,
+    pub query_params: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: SnippetBody,
+    pub insecure: bool,
+}
+
+/// single quoted shell word
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// double quoted python or javascript string
+fn string_literal(s: &str) -> String {
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+/// url encoded pairs, {{vars}} are kept readable
+fn url_encode(pairs: &[(String, String)]) -> String {
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+        .replace("%7B%7B", "{{")
+        .replace("%7D%7D", "}}")
+}
+
+/// @path value of a multipart field
+fn file_path(value: &str) -> Option<&str> {
+    value.strip_prefix('@')
+}
+
+impl SnippetRequest {
+    pub fn from_domain_action(action: &DomainAction) -> anyhow::Result<Self> {
+        if action.is_websocket() || action.is_grpc() {
+            anyhow::bail!("Only http actions can be exported");
+        }
+        let url = action
+            .urls
+            .iter()
+            .sorted()
+            .next()
+            .ok_or(anyhow::anyhow!("No url for action {}", action.name))?
+            .clone();
+        let query_params = action
+            .query_params
+            .first()
+            .cloned()
+            .flatten()
+            .map(|q| q.into_iter().sorted().collect_vec())
+            .unwrap_or_default();
+        let mut headers = action
+            .headers
+            .clone()
+            .map(|h| h.into_iter().sorted().collect_vec())
+            .unwrap_or_default();
+        let fields =
+            |body: &str| body_as_form_fields(body).map(|f| f.into_iter().sorted().collect_vec());
+        let body = match &action.body {
+            (None, _, _) => SnippetBody::None,
+            (Some(body), true, _) => SnippetBody::Form(fields(body)?),
+            (Some(body), _, true) => SnippetBody::Multipart(fields(body)?),
+            (Some(body), false, false) => {
+                let has_content_type = headers
+                    .iter()
+                    .any(|(k, _)| k.eq_ignore_ascii_case(CONTENT_TYPE));
+                // tools would send json bodies as text or form data otherwise
+                if !has_content_type && serde_json::from_str::<serde_json::Value>(body).is_ok() {
+                    headers.push((CONTENT_TYPE.to_string(), "application/json".to_string()));
+                }
+                SnippetBody::Raw(body.clone())
+            }
+        };
+        Ok(SnippetRequest {
+            verb: action.verb.clone(),
+            url,
+            query_params,
+            headers,
+            body,
+            insecure: action
+                .run_action_args
+                .as_ref()
+                .is_some_and(|args| args.insecure),
+        })
+    }
+
+    /// url with its encoded query string, {{vars}} are kept readable
+    fn full_url(&self) -> String {
+        if self.query_params.is_empty() {
+            return self.url.clone();
+        }
+        format!("{}?{}", self.url, url_encode(&self.query_params))
+    }
+
+    pub fn render(&self, format: SnippetFormat) -> String {
+        match format {
+            SnippetFormat::Curl => self.curl(),
+            SnippetFormat::Httpie => self.httpie(),
+            SnippetFormat::PythonRequests => self.python_requests(),
+            SnippetFormat::JsFetch => self.js_fetch(),
+            SnippetFormat::RustReqwest => self.rust_reqwest(),
+            SnippetFormat::Http => self.http(),
+        }
+    }
+
+    fn http(&self) -> String {
+        let mut lines = vec![format!("{} {}", self.verb, self.full_url())];
+        lines.extend(self.headers.iter().map(|(k, v)| format!("{}: {}", k, v)));
+        let has_content_type = self
+            .headers
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case(CONTENT_TYPE));
+        let body = match &self.body {
+            SnippetBody::None => None,
+            SnippetBody::Raw(body) => Some(body.clone()),
+            SnippetBody::Form(fields) => {
+                if !has_content_type {
+                    lines.push(format!(
+                        "{}: application/x-www-form-urlencoded",
+                        CONTENT_TYPE
+                    ));
+                }
+                Some(url_encode(fields))
+            }
+            SnippetBody::Multipart(fields) => {
+                lines.push(format!(
+                    "{}: multipart/form-data; boundary={}",
+                    CONTENT_TYPE, MULTIPART_BOUNDARY
+                ));
+                let parts = fields.iter().map(|(k, v)| match file_path(v) {
+                    Some(path) => format!(
+                        "--{}\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\n\n< {}",
+                        MULTIPART_BOUNDARY,
+                        k,
+                        Path::new(path)
+                            .file_name()
+                            .map(|f| f.to_string_lossy())
+                            .unwrap_or_default(),
+                        path
+                    ),
+                    None => format!(
+                        "--{}\nContent-Disposition: form-data; name=\"{}\"\n\n{}",
+                        MULTIPART_BOUNDARY, k, v
+                    ),
+                });
+                Some(format!(
+                    "{}\n--{}--",
+                    parts.collect_vec().join("\n"),
+                    MULTIPART_BOUNDARY
+                ))
+            }
+        };
+        if let Some(body) = body {
+            lines.push(String::new());
+            lines.push(body);
+        }
+        lines.join("\n")
+    }
+
+    fn curl(&self) -> String {
+        let mut first_line = vec!["curl".to_string()];
+        if self.verb != "GET" {
+            first_line.push(format!("-X {}", self.verb));
+        }
+        if self.insecure {
+            first_line.push("-k".to_string());
+        }
+        first_line.push(shell_quote(&self.full_url()));
+        let mut lines = vec![first_line.join(" ")];
+        lines.extend(
+            self.headers
+                .iter()
+                .map(|(k, v)| format!("-H {}", shell_quote(&format!("{}: {}", k, v)))),
+        );
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Raw(body) => lines.push(format!("--data-raw {}", shell_quote(body))),
+            SnippetBody::Form(fields) => lines.extend(fields.iter().map(|(k, v)| {
+                format!("--data-urlencode {}", shell_quote(&format!("{}={}", k, v)))
+            })),
+            SnippetBody::Multipart(fields) => lines.extend(
+                fields
+                    .iter()
+                    .map(|(k, v)| format!("-F {}", shell_quote(&format!("{}={}", k, v)))),
+            ),
+        }
+        lines.join(" \\\n  ")
+    }
+
+    fn httpie(&self) -> String {
+        let mut first_line = vec!["http".to_string()];
+        match &self.body {
+            SnippetBody::Form(_) => first_line.push("--form".to_string()),
+            SnippetBody::Multipart(_) => first_line.push("--multipart".to_string()),
+            _ => {}
+        }
+        if self.insecure {
+            first_line.push("--verify=no".to_string());
+        }
+        first_line.push(self.verb.clone());
+        first_line.push(shell_quote(&self.url));
+        let mut lines = vec![first_line.join(" ")];
+        lines.extend(
+            self.query_params
+                .iter()
+                .map(|(k, v)| shell_quote(&format!("{}=={}", k, v))),
+        );
+        lines.extend(
+            self.headers
+                .iter()
+                .map(|(k, v)| shell_quote(&format!("{}:{}", k, v))),
+        );
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Raw(body) => lines.push(format!("--raw {}", shell_quote(body))),
+            SnippetBody::Form(fields) | SnippetBody::Multipart(fields) => {
+                lines.extend(fields.iter().map(|(k, v)| match file_path(v) {
+                    Some(path) => shell_quote(&format!("{}@{}", k, path)),
+                    None => shell_quote(&format!("{}={}", k, v)),
+                }))
+            }
+        }
+        lines.join(" \\\n  ")
+    }
+
+    fn python_requests(&self) -> String {
+        let dict = |pairs: &[(String, String)]| {
+            format!(
+                "{{{}}}",
+                pairs
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", string_literal(k), string_literal(v)))
+                    .join(", ")
+            )
+        };
+        let mut args = vec![string_literal(&self.url)];
+        if !self.query_params.is_empty() {
+            args.push(format!("params={}", dict(&self.query_params)));
+        }
+        if !self.headers.is_empty() {
+            args.push(format!("headers={}", dict(&self.headers)));
+        }
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Raw(body) => args.push(format!("data={}", string_literal(body))),
+            SnippetBody::Form(fields) => args.push(format!("data={}", dict(fields))),
+            // text fields are sent as files without name to force a multipart body
+            SnippetBody::Multipart(fields) => args.push(format!(
+                "files={{{}}}",
+                fields
+                    .iter()
+                    .map(|(k, v)| match file_path(v) {
+                        Some(path) => format!(
+                            "{}: open({}, \"rb\")",
+                            string_literal(k),
+                            string_literal(path)
+                        ),
+                        None => format!("{}: (None, {})", string_literal(k), string_literal(v)),
+                    })
+                    .join(", ")
+            )),
+        }
+        if self.insecure {
+            args.push("verify=False".to_string());
+        }
+        format!(
+            "import requests\n\nresponse = requests.request(\n    {},\n    {},\n)\nprint(response.status_code, response.text)",
+            string_literal(&self.verb),
+            args.join(",\n    ")
+        )
+    }
+
+    fn js_fetch(&self) -> String {
+        let mut imports = vec![];
+        let mut preamble = vec![];
+        let mut options = vec![format!("method: {}", string_literal(&self.verb))];
+        if !self.headers.is_empty() {
+            options.push(format!(
+                "headers: {{\n{}\n  }}",
+                self.headers
+                    .iter()
+                    .map(|(k, v)| format!("    {}: {},", string_literal(k), string_literal(v)))
+                    .join("\n")
+            ));
+        }
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Raw(body) => options.push(format!("body: {}", string_literal(body))),
+            SnippetBody::Form(fields) => options.push(format!(
+                "body: new URLSearchParams({{\n{}\n  }})",
+                fields
+                    .iter()
+                    .map(|(k, v)| format!("    {}: {},", string_literal(k), string_literal(v)))
+                    .join("\n")
+            )),
+            SnippetBody::Multipart(fields) => {
+                preamble.push("const form = new FormData();".to_string());
+                for (k, v) in fields {
+                    match file_path(v) {
+                        Some(path) => {
+                            imports.push("import fs from \"node:fs\";\n".to_string());
+                            preamble.push(format!(
+                                "form.append({}, await fs.openAsBlob({}), {});",
+                                string_literal(k),
+                                string_literal(path),
+                                string_literal(path)
+                            ));
+                        }
+                        None => preamble.push(format!(
+                            "form.append({}, {});",
+                            string_literal(k),
+                            string_literal(v)
+                        )),
+                    }
+                }
+                options.push("body: form".to_string());
+            }
+        }
+        if self.insecure {
+            preamble.push(
+                "// fetch always checks certificates, run node with NODE_TLS_REJECT_UNAUTHORIZED=0"
+                    .to_string(),
+            );
+        }
+        let preamble = preamble.iter().map(|l| format!("{}\n", l)).join("");
+        format!(
+            "{}{}const response = await fetch({}, {{\n  {},\n}});\nconsole.log(response.status, await response.text());",
+            imports.into_iter().unique().join(""),
+            preamble,
+            string_literal(&self.full_url()),
+            options.join(",\n  ")
+        )
+    }
+
+    fn rust_reqwest(&self) -> String {
+        let client = if self.insecure {
+            "reqwest::Client::builder()\n    .danger_accept_invalid_certs(true)\n    .build()?"
+        } else {
+            "reqwest::Client::new()"
+        };
+        let mut calls = vec![format!(
+            "request(reqwest::Method::{}, {:?})",
+            self.verb, self.url
+        )];
+        if !self.query_params.is_empty() {
+            calls.push(format!(
+                "query(&[{}])",
+                self.query_params
+                    .iter()
+                    .map(|(k, v)| format!("({:?}, {:?})", k, v))
+                    .join(", ")
+            ));
+        }
+        calls.extend(
+            self.headers
+                .iter()
+                .map(|(k, v)| format!("header({:?}, {:?})", k, v)),
+        );
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Raw(body) => calls.push(format!("body({:?})", body)),
+            SnippetBody::Form(fields) => calls.push(format!(
+                "form(&[{}])",
+                fields
+                    .iter()
+                    .map(|(k, v)| format!("({:?}, {:?})", k, v))
+                    .join(", ")
+            )),
+            SnippetBody::Multipart(fields) => {
+                let parts = fields
+                    .iter()
+                    .map(|(k, v)| match file_path(v) {
+                        Some(path) => format!(
+                            "\n            .part({:?}, reqwest::multipart::Part::bytes(std::fs::read({:?})?).file_name({:?}))",
+                            k, path, path
+                        ),
+                        None => format!("\n            .text({:?}, {:?})", k, v),
+                    })
+                    .join("");
+                calls.push(format!(
+                    "multipart(\n        reqwest::multipart::Form::new(){},\n    )",
+                    parts
+                ));
+            }
+        }
+        calls.push("send()".to_string());
+        format!(
+            "let response = {}\n    .{}\n    .await?;\nprintln!(\"{{}} {{}}\", response.status(), response.text().await?);",
+            client,
+            calls.join("\n    .")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: SnippetBody) -> SnippetRequest {
+        SnippetRequest {
+            verb: "POST".to_string(),
+            url: "https://api.example.com/users".to_string(),
+            query_params: vec![("dry_run".to_string(), "{{dry run}}".to_string())],
+            headers: vec![("Authorization".to_string(), "Bearer {{token}}".to_string())],
+            body,
+            insecure: false,
+        }
+    }
+
+    #[test]
+    fn test_render_raw_body() {
+        let request = request(SnippetBody::Raw(r#"{"name":"Marco's"}"#.to_string()));
+        assert_eq!(
+            request.render(SnippetFormat::Curl),
+            r#"curl -X POST 'https://api.example.com/users?dry_run={{dry+run}}' \
+  -H 'Authorization: Bearer {{token}}' \
+  --data-raw '{"name":"Marco'\''s"}'"#
+        );
+        assert_eq!(
+            request.render(SnippetFormat::Httpie),
+            r#"http POST 'https://api.example.com/users' \
+  'dry_run=={{dry run}}' \
+  'Authorization:Bearer {{token}}' \
+  --raw '{"name":"Marco'\''s"}'"#
+        );
+        assert_eq!(
+            request.render(SnippetFormat::PythonRequests),
+            r#"import requests
+
+response = requests.request(
+    "POST",
+    "https://api.example.com/users",
+    params={"dry_run": "{{dry run}}"},
+    headers={"Authorization": "Bearer {{token}}"},
+    data="{\"name\":\"Marco's\"}",
+)
+print(response.status_code, response.text)"#
+        );
+        assert_eq!(
+            request.render(SnippetFormat::RustReqwest),
+            r#"let response = reqwest::Client::new()
+    .request(reqwest::Method::POST, "https://api.example.com/users")
+    .query(&[("dry_run", "{{dry run}}")])
+    .header("Authorization", "Bearer {{token}}")
+    .body("{\"name\":\"Marco's\"}")
+    .send()
+    .await?;
+println!("{} {}", response.status(), response.text().await?);"#
+        );
+    }
+
+    #[test]
+    fn test_render_multipart_body() {
+        let request = request(SnippetBody::Multipart(vec![
+            ("avatar".to_string(), "@me.png".to_string()),
+            ("name".to_string(), "Marco".to_string()),
+        ]));
+        assert_eq!(
+            request.render(SnippetFormat::JsFetch),
+            r#"import fs from "node:fs";
+const form = new FormData();
+form.append("avatar", await fs.openAsBlob("me.png"), "me.png");
+form.append("name", "Marco");
+const response = await fetch("https://api.example.com/users?dry_run={{dry+run}}", {
+  method: "POST",
+  headers: {
+    "Authorization": "Bearer {{token}}",
+  },
+  body: form,
+});
+console.log(response.status, await response.text());"#
+        );
+        assert!(request
+            .render(SnippetFormat::PythonRequests)
+            .contains(r#"files={"avatar": open("me.png", "rb"), "name": (None, "Marco")},"#));
+        assert_eq!(
+            request.render(SnippetFormat::Http),
+            r#"POST https://api.example.com/users?dry_run={{dry+run}}
+Authorization: Bearer {{token}}
+Content-Type: multipart/form-data; boundary=WebAppBoundary
+
+--WebAppBoundary
+Content-Disposition: form-data; name="avatar"; filename="me.png"
+
+< me.png
+--WebAppBoundary
+Content-Disposition: form-data; name="name"
+
+Marco
+--WebAppBoundary--"#
+        );
+    }
+}
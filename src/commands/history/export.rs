@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use clap::Args;
+use crossterm::style::Stylize;
+use reqwest::Url;
+
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::History;
+use crate::har::{
+    Content, Entry, Har, NameValue, PostData, Request, Response, Timings, HTTP_VERSION,
+};
+
+#[derive(Args)]
+pub struct HistoryExportArgs {
+    /// export as a HAR 1.2 file
+    #[arg(long, required = true)]
+    pub har: bool,
+
+    /// number of last calls to export
+    #[arg(short, long, default_value_t = 20)]
+    pub last: u16,
+
+    /// only export the calls of this action
+    #[arg(short, long)]
+    pub action: Option<String>,
+
+    /// output file, stdout if missing
+    #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+}
+
+fn mime_type(text: &str) -> &'static str {
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(_) => "application/json",
+        Err(_) => "text/plain",
+    }
+}
+
+/// HAR entry of a call, response headers are not kept in history
+fn to_entry(history: &History) -> Entry {
+    let headers = history
+        .headers
+        .as_deref()
+        .and_then(|h| serde_json::from_str::<Option<HashMap<String, String>>>(h).ok())
+        .flatten()
+        .unwrap_or_default();
+    let content_type = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .map(|(_, v)| v.clone());
+    let query_string = Url::parse(&history.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| NameValue {
+                    name: name.into_owned(),
+                    value: value.into_owned(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let body = history.body.clone().unwrap_or_default();
+    let response = history.response.clone().unwrap_or_default();
+    let time = (history.duration * 1000.0) as f64;
+    Entry {
+        started_date_time: history
+            .created_at
+            .map(|d| d.and_utc().to_rfc3339())
+            .unwrap_or_default(),
+        time,
+        request: Request {
+            // calls saved before the verb was recorded
+            method: history.verb.clone().unwrap_or("GET".to_string()),
+            url: history.url.clone(),
+            http_version: HTTP_VERSION.to_string(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| NameValue {
+                    name: k.clone(),
+                    value: v.clone(),
+                })
+                .collect(),
+            query_string,
+            post_data: (!body.is_empty()).then(|| PostData {
+                mime_type: content_type.unwrap_or(mime_type(&body).to_string()),
+                text: body.clone(),
+            }),
+            headers_size: -1,
+            body_size: body.len() as i64,
+            ..Default::default()
+        },
+        response: Response {
+            status: history.status_code,
+            http_version: HTTP_VERSION.to_string(),
+            content: Content {
+                size: response.len() as i64,
+                mime_type: mime_type(&response).to_string(),
+                text: Some(response.clone()),
+                encoding: None,
+            },
+            headers_size: -1,
+            body_size: response.len() as i64,
+            ..Default::default()
+        },
+        cache: serde_json::json!({}),
+        timings: Timings {
+            send: 0.0,
+            wait: time,
+            receive: 0.0,
+        },
+    }
+}
+
+impl HistoryExportArgs {
+    pub async fn export(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let history = db_handler.get_history(Some(self.last)).await?;
+        // oldest call first, as recorded by browsers
+        let entries = history
+            .iter()
+            .rev()
+            .filter(|h| self.action.as_ref().is_none_or(|a| a == &h.action_name))
+            .map(to_entry)
+            .collect::<Vec<_>>();
+        let count = entries.len();
+        let har = serde_json::to_string_pretty(&Har::new(entries))?;
+        match self.output.as_ref() {
+            Some(path) => {
+                std::fs::write(path, har)?;
+                println!(
+                    "{} calls exported to {}",
+                    count,
+                    path.display().to_string().green()
+                );
+            }
+            None => println!("{}", har),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_to_entry() {
+        let history = History {
+            id: None,
+            action_name: "create-user".to_string(),
+            url: "https://api.example.com/users?notify=true".to_string(),
+            body: Some(r#"{"name": "Marco"}"#.to_string()),
+            headers: Some(r#"{"Authorization": "Bearer xyz"}"#.to_string()),
+            response: Some("created".to_string()),
+            status_code: 201,
+            duration: 0.25,
+            created_at: None,
+            verb: Some("POST".to_string()),
+        };
+        let entry = to_entry(&history);
+        assert_eq!(entry.time, 250.0);
+        assert_eq!(entry.request.method, "POST");
+        assert_eq!(entry.request.query_string[0].name, "notify");
+        assert_eq!(entry.request.headers[0].value, "Bearer xyz");
+        let post_data = entry.request.post_data.unwrap();
+        assert_eq!(post_data.mime_type, "application/json");
+        assert_eq!(post_data.text, r#"{"name": "Marco"}"#);
+        assert_eq!(entry.response.status, 201);
+        assert_eq!(entry.response.content.mime_type, "text/plain");
+    }
+}
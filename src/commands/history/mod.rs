@@ -1,8 +1,10 @@
+pub(crate) mod export;
 pub(crate) mod list;
 pub(crate) mod list_ui;
 
 use clap::{Args, Subcommand};
 
+use crate::commands::history::export::HistoryExportArgs;
 use crate::commands::history::list::HistoryArgs;
 
 #[derive(Args)]
@@ -17,4 +19,6 @@ pub enum HistoryCommands {
     List(HistoryArgs),
     /// Run history ui
     Ui,
+    /// Export the last calls, e.g. as a HAR file
+    Export(HistoryExportArgs),
 }
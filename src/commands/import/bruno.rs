@@ -0,0 +1,453 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use async_trait::async_trait;
+use base64::Engine;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::utils::SEP;
+
+use super::import::{relative_url, save, slug, Import};
+
+const BRU_EXTENSION: &str = "bru";
+const ENVIRONMENTS_DIR: &str = "environments";
+/// collection and folder settings, not requests
+const SETTINGS_FILES: [&str; 2] = ["collection.bru", "folder.bru"];
+const VERBS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+/// Blocks of a .bru file, e.g. `headers { ... }`, their content being unindented
+struct BruFile<'a> {
+    blocks: Vec<(&'a str, String)>,
+}
+
+impl<'a> BruFile<'a> {
+    fn parse(content: &'a str) -> Self {
+        let mut blocks = vec![];
+        let mut current: Option<(&str, Vec<&str>)> = None;
+        for line in content.lines() {
+            match current.as_mut() {
+                None => {
+                    let line = line.trim_end();
+                    if let Some(name) = line.strip_suffix('{').or(line.strip_suffix('[')) {
+                        current = Some((name.trim(), vec![]));
+                    }
+                }
+                Some((name, lines)) => {
+                    if line.trim_end() == "}" || line.trim_end() == "]" {
+                        blocks.push((*name, lines.join("\n")));
+                        current = None;
+                    } else {
+                        lines.push(line.strip_prefix("  ").unwrap_or(line));
+                    }
+                }
+            }
+        }
+        BruFile { blocks }
+    }
+
+    fn block(&self, name: &str) -> Option<&str> {
+        self.blocks
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, content)| content.as_str())
+    }
+
+    /// enabled `key: value` entries of a block, disabled ones start with ~
+    fn dict(&self, name: &str) -> Vec<(&str, &str)> {
+        self.block(name)
+            .map(|content| {
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('~'))
+                    .filter_map(|l| l.split_once(':'))
+                    .map(|(k, v)| (k.trim(), v.trim()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn value(&self, block: &str, key: &str) -> Option<&str> {
+        self.dict(block)
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+/// .bru files of a collection folder, by path relative to the folder
+fn read_bru_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_bru_files(root, &path, files)?;
+        } else if path.extension().is_some_and(|e| e == BRU_EXTENSION) {
+            let relative_path = path
+                .strip_prefix(root)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.insert(relative_path, fs::read_to_string(&path)?);
+        }
+    }
+    Ok(())
+}
+
+/// Import a Bruno collection folder, sub folders being action name prefixes
+pub struct BrunoImporter<'a> {
+    pub db_handler: &'a DBHandler,
+    /// environment added to the project conf
+    pub env: Option<String>,
+}
+
+impl<'a> BrunoImporter<'a> {
+    fn variables(
+        &self,
+        files: &BTreeMap<String, String>,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let Some(env) = self.env.as_deref() else {
+            return Ok(HashMap::new());
+        };
+        let path = format!("{}/{}.{}", ENVIRONMENTS_DIR, env, BRU_EXTENSION);
+        let Some(content) = files.get(&path) else {
+            anyhow::bail!(
+                "Environment {} not found, available: {}",
+                env,
+                files
+                    .keys()
+                    .filter_map(|p| p.strip_prefix(&format!("{}/", ENVIRONMENTS_DIR)))
+                    .map(|p| p.trim_end_matches(&format!(".{}", BRU_EXTENSION)))
+                    .join(", ")
+            );
+        };
+        // secret values are not exported by bruno
+        Ok(BruFile::parse(content)
+            .dict("vars")
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect())
+    }
+
+    fn authorization(bru: &BruFile, mode: &str, name: &str) -> Option<String> {
+        match mode {
+            "none" | "inherit" => None,
+            "bearer" => Some(format!(
+                "Authorization:Bearer {}",
+                bru.value("auth:bearer", "token").unwrap_or_default()
+            )),
+            // credentials are encoded at import, vars can not be resolved later
+            "basic" => {
+                let credentials = format!(
+                    "{}:{}",
+                    bru.value("auth:basic", "username").unwrap_or_default(),
+                    bru.value("auth:basic", "password").unwrap_or_default()
+                );
+                if credentials.contains("{{") {
+                    eprintln!(
+                        "{}",
+                        format!("basic authentication of {} is not imported", name).yellow()
+                    );
+                    return None;
+                }
+                Some(format!(
+                    "Authorization:Basic {}",
+                    base64::engine::general_purpose::STANDARD.encode(credentials)
+                ))
+            }
+            mode => {
+                eprintln!(
+                    "{}",
+                    format!("{} authentication of {} is not imported", mode, name).yellow()
+                );
+                None
+            }
+        }
+    }
+
+    /// action of a request file, None for other files
+    fn to_action(
+        path: &str,
+        content: &str,
+        variables: &HashMap<String, String>,
+        project: &Project,
+    ) -> Option<Action> {
+        let bru = BruFile::parse(content);
+        let verb = VERBS.iter().find(|v| bru.block(v).is_some())?;
+        let mut folders = path.split('/').collect_vec();
+        let file_name = folders.pop().unwrap_or_default();
+        let request_name = bru
+            .value("meta", "name")
+            .unwrap_or(file_name.trim_end_matches(".bru"));
+        folders.push(request_name);
+        let name = slug(&folders.join(" "));
+
+        let url = bru.value(verb, "url").unwrap_or_default();
+        let path = url.split('?').next().unwrap_or_default();
+        // :id path params as apikrab {id} ones
+        let path = path
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => format!("{{{}}}", param),
+                None => segment.to_string(),
+            })
+            .join("/");
+        let path_params = bru
+            .dict("params:path")
+            .iter()
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect_vec();
+        let query_params = bru
+            .dict("params:query")
+            .iter()
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect_vec();
+        let mut run_action_args = RunActionArgs {
+            name: Some(name.clone()),
+            url: Some(relative_url(&path, variables, &project.main_url)),
+            verb: Some(verb.to_uppercase()),
+            path_params: (!path_params.is_empty()).then_some(path_params),
+            query_params: (!query_params.is_empty()).then_some(query_params),
+            ..Default::default()
+        };
+
+        let fields = |block: &str| {
+            bru.dict(block)
+                .iter()
+                .map(|(k, v)| match v.strip_prefix("@file(") {
+                    Some(file) => format!("{}:@{}", k, file.trim_end_matches(')')),
+                    None => format!("{}:{}", k, v),
+                })
+                .join(SEP)
+        };
+        let body_example = match bru.value(verb, "body").unwrap_or("none") {
+            "none" => None,
+            "json" => {
+                let body = bru
+                    .block("body:json")
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                run_action_args.body = Some(vec![body.clone()]);
+                Some(body)
+            }
+            "formUrlEncoded" => {
+                run_action_args.body = Some(vec![fields("body:form-urlencoded")]);
+                run_action_args.url_encoded = true;
+                None
+            }
+            "multipartForm" => {
+                run_action_args.body = Some(vec![fields("body:multipart-form")]);
+                run_action_args.form_data = true;
+                None
+            }
+            "graphql" => {
+                run_action_args.query = bru.block("body:graphql").map(|q| q.trim().to_string());
+                run_action_args.variables = bru
+                    .block("body:graphql:vars")
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty());
+                None
+            }
+            mode => {
+                eprintln!(
+                    "{}",
+                    format!("{} body of {} is only kept as example", mode, name).yellow()
+                );
+                bru.block(&format!("body:{}", mode))
+                    .map(|b| b.trim().to_string())
+            }
+        };
+
+        let mut headers = bru
+            .dict("headers")
+            .iter()
+            // the multipart boundary is generated when sending
+            .filter(|(k, _)| !run_action_args.form_data || !k.eq_ignore_ascii_case("content-type"))
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect_vec();
+        let auth = bru.value(verb, "auth").unwrap_or("none");
+        headers.extend(Self::authorization(&bru, auth, &name));
+        run_action_args.header = (!headers.is_empty()).then_some(headers);
+
+        Some(Action {
+            name: Some(name),
+            run_action_args: serde_json::to_string(&run_action_args).ok(),
+            body_example,
+            project_name: Some(project.name.clone()),
+            ..Default::default()
+        })
+    }
+
+    fn to_actions(
+        &self,
+        input: &str,
+        project: &Project,
+    ) -> anyhow::Result<(Vec<Action>, HashMap<String, String>)> {
+        let files: BTreeMap<String, String> = serde_json::from_str(input)?;
+        let variables = self.variables(&files)?;
+        let actions = files
+            .iter()
+            .filter(|(path, _)| !path.starts_with(&format!("{}/", ENVIRONMENTS_DIR)))
+            .filter(|(path, _)| !SETTINGS_FILES.iter().any(|f| path.ends_with(f)))
+            .filter_map(|(path, content)| Self::to_action(path, content, &variables, project))
+            .collect();
+        Ok((actions, variables))
+    }
+}
+
+#[async_trait]
+impl<'a> Import for BrunoImporter<'a> {
+    /// the .bru files of the folder as a json map
+    async fn load(&self, dir: &str) -> anyhow::Result<String> {
+        let mut files = BTreeMap::new();
+        read_bru_files(Path::new(dir), Path::new(dir), &mut files)
+            .map_err(|e| anyhow::anyhow!("Error loading bruno collection {}: {}", dir, e))?;
+        Ok(serde_json::to_string(&files)?)
+    }
+
+    async fn actions(&self, input: &str, project: &mut Project) -> anyhow::Result<Vec<Action>> {
+        let (actions, mut variables) = self.to_actions(input, project)?;
+        // conf given on the command line wins
+        variables.extend(project.get_project_conf()?);
+        if !variables.is_empty() {
+            project.conf = Some(serde_json::to_string(&variables)?);
+        }
+
+        Ok(actions)
+    }
+
+    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
+        let actions = self.actions(input, project).await?;
+        save(self.db_handler, project, &actions).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bruno_to_actions() {
+        let files = BTreeMap::from([
+            (
+                "bruno.json".to_string(),
+                r#"{"version": "1", "name": "Shop"}"#.to_string(),
+            ),
+            (
+                "environments/Local.bru".to_string(),
+                "vars {\n  baseUrl: http://localhost:8080/api\n}\nvars:secret [\n  token\n]\n"
+                    .to_string(),
+            ),
+            (
+                "users/Get user.bru".to_string(),
+                r#"meta {
+  name: Get user
+  type: http
+  seq: 1
+}
+
+get {
+  url: {{baseUrl}}/users/:id?expand=roles
+  body: none
+  auth: bearer
+}
+
+params:query {
+  expand: roles
+  ~debug: true
+}
+
+params:path {
+  id: 42
+}
+
+auth:bearer {
+  token: {{token}}
+}
+"#
+                .to_string(),
+            ),
+            (
+                "users/Create user.bru".to_string(),
+                r#"meta {
+  name: Create user
+}
+
+post {
+  url: {{baseUrl}}/users
+  body: json
+  auth: none
+}
+
+headers {
+  Content-Type: application/json
+}
+
+body:json {
+  {
+    "name": "Marco"
+  }
+}
+
+body:text {
+  ignored
+}
+"#
+                .to_string(),
+            ),
+        ]);
+        let project = Project {
+            id: None,
+            name: "shop".to_string(),
+            main_url: "http://localhost:8080/api".to_string(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let db_handler = DBHandler::default();
+        let importer = BrunoImporter {
+            db_handler: &db_handler,
+            env: Some("Local".to_string()),
+        };
+
+        let input = serde_json::to_string(&files).unwrap();
+        let (actions, variables) = importer.to_actions(&input, &project).unwrap();
+        assert_eq!(variables["baseUrl"], "http://localhost:8080/api");
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.name.clone().unwrap())
+                .collect_vec(),
+            vec!["users-create-user", "users-get-user"]
+        );
+        let create_user = actions[0].get_run_action_args().unwrap();
+        assert_eq!(
+            create_user.body,
+            Some(vec!["{\n  \"name\": \"Marco\"\n}".to_string()])
+        );
+        assert_eq!(
+            create_user.header,
+            Some(vec!["Content-Type:application/json".to_string()])
+        );
+        let get_user = actions[1].get_run_action_args().unwrap();
+        assert_eq!(get_user.url.as_deref(), Some("users/{id}"));
+        assert_eq!(get_user.path_params, Some(vec!["id:42".to_string()]));
+        assert_eq!(
+            get_user.query_params,
+            Some(vec!["expand:roles".to_string()])
+        );
+        assert_eq!(
+            get_user.header,
+            Some(vec!["Authorization:Bearer {{token}}".to_string()])
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use std::io::{self, Read};
+use std::str::FromStr;
+
+use arboard::Clipboard;
+use async_trait::async_trait;
+use base64::Engine;
+use clap::Args;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use reqwest::Url;
+
+use crate::commands::record::capture::Exchange;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::http::Verb;
+use crate::utils::SEP;
+
+use super::import::Import;
+
+/// curl options followed by a value, the others are flags
+const VALUE_OPTIONS: [&str; 44] = [
+    "-X",
+    "--request",
+    "-H",
+    "--header",
+    "-d",
+    "--data",
+    "--data-raw",
+    "--data-binary",
+    "--data-ascii",
+    "--data-urlencode",
+    "-F",
+    "--form",
+    "--form-string",
+    "-u",
+    "--user",
+    "-A",
+    "--user-agent",
+    "-b",
+    "--cookie",
+    "-e",
+    "--referer",
+    "-m",
+    "--max-time",
+    "--url",
+    "-o",
+    "--output",
+    "-x",
+    "--proxy",
+    "-w",
+    "--write-out",
+    "-c",
+    "--cookie-jar",
+    "-T",
+    "--upload-file",
+    "-r",
+    "--range",
+    "--connect-timeout",
+    "--retry",
+    "--max-redirs",
+    "--resolve",
+    "--cacert",
+    "--cert",
+    "--key",
+    "--limit-rate",
+];
+
+/// Import a curl command, e.g. copied from the browser devtools, as a project action
+#[derive(Args)]
+pub struct CurlImportArgs {
+    /// curl command, read from stdin if missing
+    pub command: Option<String>,
+
+    /// read the curl command from the clipboard
+    #[arg(long, conflicts_with = "command")]
+    pub clipboard: bool,
+
+    /// project of the action, created from the curl url if missing
+    #[arg(short, long)]
+    pub project: String,
+
+    /// action name, derived from the verb and path by default
+    #[arg(short, long)]
+    pub name: Option<String>,
+}
+
+impl CurlImportArgs {
+    pub async fn import(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let command = match (self.command.as_ref(), self.clipboard) {
+            (Some(command), _) => command.clone(),
+            (None, true) => Clipboard::new()?.get_text()?,
+            (None, false) => {
+                let mut command = String::new();
+                io::stdin().read_to_string(&mut command)?;
+                command
+            }
+        };
+        let mut project = db_handler
+            .get_project(&self.project)
+            .await
+            .unwrap_or(Project {
+                id: None,
+                name: self.project.clone(),
+                main_url: "".to_string(),
+                conf: None,
+                source: None,
+                created_at: None,
+                updated_at: None,
+            });
+        CurlImporter {
+            db_handler,
+            name: self.name.clone(),
+        }
+        .import(&command, &mut project)
+        .await
+    }
+}
+
+/// Request described by a curl command line
+#[derive(Debug, Default)]
+struct CurlCommand {
+    verb: Option<String>,
+    url: Option<String>,
+    headers: Vec<(String, String)>,
+    data: Vec<String>,
+    /// file sent as body with -d @file
+    data_file: Option<String>,
+    form: Vec<(String, String)>,
+    user: Option<String>,
+    insecure: bool,
+    get: bool,
+    max_time: Option<u64>,
+    /// cookie passed with -b, the browser ones sent as header are not kept
+    cookie: Option<String>,
+}
+
+/// curl options and their value, e.g. -XPOST as ("-X", Some("POST")),
+/// urls are returned as --url options
+fn options(words: Vec<String>) -> Vec<(String, Option<String>)> {
+    let mut words = words.into_iter();
+    let mut options = vec![];
+    while let Some(word) = words.next() {
+        if word.starts_with("--") {
+            let value = VALUE_OPTIONS
+                .contains(&word.as_str())
+                .then(|| words.next())
+                .flatten();
+            options.push((word, value));
+        } else if word.len() > 1 && word.starts_with('-') {
+            // short flags can be combined, e.g. -sSLk, the last one may take a value
+            for (i, c) in word.char_indices().skip(1) {
+                let name = format!("-{}", c);
+                if VALUE_OPTIONS.contains(&name.as_str()) {
+                    let rest = &word[i + c.len_utf8()..];
+                    let value = if rest.is_empty() {
+                        words.next()
+                    } else {
+                        Some(rest.to_string())
+                    };
+                    options.push((name, value));
+                    break;
+                }
+                options.push((name, None));
+            }
+        } else {
+            options.push(("--url".to_string(), Some(word)));
+        }
+    }
+    options
+}
+
+/// --data-urlencode value, only the content of name=content is encoded
+fn url_encode_data(data: &str) -> String {
+    let encode = |s: &str| url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+    match data.split_once('=') {
+        Some(("", content)) => encode(content),
+        Some((name, content)) => format!("{}={}", name, encode(content)),
+        None => encode(data),
+    }
+}
+
+impl FromStr for CurlCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(command: &str) -> anyhow::Result<Self> {
+        // line continuations of multiline commands
+        let command = command.replace("\\\r\n", " ").replace("\\\n", " ");
+        let mut words =
+            shlex::split(&command).ok_or(anyhow::anyhow!("Invalid quoting in curl command"))?;
+        if words.first().is_some_and(|w| w == "curl") {
+            words.remove(0);
+        }
+        let mut curl = CurlCommand::default();
+        for (option, arg) in options(words) {
+            let value = || {
+                arg.clone()
+                    .ok_or(anyhow::anyhow!("Missing value of {}", option))
+            };
+            match option.as_str() {
+                "-X" | "--request" => curl.verb = Some(value()?.to_uppercase()),
+                "-H" | "--header" => {
+                    if let Some((k, v)) = value()?.split_once(':') {
+                        curl.headers
+                            .push((k.trim().to_string(), v.trim().to_string()));
+                    }
+                }
+                "-d" | "--data" | "--data-binary" | "--data-ascii" => {
+                    let data = value()?;
+                    match data.strip_prefix('@') {
+                        Some(path) => curl.data_file = Some(path.to_string()),
+                        None => curl.data.push(data),
+                    }
+                }
+                "--data-raw" => curl.data.push(value()?),
+                "--data-urlencode" => curl.data.push(url_encode_data(&value()?)),
+                "-F" | "--form" | "--form-string" => {
+                    let field = value()?;
+                    let (k, v) = field.split_once('=').unwrap_or((&field, ""));
+                    // ;type= and ;filename= of uploaded files are not kept
+                    let v = match v.starts_with('@') {
+                        true => v.split(';').next().unwrap_or_default(),
+                        false => v,
+                    };
+                    curl.form.push((k.to_string(), v.to_string()));
+                }
+                "-u" | "--user" => curl.user = Some(value()?),
+                "-A" | "--user-agent" => curl.headers.push(("User-Agent".to_string(), value()?)),
+                "-e" | "--referer" => curl.headers.push(("Referer".to_string(), value()?)),
+                "-b" | "--cookie" => {
+                    // without = it is a cookie file
+                    let cookie = value()?;
+                    if cookie.contains('=') {
+                        curl.cookie = Some(cookie);
+                    }
+                }
+                "-k" | "--insecure" => curl.insecure = true,
+                "-G" | "--get" => curl.get = true,
+                "-m" | "--max-time" => curl.max_time = Some(value()?.parse::<f64>()?.ceil() as u64),
+                "--url" => curl.url = Some(value()?),
+                // output, verbosity, compression or transfer options
+                _ => {}
+            }
+        }
+        Ok(curl)
+    }
+}
+
+impl CurlCommand {
+    fn verb(&self) -> String {
+        let has_body = !self.data.is_empty() || self.data_file.is_some() || !self.form.is_empty();
+        match self.verb.as_ref() {
+            Some(verb) => verb.clone(),
+            None if has_body && !self.get => "POST".to_string(),
+            None => "GET".to_string(),
+        }
+    }
+
+    fn to_exchange(&self) -> anyhow::Result<Exchange> {
+        let url = self
+            .url
+            .as_ref()
+            .ok_or(anyhow::anyhow!("No url found in curl command"))?;
+        // curl defaults to http
+        let mut url = match url.contains("://") {
+            true => Url::parse(url)?,
+            false => Url::parse(&format!("http://{}", url))?,
+        };
+        let mut body = self.data.iter().join("&");
+        if self.get && !body.is_empty() {
+            let query = url.query().into_iter().chain([body.as_str()]).join("&");
+            url.set_query(Some(&query));
+            body.clear();
+        }
+
+        let has_header = |name: &str| {
+            self.headers
+                .iter()
+                .any(|(k, _)| k.eq_ignore_ascii_case(name))
+        };
+        let mut headers = self.headers.clone();
+        let is_json = serde_json::from_str::<serde_json::Value>(&body).is_ok();
+        if !body.is_empty() && !is_json && !has_header("content-type") {
+            // content type sent by curl with -d, json bodies usually miss their header
+            headers.push((
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded".to_string(),
+            ));
+        }
+        if let Some(user) = self.user.as_ref().filter(|_| !has_header("authorization")) {
+            let credentials = base64::engine::general_purpose::STANDARD.encode(user);
+            headers.push((
+                "Authorization".to_string(),
+                format!("Basic {}", credentials),
+            ));
+        }
+
+        let verb = self.verb();
+        if Verb::from_str(&verb).is_err() {
+            anyhow::bail!("Unsupported verb {}", verb);
+        }
+        Ok(Exchange {
+            verb,
+            url,
+            headers,
+            body,
+            status: 0,
+            response: "".to_string(),
+        })
+    }
+
+    fn to_action(&self, exchange: &Exchange, project: &Project, name: Option<&str>) -> Action {
+        let name = name
+            .map(|n| n.to_string())
+            .unwrap_or(exchange.action_name(project));
+        let mut run_action_args = exchange.run_action_args(project);
+        run_action_args.name = Some(name.clone());
+        run_action_args.insecure = self.insecure;
+        run_action_args.timeout = self.max_time;
+        // several cookies would be split as several headers
+        if let Some(cookie) = self.cookie.as_ref().filter(|c| !c.contains(SEP)) {
+            run_action_args
+                .header
+                .get_or_insert_with(Vec::new)
+                .push(format!("Cookie:{}", cookie));
+        }
+        if let Some(path) = self.data_file.as_ref() {
+            run_action_args.body = Some(vec![format!("@{}", path)]);
+        }
+        if !self.form.is_empty() {
+            run_action_args.body = Some(vec![self
+                .form
+                .iter()
+                .map(|(k, v)| format!("{}:{}", k, v))
+                .join(SEP)]);
+            run_action_args.form_data = true;
+            // the multipart boundary is generated when sending
+            if let Some(headers) = run_action_args.header.as_mut() {
+                headers.retain(|h| !h.to_lowercase().starts_with("content-type:"));
+            }
+            run_action_args.content_type = None;
+        }
+        Action {
+            name: Some(name),
+            run_action_args: serde_json::to_string(&run_action_args).ok(),
+            body_example: (!exchange.body.is_empty()).then(|| exchange.body.clone()),
+            project_name: Some(project.name.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Import a curl command as a project action
+pub struct CurlImporter<'a> {
+    pub db_handler: &'a DBHandler,
+    /// action name, derived from the verb and path if none
+    pub name: Option<String>,
+}
+
+#[async_trait]
+impl<'a> Import for CurlImporter<'a> {
+    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
+        let curl = CurlCommand::from_str(input)?;
+        let exchange = curl.to_exchange()?;
+        // new projects are rooted at the origin of the request
+        if project.main_url.is_empty() {
+            project.main_url = exchange.url.origin().ascii_serialization();
+        }
+        let action = curl.to_action(&exchange, project, self.name.as_deref());
+        let run_action_args = action.get_run_action_args()?;
+        if !exchange.body.is_empty() && run_action_args.body.is_none() {
+            eprintln!(
+                "{}",
+                "Body is neither json nor url encoded, only kept as example".yellow()
+            );
+        }
+        if curl.cookie.as_ref().is_some_and(|c| c.contains(SEP)) {
+            eprintln!(
+                "{}",
+                "Several cookies cannot be kept in one header, -b is ignored".yellow()
+            );
+        }
+
+        self.db_handler.upsert_project(project).await?;
+        self.db_handler.upsert_action(&action).await?;
+        println!("Action {} saved", action.name.unwrap_or_default().green());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> Project {
+        Project {
+            id: None,
+            name: "shop".to_string(),
+            main_url: "https://api.example.com".to_string(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_devtools_curl_to_action() {
+        let command = r#"curl 'https://api.example.com/orders/42?expand=items' \
+  -X 'PATCH' \
+  -H 'accept: application/json' \
+  -H 'content-type: application/json' \
+  -H 'cookie: session=abc' \
+  --data-raw '{"status":"paid"}' \
+  --compressed -sk"#;
+        let curl = CurlCommand::from_str(command).unwrap();
+        let exchange = curl.to_exchange().unwrap();
+        let action = curl.to_action(&exchange, &project(), None);
+        let run_action_args = action.get_run_action_args().unwrap();
+
+        assert_eq!(action.name.as_deref(), Some("patch-orders-42"));
+        assert_eq!(run_action_args.url.as_deref(), Some("orders/42"));
+        assert_eq!(run_action_args.verb.as_deref(), Some("PATCH"));
+        assert_eq!(
+            run_action_args.query_params,
+            Some(vec!["expand:items".to_string()])
+        );
+        assert_eq!(
+            run_action_args.header,
+            Some(vec![
+                "accept:application/json".to_string(),
+                "content-type:application/json".to_string()
+            ])
+        );
+        assert_eq!(
+            run_action_args.body,
+            Some(vec![r#"{"status":"paid"}"#.to_string()])
+        );
+        assert!(run_action_args.insecure);
+    }
+
+    #[test]
+    fn test_form_and_basic_auth() {
+        let curl = CurlCommand::from_str(
+            "curl -u marco:secret -XPOST https://api.example.com/login \
+             --data-urlencode 'name=Marco Polo' -d age=18 -m 2.5",
+        )
+        .unwrap();
+        let exchange = curl.to_exchange().unwrap();
+        let run_action_args = curl
+            .to_action(&exchange, &project(), Some("login"))
+            .get_run_action_args()
+            .unwrap();
+        assert_eq!(
+            run_action_args.body,
+            Some(vec!["name:Marco Polo;age:18".to_string()])
+        );
+        assert!(run_action_args.url_encoded);
+        assert!(run_action_args
+            .header
+            .unwrap()
+            .contains(&"Authorization:Basic bWFyY286c2VjcmV0".to_string()));
+        assert_eq!(run_action_args.timeout, Some(3));
+
+        let curl = CurlCommand::from_str(
+            "curl https://api.example.com/avatars -F 'file=@me.png;type=image/png' -F user=42",
+        )
+        .unwrap();
+        let exchange = curl.to_exchange().unwrap();
+        let run_action_args = curl
+            .to_action(&exchange, &project(), None)
+            .get_run_action_args()
+            .unwrap();
+        assert_eq!(run_action_args.verb.as_deref(), Some("POST"));
+        assert_eq!(
+            run_action_args.body,
+            Some(vec!["file:@me.png;user:42".to_string()])
+        );
+        assert!(run_action_args.form_data);
+    }
+
+    #[test]
+    fn test_explicit_cookie() {
+        let curl = CurlCommand::from_str(
+            "curl -b session=abc -H 'cookie: tracking=1' https://api.example.com/me",
+        )
+        .unwrap();
+        let exchange = curl.to_exchange().unwrap();
+        let run_action_args = curl
+            .to_action(&exchange, &project(), None)
+            .get_run_action_args()
+            .unwrap();
+        assert_eq!(
+            run_action_args.header,
+            Some(vec!["Cookie:session=abc".to_string()])
+        );
+
+        let curl =
+            CurlCommand::from_str("curl -b 'session=abc; theme=dark' https://api.example.com/me")
+                .unwrap();
+        let exchange = curl.to_exchange().unwrap();
+        let run_action_args = curl
+            .to_action(&exchange, &project(), None)
+            .get_run_action_args()
+            .unwrap();
+        assert_eq!(run_action_args.header, None);
+    }
+}
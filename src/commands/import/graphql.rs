@@ -0,0 +1,284 @@
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use async_trait::async_trait;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+use super::import::{save, Import};
+
+const SCHEMA_KEY: &str = "__schema";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Schema {
+    query_type: Option<NamedType>,
+    mutation_type: Option<NamedType>,
+    types: Vec<FullType>,
+}
+
+#[derive(Deserialize)]
+struct NamedType {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct FullType {
+    name: String,
+    fields: Option<Vec<Field>>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: String,
+    #[serde(default)]
+    args: Vec<InputValue>,
+    #[serde(rename = "type")]
+    type_ref: TypeRef,
+}
+
+#[derive(Deserialize)]
+struct InputValue {
+    name: String,
+    #[serde(rename = "type")]
+    type_ref: TypeRef,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TypeRef {
+    kind: String,
+    name: Option<String>,
+    of_type: Option<Box<TypeRef>>,
+}
+
+impl TypeRef {
+    /// type as written in a query, e.g. [ID!]!
+    fn signature(&self) -> String {
+        let inner = || {
+            self.of_type
+                .as_ref()
+                .map(|t| t.signature())
+                .unwrap_or_default()
+        };
+        match self.kind.as_str() {
+            "NON_NULL" => format!("{}!", inner()),
+            "LIST" => format!("[{}]", inner()),
+            _ => self.name.clone().unwrap_or_default(),
+        }
+    }
+
+    /// named type wrapped by lists and non nulls
+    fn named(&self) -> &TypeRef {
+        match &self.of_type {
+            Some(t) => t.named(),
+            None => self,
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        matches!(self.named().kind.as_str(), "SCALAR" | "ENUM")
+    }
+}
+
+/// Import a graphql schema introspection result, one action per query and mutation
+pub struct GraphqlImporter<'a> {
+    pub db_handler: &'a DBHandler,
+}
+
+impl<'a> GraphqlImporter<'a> {
+    /// scalar fields of the returned type, __typename if none
+    fn selection_set(schema: &Schema, field: &Field) -> String {
+        if field.type_ref.is_leaf() {
+            return "".to_string();
+        }
+        let type_name = field.type_ref.named().name.as_deref().unwrap_or_default();
+        let scalar_fields = schema
+            .types
+            .iter()
+            .find(|t| t.name == type_name)
+            .and_then(|t| t.fields.as_ref())
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter(|f| f.type_ref.is_leaf() && f.args.is_empty())
+                    .map(|f| f.name.as_str())
+                    .collect_vec()
+            })
+            .filter(|fields| !fields.is_empty())
+            .unwrap_or(vec!["__typename"]);
+        format!(
+            " {{\n{}\n  }}",
+            scalar_fields
+                .iter()
+                .map(|f| format!("    {}", f))
+                .join("\n")
+        )
+    }
+
+    /// graphql document for a root field
+    fn get_query(schema: &Schema, operation: &str, field: &Field) -> String {
+        let (definitions, arguments) = if field.args.is_empty() {
+            ("".to_string(), "".to_string())
+        } else {
+            (
+                format!(
+                    "({})",
+                    field
+                        .args
+                        .iter()
+                        .map(|a| format!("${}: {}", a.name, a.type_ref.signature()))
+                        .join(", ")
+                ),
+                format!(
+                    "({})",
+                    field
+                        .args
+                        .iter()
+                        .map(|a| format!("{}: ${}", a.name, a.name))
+                        .join(", ")
+                ),
+            )
+        };
+        format!(
+            "{} {}{} {{\n  {}{}{}\n}}",
+            operation,
+            field.name,
+            definitions,
+            field.name,
+            arguments,
+            Self::selection_set(schema, field)
+        )
+    }
+
+    /// actions named after their field, a mutation sharing the name of a query
+    /// being prefixed, e.g. mutation-user
+    fn get_actions(schema: &Schema, project_name: &str) -> Vec<Action> {
+        let mut names = HashSet::new();
+        [
+            ("query", schema.query_type.as_ref()),
+            ("mutation", schema.mutation_type.as_ref()),
+        ]
+        .iter()
+        .filter_map(|(operation, root)| {
+            let root_name = &root.as_ref()?.name;
+            let root_type = schema.types.iter().find(|t| &t.name == root_name)?;
+            Some((operation, root_type.fields.as_ref()?))
+        })
+        .flat_map(|(operation, fields)| fields.iter().map(move |field| (operation, field)))
+        .map(|(operation, field)| {
+            let name = match names.contains(&field.name) {
+                true => format!("{}-{}", operation, field.name),
+                false => field.name.clone(),
+            };
+            names.insert(name.clone());
+            let run_action_args = RunActionArgs {
+                name: Some(name.clone()),
+                url: Some("".to_string()),
+                verb: Some("POST".to_string()),
+                query: Some(Self::get_query(schema, operation, field)),
+                ..Default::default()
+            };
+            Action {
+                name: Some(name),
+                run_action_args: serde_json::to_string(&run_action_args).ok(),
+                project_name: Some(project_name.to_string()),
+                ..Default::default()
+            }
+        })
+        .collect()
+    }
+}
+
+#[async_trait]
+impl<'a> Import for GraphqlImporter<'a> {
+    async fn actions(&self, input: &str, project: &mut Project) -> anyhow::Result<Vec<Action>> {
+        let introspection: Value = serde_json::from_str(input)?;
+        // accept the raw introspection response or its data
+        let schema = introspection
+            .get("data")
+            .unwrap_or(&introspection)
+            .get(SCHEMA_KEY)
+            .ok_or(anyhow::anyhow!("No {} found in graphql schema", SCHEMA_KEY))?;
+        let schema: Schema = serde_json::from_value(schema.clone())?;
+        Ok(Self::get_actions(&schema, &project.name))
+    }
+
+    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
+        let actions = self.actions(input, project).await?;
+        save(self.db_handler, project, &actions).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_actions() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+            "queryType": {"name": "Query"},
+            "mutationType": null,
+            "types": [
+                {"kind": "OBJECT", "name": "Query", "fields": [
+                    {"name": "user", "args": [
+                        {"name": "id", "type": {"kind": "NON_NULL", "name": null, "ofType": {"kind": "SCALAR", "name": "ID", "ofType": null}}}
+                    ], "type": {"kind": "OBJECT", "name": "User", "ofType": null}}
+                ]},
+                {"kind": "OBJECT", "name": "User", "fields": [
+                    {"name": "id", "args": [], "type": {"kind": "SCALAR", "name": "ID", "ofType": null}},
+                    {"name": "name", "args": [], "type": {"kind": "SCALAR", "name": "String", "ofType": null}},
+                    {"name": "friends", "args": [], "type": {"kind": "LIST", "name": null, "ofType": {"kind": "OBJECT", "name": "User", "ofType": null}}}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let actions = GraphqlImporter::get_actions(&schema, "users");
+        assert_eq!(actions.len(), 1);
+        let run_action_args = actions[0].get_run_action_args().unwrap();
+        assert_eq!(
+            run_action_args.query.unwrap(),
+            "query user($id: ID!) {\n  user(id: $id) {\n    id\n    name\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn test_query_and_mutation_sharing_a_name() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+            "queryType": {"name": "Query"},
+            "mutationType": {"name": "Mutation"},
+            "types": [
+                {"kind": "OBJECT", "name": "Query", "fields": [
+                    {"name": "user", "args": [], "type": {"kind": "SCALAR", "name": "String", "ofType": null}}
+                ]},
+                {"kind": "OBJECT", "name": "Mutation", "fields": [
+                    {"name": "user", "args": [
+                        {"name": "name", "type": {"kind": "SCALAR", "name": "String", "ofType": null}}
+                    ], "type": {"kind": "SCALAR", "name": "String", "ofType": null}},
+                    {"name": "logout", "args": [], "type": {"kind": "SCALAR", "name": "Boolean", "ofType": null}}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let actions = GraphqlImporter::get_actions(&schema, "users");
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.name.clone().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["user", "mutation-user", "logout"]
+        );
+        let run_action_args = actions[1].get_run_action_args().unwrap();
+        assert_eq!(run_action_args.name.as_deref(), Some("mutation-user"));
+        assert!(run_action_args
+            .query
+            .unwrap()
+            .starts_with("mutation user($name: String)"));
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use base64::Engine;
+use clap::Args;
+use crossterm::style::Stylize;
+use reqwest::Url;
+
+use crate::commands::record::capture::Exchange;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::har::{Entry, Har};
+use crate::http::Verb;
+
+use super::import::Import;
+
+/// Import a HAR file recorded by the browser devtools as project actions
+#[derive(Args)]
+pub struct HarImportArgs {
+    /// path or url of the HAR file
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    pub file: String,
+
+    /// project of the actions, created from the first request url if missing
+    #[arg(short, long)]
+    pub project: String,
+
+    /// only import requests to these hosts
+    #[arg(long)]
+    pub host: Option<Vec<String>>,
+
+    /// only import requests with these verbs, OPTIONS preflights are skipped otherwise
+    #[arg(short, long)]
+    pub method: Option<Vec<String>>,
+
+    /// only import responses whose content type contains this value, e.g. json
+    #[arg(long)]
+    pub content_type: Option<String>,
+}
+
+impl HarImportArgs {
+    pub async fn import(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let importer = HarImporter {
+            db_handler,
+            hosts: self.host.clone().unwrap_or_default(),
+            methods: self
+                .method
+                .iter()
+                .flatten()
+                .map(|m| m.to_uppercase())
+                .collect(),
+            content_type: self.content_type.clone(),
+        };
+        let content = importer.load(&self.file).await?;
+        let mut project = db_handler
+            .get_project(&self.project)
+            .await
+            .unwrap_or(Project {
+                id: None,
+                name: self.project.clone(),
+                main_url: "".to_string(),
+                conf: None,
+                source: None,
+                created_at: None,
+                updated_at: None,
+            });
+        importer.import(&content, &mut project).await
+    }
+}
+
+/// Import the entries of a HAR file matching the filters
+pub struct HarImporter<'a> {
+    pub db_handler: &'a DBHandler,
+    pub hosts: Vec<String>,
+    pub methods: Vec<String>,
+    pub content_type: Option<String>,
+}
+
+impl<'a> HarImporter<'a> {
+    fn is_selected(&self, entry: &Entry, url: &Url) -> bool {
+        let method = entry.request.method.to_uppercase();
+        let is_method_selected = match self.methods.is_empty() {
+            true => method != Verb::Options.to_string(),
+            false => self.methods.contains(&method),
+        };
+        let is_host_selected = self.hosts.is_empty()
+            || url
+                .host_str()
+                .is_some_and(|host| self.hosts.iter().any(|h| h == host));
+        let is_content_type_selected = self
+            .content_type
+            .as_ref()
+            .is_none_or(|c| entry.response.content.mime_type.contains(c.as_str()));
+        is_method_selected && is_host_selected && is_content_type_selected
+    }
+
+    /// response body, binary responses are not kept
+    fn response_text(entry: &Entry) -> String {
+        let content = &entry.response.content;
+        let text = content.text.clone().unwrap_or_default();
+        match content.encoding.as_deref() {
+            Some("base64") => base64::engine::general_purpose::STANDARD
+                .decode(text)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .unwrap_or_default(),
+            _ => text,
+        }
+    }
+
+    fn to_exchanges(&self, har: &Har) -> Vec<Exchange> {
+        har.log
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let url = Url::parse(&entry.request.url).ok()?;
+                if !self.is_selected(entry, &url) {
+                    return None;
+                }
+                // skip verbs apikrab cannot run, e.g. HEAD
+                let verb = Verb::from_str(&entry.request.method.to_uppercase()).ok()?;
+                Some(Exchange {
+                    verb: verb.to_string(),
+                    url,
+                    headers: entry
+                        .request
+                        .headers
+                        .iter()
+                        // http2 pseudo headers, e.g. :authority
+                        .filter(|h| !h.name.starts_with(':'))
+                        .map(|h| (h.name.clone(), h.value.clone()))
+                        .collect(),
+                    body: entry
+                        .request
+                        .post_data
+                        .as_ref()
+                        .map(|p| p.text.clone())
+                        .unwrap_or_default(),
+                    status: entry.response.status,
+                    response: Self::response_text(entry),
+                })
+            })
+            .collect()
+    }
+
+    /// one action per name, the last successful exchange being kept
+    fn to_actions(exchanges: &[Exchange], project: &Project) -> Vec<Action> {
+        let mut actions: Vec<Action> = vec![];
+        let mut indexes = HashMap::new();
+        for exchange in exchanges {
+            let action = exchange.to_action(project);
+            let name = action.name.clone().unwrap_or_default();
+            match indexes.get(&name) {
+                Some(&i) => {
+                    let previous: &Action = &actions[i];
+                    if action.response_example.is_some() || previous.response_example.is_none() {
+                        actions[i] = action;
+                    }
+                }
+                None => {
+                    indexes.insert(name, actions.len());
+                    actions.push(action);
+                }
+            }
+        }
+        actions
+    }
+}
+
+#[async_trait]
+impl<'a> Import for HarImporter<'a> {
+    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
+        let har: Har = serde_json::from_str(input)?;
+        let exchanges = self.to_exchanges(&har);
+        let Some(first) = exchanges.first() else {
+            anyhow::bail!("No request of the HAR file matches the filters");
+        };
+        // new projects are rooted at the origin of the first request
+        if project.main_url.is_empty() {
+            project.main_url = first.url.origin().ascii_serialization();
+        }
+
+        self.db_handler.upsert_project(project).await?;
+        let actions = Self::to_actions(&exchanges, project);
+        for action in actions.iter() {
+            self.db_handler.upsert_action(action).await?;
+        }
+        println!(
+            "{} actions imported in project {}",
+            actions.len(),
+            project.name.clone().green()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_har_to_actions() {
+        let har: Har = serde_json::from_str(
+            r#"{"log": {"version": "1.2", "entries": [
+                {"request": {"method": "GET", "url": "https://api.example.com/users/42?expand=roles",
+                    "headers": [{"name": ":authority", "value": "api.example.com"},
+                                {"name": "Authorization", "value": "Bearer xyz"}]},
+                 "response": {"status": 200, "content": {"mimeType": "application/json",
+                    "text": "eyJpZCI6IDQyfQ==", "encoding": "base64"}}},
+                {"request": {"method": "GET", "url": "https://api.example.com/users/42"},
+                 "response": {"status": 500, "content": {"mimeType": "application/json", "text": "{}"}}},
+                {"request": {"method": "OPTIONS", "url": "https://api.example.com/users/42"},
+                 "response": {"status": 204, "content": {"mimeType": "x-unknown"}}},
+                {"request": {"method": "POST", "url": "https://api.example.com/users",
+                    "postData": {"mimeType": "application/json", "text": "{\"name\": \"Marco\"}"}},
+                 "response": {"status": 201, "content": {"mimeType": "application/json", "text": "{}"}}},
+                {"request": {"method": "GET", "url": "https://cdn.example.com/app.js"},
+                 "response": {"status": 200, "content": {"mimeType": "text/javascript", "text": ""}}}
+            ]}}"#,
+        )
+        .unwrap();
+        let project = Project {
+            id: None,
+            name: "users".to_string(),
+            main_url: "https://api.example.com".to_string(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let db_handler = DBHandler::default();
+        let importer = HarImporter {
+            db_handler: &db_handler,
+            hosts: vec!["api.example.com".to_string()],
+            methods: vec![],
+            content_type: Some("json".to_string()),
+        };
+
+        let actions = HarImporter::to_actions(&importer.to_exchanges(&har), &project);
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.name.clone().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["get-users-42", "post-users"]
+        );
+        assert_eq!(
+            actions[0].response_example.as_deref(),
+            Some(r#"{"id": 42}"#)
+        );
+        let run_action_args = actions[0].get_run_action_args().unwrap();
+        assert_eq!(
+            run_action_args.header,
+            Some(vec!["Authorization:Bearer xyz".to_string()])
+        );
+        assert_eq!(
+            actions[1].get_run_action_args().unwrap().body,
+            Some(vec![r#"{"name": "Marco"}"#.to_string()])
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use clap::Args;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use reqwest::Url;
+
+use crate::commands::record::capture::header_args;
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::http_file::{HttpFile, HttpRequest};
+use crate::utils::{replace_with_conf, Interpol, SEP};
+
+use super::import::{base_url, relative_url, slug, Import};
+
+/// Headers computed when sending the request
+const SKIPPED_HEADERS: [&str; 2] = ["host", "content-length"];
+
+/// Import a JetBrains / VS Code .http or .rest file as project actions
+#[derive(Args)]
+pub struct HttpFileImportArgs {
+    /// path or url of the .http file
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    pub file: String,
+
+    /// project of the actions, created from the first request url if missing
+    #[arg(short, long)]
+    pub project: String,
+}
+
+impl HttpFileImportArgs {
+    pub async fn import(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let importer = HttpFileImporter { db_handler };
+        let content = importer.load(&self.file).await?;
+        let mut project = db_handler
+            .get_project(&self.project)
+            .await
+            .unwrap_or(Project {
+                id: None,
+                name: self.project.clone(),
+                main_url: "".to_string(),
+                conf: None,
+                source: None,
+                created_at: None,
+                updated_at: None,
+            });
+        importer.import(&content, &mut project).await
+    }
+}
+
+/// value of a `key=value` parameter of a header, e.g. the multipart boundary
+fn header_param<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    header
+        .split(';')
+        .find_map(|p| p.trim().strip_prefix(key)?.strip_prefix('='))
+        .map(|v| v.trim_matches('"'))
+}
+
+/// multipart fields, `< path` parts being file uploads
+fn multipart_fields(body: &str, boundary: &str) -> Vec<(String, String)> {
+    body.split(&format!("--{}", boundary))
+        .filter_map(|part| {
+            let (headers, content) = part.trim_start_matches('\n').split_once("\n\n")?;
+            let name = headers
+                .lines()
+                .find(|h| h.to_lowercase().starts_with("content-disposition"))
+                .and_then(|h| header_param(h, "name"))?;
+            let content = content.trim();
+            let value = match content.strip_prefix('<') {
+                Some(path) => format!("@{}", path.trim()),
+                None => content.to_string(),
+            };
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Import the requests of a .http file, its variables being added to the project conf
+pub struct HttpFileImporter<'a> {
+    pub db_handler: &'a DBHandler,
+}
+
+impl<'a> HttpFileImporter<'a> {
+    /// project conf extended with the file variables, each one being resolved with the previous ones
+    fn variables(http_file: &HttpFile, conf: HashMap<String, String>) -> HashMap<String, String> {
+        http_file
+            .variables
+            .iter()
+            .fold(conf, |mut variables, (name, value)| {
+                let value = replace_with_conf(value, &variables, Interpol::MultiInterpol);
+                let value = value.into_owned();
+                variables.insert(name.clone(), value);
+                variables
+            })
+    }
+
+    /// base url variable of the first request, or its origin
+    fn main_url(request: &HttpRequest, variables: &HashMap<String, String>) -> Option<String> {
+        match base_url(&request.url, variables) {
+            Some((base_url, _)) => Some(base_url.trim_end_matches('/').to_string()),
+            None => Url::parse(&request.url)
+                .ok()
+                .filter(|u| u.has_host())
+                .map(|u| u.origin().ascii_serialization()),
+        }
+    }
+
+    fn to_action(
+        request: &HttpRequest,
+        variables: &HashMap<String, String>,
+        project: &Project,
+    ) -> Action {
+        let (path, query) = request.url.split_once('?').unwrap_or((&request.url, ""));
+        let url = relative_url(path, variables, &project.main_url);
+        let name = match request.name.as_deref().map(slug) {
+            Some(name) if !name.is_empty() => name,
+            _ => {
+                let path = url.split_once("://").map_or(url.as_str(), |(_, u)| {
+                    u.split_once('/').map(|(_, p)| p).unwrap_or_default()
+                });
+                match slug(path).as_str() {
+                    "" => format!("{}-index", request.verb.to_lowercase()),
+                    path => format!("{}-{}", request.verb.to_lowercase(), path),
+                }
+            }
+        };
+        let query_params = url::form_urlencoded::parse(query.as_bytes())
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect_vec();
+
+        let content_type = request
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+            .map(|(_, v)| v.as_str())
+            .unwrap_or_default();
+        let is_content_type = |mime: &str| content_type.to_lowercase().starts_with(mime);
+        let mut run_action_args = RunActionArgs {
+            name: Some(name.clone()),
+            url: Some(url),
+            verb: Some(request.verb.clone()),
+            ..Default::default()
+        };
+        if let Some(body) = request.body.as_deref() {
+            let trimmed = body.trim_start();
+            if let Some(path) = body.strip_prefix('<').filter(|p| !p.contains('\n')) {
+                run_action_args.body = Some(vec![format!("@{}", path.trim())]);
+            } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                run_action_args.body = Some(vec![body.to_string()]);
+            } else if is_content_type("application/x-www-form-urlencoded") {
+                let body = body.lines().map(str::trim).join("");
+                let fields = url::form_urlencoded::parse(body.as_bytes())
+                    .map(|(k, v)| format!("{}:{}", k, v))
+                    .join(SEP);
+                run_action_args.body = Some(vec![fields]);
+                run_action_args.url_encoded = true;
+            } else if let Some(boundary) = is_content_type("multipart/form-data")
+                .then(|| header_param(content_type, "boundary"))
+                .flatten()
+            {
+                let fields = multipart_fields(body, boundary)
+                    .iter()
+                    .map(|(k, v)| format!("{}:{}", k, v))
+                    .join(SEP);
+                run_action_args.body = Some(vec![fields]);
+                run_action_args.form_data = true;
+            } else {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Body of {} is neither json nor a form, only kept as example",
+                        name
+                    )
+                    .yellow()
+                );
+            }
+        }
+        let (headers, content_type) = header_args(
+            request
+                .headers
+                .iter()
+                .filter(|(k, _)| !SKIPPED_HEADERS.contains(&k.to_lowercase().as_str()))
+                // the multipart boundary is generated when sending
+                .filter(|(k, _)| {
+                    !run_action_args.form_data || !k.eq_ignore_ascii_case("content-type")
+                }),
+        );
+        run_action_args.content_type = content_type;
+        run_action_args.header = (!headers.is_empty()).then_some(headers);
+        run_action_args.query_params = (!query_params.is_empty()).then_some(query_params);
+
+        Action {
+            name: Some(name),
+            run_action_args: serde_json::to_string(&run_action_args).ok(),
+            body_example: request.body.clone(),
+            project_name: Some(project.name.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> Import for HttpFileImporter<'a> {
+    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
+        let http_file = HttpFile::from_str(input)?;
+        let variables = Self::variables(&http_file, project.get_project_conf()?);
+        if project.main_url.is_empty() {
+            project.main_url =
+                Self::main_url(&http_file.requests[0], &variables).unwrap_or_default();
+        }
+        if !variables.is_empty() {
+            project.conf = Some(serde_json::to_string(&variables)?);
+        }
+
+        self.db_handler.upsert_project(project).await?;
+        for request in http_file.requests.iter() {
+            let action = Self::to_action(request, &variables, project);
+            self.db_handler.upsert_action(&action).await?;
+        }
+        println!(
+            "{} actions imported in project {}",
+            http_file.requests.len(),
+            project.name.clone().green()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_file_to_actions() {
+        let http_file = HttpFile::from_str(
+            r#"@host = http://localhost:8080
+@baseUrl = {{host}}/api
+
+### Get user
+GET {{baseUrl}}/users/{{id}}?expand=roles
+Accept: application/json; charset=utf-8
+
+###
+POST {{baseUrl}}/login
+Content-Type: application/x-www-form-urlencoded
+
+user=marco&
+password={{password}}
+
+###
+POST {{baseUrl}}/avatars
+Content-Type: multipart/form-data; boundary=WebAppBoundary
+
+--WebAppBoundary
+Content-Disposition: form-data; name="user"
+
+42
+--WebAppBoundary
+Content-Disposition: form-data; name="file"; filename="me.png"
+
+< ./me.png
+--WebAppBoundary--
+"#,
+        )
+        .unwrap();
+        let variables = HttpFileImporter::variables(&http_file, HashMap::new());
+        assert_eq!(variables["baseUrl"], "http://localhost:8080/api");
+        let project = Project {
+            id: None,
+            name: "users".to_string(),
+            main_url: HttpFileImporter::main_url(&http_file.requests[0], &variables).unwrap(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        assert_eq!(project.main_url, "http://localhost:8080/api");
+
+        let actions = http_file
+            .requests
+            .iter()
+            .map(|r| HttpFileImporter::to_action(r, &variables, &project))
+            .collect_vec();
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.name.clone().unwrap())
+                .collect_vec(),
+            vec!["get-user", "post-login", "post-avatars"]
+        );
+        let get_user = actions[0].get_run_action_args().unwrap();
+        assert_eq!(get_user.url.as_deref(), Some("users/{{id}}"));
+        assert_eq!(
+            get_user.query_params,
+            Some(vec!["expand:roles".to_string()])
+        );
+        assert_eq!(
+            get_user.header,
+            Some(vec!["Accept:application/json".to_string()])
+        );
+        let login = actions[1].get_run_action_args().unwrap();
+        assert!(login.url_encoded);
+        assert_eq!(
+            login.body,
+            Some(vec!["user:marco;password:{{password}}".to_string()])
+        );
+        let avatars = actions[2].get_run_action_args().unwrap();
+        assert!(avatars.form_data);
+        assert_eq!(avatars.header, None);
+        assert_eq!(
+            avatars.body,
+            Some(vec!["user:42;file:@./me.png".to_string()])
+        );
+    }
+}
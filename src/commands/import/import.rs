@@ -1,7 +1,12 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
+use crossterm::style::Stylize;
+use itertools::Itertools;
 use tokio::{fs::File, io::AsyncReadExt};
 
-use crate::db::dto::Project;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
 
 #[async_trait]
 pub trait Import {
@@ -34,6 +39,65 @@ pub trait Import {
         }
     }
 
+    /// actions of the input, the project url and conf being completed but nothing saved,
+    /// only project sources (openapi, postman...) can list them
+    async fn actions(&self, _input: &str, _project: &mut Project) -> anyhow::Result<Vec<Action>> {
+        anyhow::bail!("Actions of this importer are only imported in a project")
+    }
+
     /// import main function
     async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()>;
 }
+
+/// save a project and its imported actions
+pub async fn save(
+    db_handler: &DBHandler,
+    project: &Project,
+    actions: &[Action],
+) -> anyhow::Result<()> {
+    db_handler.upsert_project(project).await?;
+    for action in actions {
+        db_handler.upsert_action(action).await?;
+    }
+    println!(
+        "{} actions imported in project {}",
+        actions.len(),
+        project.name.clone().green()
+    );
+    Ok(())
+}
+
+/// lowercase words separated by -, e.g. get-users-id
+pub fn slug(s: &str) -> String {
+    s.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .join("-")
+        .to_lowercase()
+}
+
+/// value of the leading {{var}} of an url, usually the base url, and the rest of the url
+pub fn base_url<'u>(
+    url: &'u str,
+    variables: &'u HashMap<String, String>,
+) -> Option<(&'u str, &'u str)> {
+    let (name, path) = url.strip_prefix("{{")?.split_once("}}")?;
+    Some((variables.get(name.trim())?, path))
+}
+
+/// url relative to the project url when possible, its leading {{var}} being resolved
+pub fn relative_url(url: &str, variables: &HashMap<String, String>, main_url: &str) -> String {
+    let url = match base_url(url, variables) {
+        Some((base_url, path)) => format!("{}{}", base_url, path),
+        // unknown base url, the project one is used instead
+        None if url.starts_with("{{") && !url.contains("://") => {
+            let path = url.split_once("}}").map(|(_, p)| p).unwrap_or_default();
+            return path.trim_matches('/').to_string();
+        }
+        None => url.to_string(),
+    };
+    let main_url = main_url.trim_end_matches('/');
+    match url.strip_prefix(main_url) {
+        Some(path) if !main_url.is_empty() => path.trim_matches('/').to_string(),
+        _ => url,
+    }
+}
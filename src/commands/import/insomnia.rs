@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use base64::Engine;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::utils::{replace_with_conf, Interpol, SEP};
+
+use super::import::{relative_url, save, slug, Import};
+
+lazy_static! {
+    /// nunjucks variable, e.g. {{ _.base_url }}
+    static ref TEMPLATE: Regex = Regex::new(r"\{\{\s*(?:_\.)?([\w.-]+)\s*\}\}").unwrap();
+}
+
+/// {{ _.var }} as an apikrab {{var}}
+fn template(s: &str) -> String {
+    TEMPLATE.replace_all(s, "{{$1}}").into_owned()
+}
+
+/// Insomnia v4 export, only the resources used by apikrab are deserialized
+#[derive(Deserialize)]
+struct Export {
+    resources: Vec<Resource>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "_type", rename_all = "snake_case")]
+enum Resource {
+    RequestGroup(Group),
+    Request(Request),
+    Environment(Environment),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Group {
+    #[serde(rename = "_id")]
+    id: String,
+    parent_id: Option<String>,
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Param {
+    name: String,
+    value: String,
+    disabled: bool,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    file_name: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Body {
+    mime_type: Option<String>,
+    text: Option<String>,
+    params: Vec<Param>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Request {
+    parent_id: Option<String>,
+    name: String,
+    method: String,
+    url: String,
+    headers: Vec<Param>,
+    parameters: Vec<Param>,
+    body: Body,
+    authentication: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Environment {
+    #[serde(rename = "_id")]
+    id: String,
+    parent_id: Option<String>,
+    name: String,
+    #[serde(default)]
+    data: Value,
+}
+
+/// environment values, nested ones being named with a dotted path as in templates
+fn flatten(prefix: &str, value: &Value, variables: &mut HashMap<String, String>) {
+    match value {
+        Value::Object(map) => map.iter().for_each(|(k, v)| {
+            let name = match prefix {
+                "" => k.clone(),
+                _ => format!("{}.{}", prefix, k),
+            };
+            flatten(&name, v, variables)
+        }),
+        Value::String(s) => {
+            variables.insert(prefix.to_string(), template(s));
+        }
+        Value::Null => {}
+        v => {
+            variables.insert(prefix.to_string(), v.to_string());
+        }
+    }
+}
+
+fn enabled(params: &[Param]) -> impl Iterator<Item = &Param> {
+    params.iter().filter(|p| !p.disabled)
+}
+
+/// Import an Insomnia v4 export, folders being action name prefixes
+pub struct InsomniaImporter<'a> {
+    pub db_handler: &'a DBHandler,
+    /// sub environment added to the base one
+    pub env: Option<String>,
+}
+
+impl<'a> InsomniaImporter<'a> {
+    /// base environment extended with the selected sub environment
+    fn variables(&self, environments: &[&Environment]) -> anyhow::Result<HashMap<String, String>> {
+        let ids = environments.iter().map(|e| e.id.as_str()).collect_vec();
+        let is_base = |e: &&&Environment| {
+            e.parent_id
+                .as_deref()
+                .is_none_or(|parent| !ids.contains(&parent))
+        };
+        let mut selected = environments.iter().filter(is_base).collect_vec();
+        if let Some(env) = self.env.as_deref() {
+            match environments.iter().find(|e| !is_base(e) && e.name == env) {
+                Some(e) => selected.push(e),
+                None => anyhow::bail!(
+                    "Environment {} not found, available: {}",
+                    env,
+                    environments
+                        .iter()
+                        .filter(|e| !is_base(e))
+                        .map(|e| e.name.as_str())
+                        .join(", ")
+                ),
+            }
+        }
+        let mut variables = HashMap::new();
+        for environment in selected {
+            flatten("", &environment.data, &mut variables);
+        }
+        // values referencing other values, e.g. {{ _.host }}/api
+        let resolved = variables
+            .iter()
+            .map(|(k, v)| {
+                let v = replace_with_conf(v, &variables, Interpol::MultiInterpol);
+                (k.clone(), v.into_owned())
+            })
+            .collect();
+        Ok(resolved)
+    }
+
+    /// folder names of a request, from the root
+    fn folders<'g>(
+        groups: &HashMap<&str, &'g Group>,
+        mut parent_id: Option<&'g str>,
+    ) -> Vec<&'g str> {
+        let mut folders = vec![];
+        while let Some(group) = parent_id.and_then(|id| groups.get(id)) {
+            folders.insert(0, group.name.as_str());
+            parent_id = group.parent_id.as_deref();
+        }
+        folders
+    }
+
+    fn authorization(request: &Request, name: &str) -> Option<String> {
+        let auth = &request.authentication;
+        if auth.get("disabled").and_then(Value::as_bool) == Some(true) {
+            return None;
+        }
+        let field = |key: &str| template(auth.get(key).and_then(Value::as_str).unwrap_or_default());
+        match auth.get("type").and_then(Value::as_str) {
+            None => None,
+            Some("bearer") => {
+                let prefix = auth
+                    .get("prefix")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                let prefix = if prefix.is_empty() { "Bearer" } else { prefix };
+                Some(format!("Authorization:{} {}", prefix, field("token")))
+            }
+            // credentials are encoded at import, vars can not be resolved later
+            Some("basic")
+                if !field("username").contains("{{") && !field("password").contains("{{") =>
+            {
+                let credentials = format!("{}:{}", field("username"), field("password"));
+                Some(format!(
+                    "Authorization:Basic {}",
+                    base64::engine::general_purpose::STANDARD.encode(credentials)
+                ))
+            }
+            Some(kind) => {
+                eprintln!(
+                    "{}",
+                    format!("{} authentication of {} is not imported", kind, name).yellow()
+                );
+                None
+            }
+        }
+    }
+
+    fn to_action(
+        request: &Request,
+        name: &str,
+        variables: &HashMap<String, String>,
+        project: &Project,
+    ) -> Action {
+        let (path, query) = request.url.split_once('?').unwrap_or((&request.url, ""));
+        let mut query_params = url::form_urlencoded::parse(query.as_bytes())
+            .map(|(k, v)| format!("{}:{}", k, template(&v)))
+            .collect_vec();
+        query_params.extend(
+            enabled(&request.parameters).map(|p| format!("{}:{}", p.name, template(&p.value))),
+        );
+        let mut run_action_args = RunActionArgs {
+            name: Some(name.to_string()),
+            url: Some(relative_url(&template(path), variables, &project.main_url)),
+            verb: Some(request.method.to_uppercase()),
+            query_params: (!query_params.is_empty()).then_some(query_params),
+            ..Default::default()
+        };
+
+        let body = &request.body;
+        let text = body.text.as_deref().map(template).unwrap_or_default();
+        let fields = || {
+            enabled(&body.params)
+                .map(|p| match (p.kind.as_deref(), p.file_name.as_deref()) {
+                    (Some("file"), Some(file)) => format!("{}:@{}", p.name, file),
+                    _ => format!("{}:{}", p.name, template(&p.value)),
+                })
+                .join(SEP)
+        };
+        match body.mime_type.as_deref().unwrap_or_default() {
+            "application/x-www-form-urlencoded" => {
+                run_action_args.body = Some(vec![fields()]);
+                run_action_args.url_encoded = true;
+            }
+            "multipart/form-data" => {
+                run_action_args.body = Some(vec![fields()]);
+                run_action_args.form_data = true;
+            }
+            // the text is a json envelope of the query and its variables
+            "application/graphql" => {
+                let graphql = serde_json::from_str::<Value>(&text).unwrap_or_default();
+                run_action_args.query = graphql["query"].as_str().map(String::from);
+                run_action_args.variables = graphql
+                    .get("variables")
+                    .filter(|v| !v.is_null())
+                    .map(|v| v.to_string());
+            }
+            _ if text.trim_start().starts_with(['{', '[']) => {
+                run_action_args.body = Some(vec![text.clone()]);
+            }
+            _ if !text.is_empty() => eprintln!(
+                "{}",
+                format!(
+                    "Body of {} is neither json nor a form, only kept as example",
+                    name
+                )
+                .yellow()
+            ),
+            _ => {}
+        }
+
+        let mut headers = enabled(&request.headers)
+            // the multipart boundary is generated when sending
+            .filter(|h| !run_action_args.form_data || !h.name.eq_ignore_ascii_case("content-type"))
+            .map(|h| format!("{}:{}", h.name, template(&h.value)))
+            .collect_vec();
+        headers.extend(Self::authorization(request, name));
+        run_action_args.header = (!headers.is_empty()).then_some(headers);
+
+        Action {
+            name: Some(name.to_string()),
+            run_action_args: serde_json::to_string(&run_action_args).ok(),
+            body_example: (!text.is_empty()).then_some(text),
+            project_name: Some(project.name.clone()),
+            ..Default::default()
+        }
+    }
+
+    fn to_actions(
+        &self,
+        input: &str,
+        project: &Project,
+    ) -> anyhow::Result<(Vec<Action>, HashMap<String, String>)> {
+        let export: Export = serde_json::from_str(input)?;
+        let mut groups = HashMap::new();
+        let mut requests = vec![];
+        let mut environments = vec![];
+        for resource in export.resources.iter() {
+            match resource {
+                Resource::RequestGroup(group) => {
+                    groups.insert(group.id.as_str(), group);
+                }
+                Resource::Request(request) => requests.push(request),
+                Resource::Environment(environment) => environments.push(environment),
+                Resource::Other => {}
+            }
+        }
+        let variables = self.variables(&environments)?;
+        let actions = requests
+            .iter()
+            .map(|request| {
+                let mut folders = Self::folders(&groups, request.parent_id.as_deref());
+                folders.push(&request.name);
+                Self::to_action(request, &slug(&folders.join(" ")), &variables, project)
+            })
+            .collect();
+        Ok((actions, variables))
+    }
+}
+
+#[async_trait]
+impl<'a> Import for InsomniaImporter<'a> {
+    async fn actions(&self, input: &str, project: &mut Project) -> anyhow::Result<Vec<Action>> {
+        let (actions, mut variables) = self.to_actions(input, project)?;
+        // conf given on the command line wins
+        variables.extend(project.get_project_conf()?);
+        if !variables.is_empty() {
+            project.conf = Some(serde_json::to_string(&variables)?);
+        }
+
+        Ok(actions)
+    }
+
+    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
+        let actions = self.actions(input, project).await?;
+        save(self.db_handler, project, &actions).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insomnia_to_actions() {
+        let input = r#"{"_type": "export", "__export_format": 4, "resources": [
+            {"_id": "wrk_1", "_type": "workspace", "name": "Shop"},
+            {"_id": "fld_1", "_type": "request_group", "parentId": "wrk_1", "name": "Users"},
+            {"_id": "fld_2", "_type": "request_group", "parentId": "fld_1", "name": "Admin"},
+            {"_id": "req_1", "_type": "request", "parentId": "fld_2", "name": "Get user",
+             "method": "GET", "url": "{{ _.base_url }}/users/{{ _.user.id }}?expand=roles",
+             "headers": [{"name": "Accept", "value": "application/json"},
+                         {"name": "X-Debug", "value": "1", "disabled": true}],
+             "authentication": {"type": "bearer", "token": "{{ _.token }}"}},
+            {"_id": "req_2", "_type": "request", "parentId": "wrk_1", "name": "Upload",
+             "method": "post", "url": "https://shop.example.com/api/files",
+             "headers": [{"name": "Content-Type", "value": "multipart/form-data"}],
+             "body": {"mimeType": "multipart/form-data", "params": [
+                {"name": "file", "type": "file", "fileName": "/tmp/a.png"},
+                {"name": "label", "value": "avatar"}]}},
+            {"_id": "env_1", "_type": "environment", "parentId": "wrk_1", "name": "Base Environment",
+             "data": {"host": "https://shop.example.com", "base_url": "{{ _.host }}/api"}},
+            {"_id": "env_2", "_type": "environment", "parentId": "env_1", "name": "Staging",
+             "data": {"token": "abc", "user": {"id": 42}}}
+        ]}"#;
+        let project = Project {
+            id: None,
+            name: "shop".to_string(),
+            main_url: "https://shop.example.com/api".to_string(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let db_handler = DBHandler::default();
+        let importer = InsomniaImporter {
+            db_handler: &db_handler,
+            env: Some("Staging".to_string()),
+        };
+
+        let (actions, variables) = importer.to_actions(input, &project).unwrap();
+        assert_eq!(variables["base_url"], "https://shop.example.com/api");
+        assert_eq!(variables["user.id"], "42");
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.name.clone().unwrap())
+                .collect_vec(),
+            vec!["users-admin-get-user", "upload"]
+        );
+        let get_user = actions[0].get_run_action_args().unwrap();
+        assert_eq!(get_user.url.as_deref(), Some("users/{{user.id}}"));
+        assert_eq!(
+            get_user.query_params,
+            Some(vec!["expand:roles".to_string()])
+        );
+        assert_eq!(
+            get_user.header,
+            Some(vec![
+                "Accept:application/json".to_string(),
+                "Authorization:Bearer {{token}}".to_string()
+            ])
+        );
+        let upload = actions[1].get_run_action_args().unwrap();
+        assert_eq!(upload.url.as_deref(), Some("files"));
+        assert_eq!(upload.verb.as_deref(), Some("POST"));
+        assert!(upload.form_data);
+        assert_eq!(upload.header, None);
+        assert_eq!(
+            upload.body,
+            Some(vec!["file:@/tmp/a.png;label:avatar".to_string()])
+        );
+
+        let importer = InsomniaImporter {
+            db_handler: &db_handler,
+            env: Some("Prod".to_string()),
+        };
+        assert!(importer.to_actions(input, &project).is_err());
+    }
+}
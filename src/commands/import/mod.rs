@@ -1,3 +1,32 @@
+pub(crate) mod bruno;
+pub(crate) mod curl;
+pub(crate) mod graphql;
+pub(crate) mod har;
+pub(crate) mod http_file;
+#[allow(clippy::module_inception)]
 pub(crate) mod import;
+pub(crate) mod insomnia;
 pub(crate) mod openapi;
+pub(crate) mod openapi_compat;
 pub(crate) mod postman;
+
+use clap::{Args, Subcommand};
+use curl::CurlImportArgs;
+use har::HarImportArgs;
+use http_file::HttpFileImportArgs;
+
+#[derive(Args)]
+pub struct Import {
+    #[command(subcommand)]
+    pub import_commands: ImportCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ImportCommands {
+    /// Import a curl command as a project action
+    Curl(CurlImportArgs),
+    /// Import the requests of a HAR file as project actions
+    Har(HarImportArgs),
+    /// Import a JetBrains / VS Code .http or .rest file as project actions
+    Http(HttpFileImportArgs),
+}
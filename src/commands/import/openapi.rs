@@ -1,65 +1,560 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
 use crate::commands::run::action::RunActionArgs;
 use crate::db::db_handler::DBHandler;
 use crate::db::dto::{Action, Project};
+use crate::http::Verb;
 use async_trait::async_trait;
-use openapiv3::{OpenAPI, Operation};
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use openapiv3::{
+    APIKeyLocation, Content, OpenAPI, Operation, Parameter, ParameterData,
+    ParameterSchemaOrContent, ReferenceOr, SecurityScheme, Server, StatusCode,
+};
+use serde_json::Value;
+
+use super::import::{save, slug, Import};
+use super::openapi_compat::Converter;
+
+/// Headers described by the spec but computed when sending the request
+const SKIPPED_HEADERS: [&str; 3] = ["accept", "content-type", "authorization"];
 
-use super::import::Import;
+/// item of a local $ref, e.g. #/components/parameters/limit
+fn resolve<'a, T>(
+    item: &'a ReferenceOr<T>,
+    lookup: impl Fn(&str) -> Option<&'a ReferenceOr<T>>,
+) -> Option<&'a T> {
+    match item {
+        ReferenceOr::Item(item) => Some(item),
+        ReferenceOr::Reference { reference } => lookup(reference.rsplit('/').next()?)?.as_item(),
+    }
+}
 
+/// strings are not quoted
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// server url, its {variables} being replaced by their default value
+fn server_url(server: &Server) -> String {
+    server
+        .variables
+        .iter()
+        .flatten()
+        .fold(server.url.clone(), |url, (name, variable)| {
+            url.replace(&format!("{{{}}}", name), &variable.default)
+        })
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Import the operations of a swagger 2.0 or openapi 3 spec, json or yaml
 pub struct OpenapiV3Importer<'a> {
     pub db_handler: &'a DBHandler,
 }
 
 impl<'a> OpenapiV3Importer<'a> {
-    pub fn get_action(op: &Operation, path: &str, verb: &str, project_name: &str) -> Action {
-        let run_action_args = RunActionArgs {
-            url: Some(path[1..].to_string()),
+    /// openapi 3.0 spec, converted from swagger 2.0 or openapi 3.1 if needed,
+    /// and the parts skipped by the conversion
+    pub fn parse(input: &str) -> anyhow::Result<(OpenAPI, Vec<String>)> {
+        // yaml being a superset of json, its error is the most relevant one
+        let spec: Value = match serde_json::from_str(input) {
+            Ok(spec) => spec,
+            // yaml status codes are integer keys, json values need string ones
+            Err(_) => serde_yaml::from_str::<serde_yaml::Value>(input)
+                .map_err(anyhow::Error::from)
+                .and_then(|spec| Ok(serde_json::to_value(spec)?))
+                .map_err(|e| anyhow::anyhow!("Invalid openapi spec: {}", e))?,
+        };
+        let version = |key: &str| spec.get(key).and_then(Value::as_str).map(str::to_string);
+        let mut converter = Converter::default();
+        let spec = match (version("swagger"), version("openapi")) {
+            (Some(v), _) if v.starts_with("2.") => converter.convert_swagger2(spec),
+            (_, Some(v)) if v.starts_with("3.0") => spec,
+            (_, Some(v)) if v.starts_with("3.1") => converter.convert_openapi31(spec),
+            (swagger, openapi) => anyhow::bail!(
+                "Unsupported spec version {}, only swagger 2.0 and openapi 3.0 and 3.1 are",
+                swagger.or(openapi).unwrap_or("unknown".to_string())
+            ),
+        };
+        let openapi = serde_json::from_value(spec)
+            .map_err(|e| anyhow::anyhow!("Invalid openapi spec: {}", e))?;
+        Ok((openapi, converter.skipped))
+    }
+
+    fn example(openapi: &OpenAPI, example: &ReferenceOr<openapiv3::Example>) -> Option<Value> {
+        resolve(example, |name| {
+            openapi.components.as_ref()?.examples.get(name)
+        })?
+        .value
+        .clone()
+    }
+
+    fn schema_example(openapi: &OpenAPI, schema: &ReferenceOr<openapiv3::Schema>) -> Option<Value> {
+        let schema = resolve(schema, |name| {
+            openapi.components.as_ref()?.schemas.get(name)
+        })?;
+        schema
+            .schema_data
+            .example
+            .clone()
+            .or_else(|| schema.schema_data.default.clone())
+    }
+
+    /// example of the json media type if any, or of the first one
+    fn content_example(openapi: &OpenAPI, content: &Content) -> Option<(String, Value)> {
+        let (mime, media) = content
+            .iter()
+            .find(|(mime, _)| mime.contains("json"))
+            .or_else(|| content.first())?;
+        let example = media
+            .example
+            .clone()
+            .or_else(|| {
+                media
+                    .examples
+                    .values()
+                    .find_map(|e| Self::example(openapi, e))
+            })
+            .or_else(|| {
+                media
+                    .schema
+                    .as_ref()
+                    .and_then(|s| Self::schema_example(openapi, s))
+            })?;
+        Some((mime.clone(), example))
+    }
+
+    fn parameter_default(openapi: &OpenAPI, data: &ParameterData) -> Option<String> {
+        data.example
+            .clone()
+            .or_else(|| {
+                data.examples
+                    .values()
+                    .find_map(|e| Self::example(openapi, e))
+            })
+            .or_else(|| match &data.format {
+                ParameterSchemaOrContent::Schema(schema) => Self::schema_example(openapi, schema),
+                ParameterSchemaOrContent::Content(content) => {
+                    Self::content_example(openapi, content).map(|(_, v)| v)
+                }
+            })
+            .map(|v| value_to_string(&v))
+    }
+
+    /// headers, query params and conf variables of the first security requirement
+    fn security(openapi: &OpenAPI, op: &Operation) -> (Vec<String>, Vec<String>, Vec<String>) {
+        let (mut headers, mut query_params, mut variables) = (vec![], vec![], vec![]);
+        let requirement = op
+            .security
+            .as_ref()
+            .or(openapi.security.as_ref())
+            .and_then(|requirements| requirements.first());
+        for name in requirement.into_iter().flat_map(|r| r.keys()) {
+            let scheme = openapi
+                .components
+                .as_ref()
+                .and_then(|c| c.security_schemes.get(name))
+                .and_then(|s| {
+                    resolve(s, |name| {
+                        openapi.components.as_ref()?.security_schemes.get(name)
+                    })
+                });
+            match scheme {
+                Some(SecurityScheme::APIKey {
+                    location,
+                    name: key,
+                    ..
+                }) => match location {
+                    APIKeyLocation::Header => headers.push(format!("{}:{{{{{}}}}}", key, name)),
+                    APIKeyLocation::Query => query_params.push(format!("{}:{{{{{}}}}}", key, name)),
+                    APIKeyLocation::Cookie => {
+                        headers.push(format!("Cookie:{}={{{{{}}}}}", key, name))
+                    }
+                },
+                Some(SecurityScheme::HTTP { scheme, .. })
+                    if scheme.eq_ignore_ascii_case("basic") =>
+                {
+                    headers.push(format!("Authorization:Basic {{{{{}}}}}", name))
+                }
+                // bearer, oauth2 and openid connect tokens
+                Some(_) => headers.push(format!("Authorization:Bearer {{{{{}}}}}", name)),
+                None => continue,
+            }
+            variables.push(name.clone());
+        }
+        (headers, query_params, variables)
+    }
+
+    /// path item and operation parameters, the latter overriding the former
+    fn parameters<'o>(
+        openapi: &'o OpenAPI,
+        path_parameters: &'o [ReferenceOr<Parameter>],
+        op: &'o Operation,
+    ) -> Vec<&'o Parameter> {
+        path_parameters
+            .iter()
+            .chain(op.parameters.iter())
+            .filter_map(|p| resolve(p, |name| openapi.components.as_ref()?.parameters.get(name)))
+            .rev()
+            .unique_by(|p| {
+                let data = p.parameter_data_ref();
+                (std::mem::discriminant(*p), data.name.clone())
+            })
+            .collect_vec()
+            .into_iter()
+            .rev()
+            .collect()
+    }
+
+    pub fn get_action(
+        openapi: &OpenAPI,
+        op: &Operation,
+        path: &str,
+        path_parameters: &[ReferenceOr<Parameter>],
+        verb: &str,
+        project_name: &str,
+    ) -> Action {
+        let name = match op.operation_id.as_ref() {
+            Some(operation_id) => operation_id.clone(),
+            None => match slug(path).as_str() {
+                "" => format!("{}-index", verb.to_lowercase()),
+                path => format!("{}-{}", verb.to_lowercase(), path),
+            },
+        };
+        let (mut headers, mut query_params, _) = Self::security(openapi, op);
+        let mut path_params = vec![];
+        for parameter in Self::parameters(openapi, path_parameters, op) {
+            let data = parameter.parameter_data_ref();
+            // optional parameters without default are left out
+            let value = match Self::parameter_default(openapi, data) {
+                Some(value) => value,
+                None if data.required => format!("{{{{{}}}}}", data.name),
+                None => continue,
+            };
+            match parameter {
+                // the {param} slot of the url is kept when there is no default
+                Parameter::Path { .. } if !value.starts_with("{{") => {
+                    path_params.push(format!("{}:{}", data.name, value))
+                }
+                Parameter::Path { .. } => {}
+                Parameter::Query { .. } => query_params.push(format!("{}:{}", data.name, value)),
+                Parameter::Header { .. }
+                    if !SKIPPED_HEADERS.contains(&data.name.to_lowercase().as_str()) =>
+                {
+                    headers.push(format!("{}:{}", data.name, value))
+                }
+                Parameter::Header { .. } | Parameter::Cookie { .. } => {}
+            }
+        }
+
+        let mut run_action_args = RunActionArgs {
+            name: Some(name.clone()),
+            url: Some(path.trim_start_matches('/').to_string()),
             verb: Some(verb.to_string()),
+            path_params: (!path_params.is_empty()).then_some(path_params),
+            query_params: (!query_params.is_empty()).then_some(query_params),
+            header: (!headers.is_empty()).then_some(headers),
             ..Default::default()
         };
+        let request_body = op.request_body.as_ref().and_then(|body| {
+            resolve(body, |name| {
+                openapi.components.as_ref()?.request_bodies.get(name)
+            })
+        });
+        if let Some(request_body) = request_body {
+            let mime = request_body
+                .content
+                .keys()
+                .next()
+                .cloned()
+                .unwrap_or_default();
+            run_action_args.url_encoded = mime.starts_with("application/x-www-form-urlencoded");
+            run_action_args.form_data = mime.starts_with("multipart/form-data");
+        }
+        let body_example = request_body
+            .and_then(|body| Self::content_example(openapi, &body.content))
+            .map(|(_, example)| example);
+        let response_example = op
+            .responses
+            .responses
+            .iter()
+            .find(|(status, _)| match status {
+                StatusCode::Code(code) => (200..300).contains(code),
+                StatusCode::Range(range) => *range == 2,
+            })
+            .and_then(|(_, response)| {
+                resolve(response, |name| {
+                    openapi.components.as_ref()?.responses.get(name)
+                })
+            })
+            .and_then(|response| Self::content_example(openapi, &response.content))
+            .map(|(_, example)| example);
+        let pretty = |v: Value| match v {
+            Value::String(s) => s,
+            v => serde_json::to_string_pretty(&v).unwrap_or_default(),
+        };
 
         Action {
             id: None,
-            name: op
-                .operation_id
-                .clone()
-                .map(|_| format!("{}-{}", verb, path)),
+            name: Some(name),
             run_action_args: serde_json::to_string(&run_action_args).ok(),
+            body_example: body_example.map(pretty),
+            response_example: response_example.map(pretty),
             project_name: Some(project_name.to_string()),
             ..Default::default()
         }
     }
+
+    /// actions of all operations, and the security schemes they use
+    fn to_actions(openapi: &OpenAPI, project: &Project) -> (Vec<Action>, BTreeSet<String>) {
+        let mut actions = vec![];
+        let mut schemes = BTreeSet::new();
+        for (path, item) in openapi.paths.iter() {
+            let Some(item) = item.as_item() else {
+                continue;
+            };
+            for (method, op) in item.iter() {
+                let verb = method.to_uppercase();
+                if Verb::from_str(&verb).is_err() {
+                    eprintln!(
+                        "{}",
+                        format!("{} {} is not supported, skipping it", verb, path).yellow()
+                    );
+                    continue;
+                }
+                schemes.extend(Self::security(openapi, op).2);
+                actions.push(Self::get_action(
+                    openapi,
+                    op,
+                    path,
+                    &item.parameters,
+                    &verb,
+                    &project.name,
+                ));
+            }
+        }
+        (actions, schemes)
+    }
 }
 
 #[async_trait]
 impl<'a> Import for OpenapiV3Importer<'a> {
-    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
-        let openapi: OpenAPI = serde_json::from_str(input)?;
-
-        // upsert project first
-        self.db_handler.upsert_project(project).await?;
-
-        // add all actions
-        for (path, path_item) in openapi.paths {
-            let item = path_item.as_item();
-
-            if let Some(element) = item {
-                let verbs = vec!["GET", "POST", "PUT", "DELETE"];
-                let operations = vec![
-                    element.get.as_ref(),
-                    element.post.as_ref(),
-                    element.put.as_ref(),
-                    element.delete.as_ref(),
-                ];
-
-                for (verb, op) in verbs.iter().zip(operations.iter()) {
-                    if let Some(op) = op {
-                        let action = Self::get_action(op, &path, verb, &project.name);
-                        self.db_handler.upsert_action(&action).await?;
-                    }
-                }
+    async fn actions(&self, input: &str, project: &mut Project) -> anyhow::Result<Vec<Action>> {
+        let (openapi, skipped) = Self::parse(input)?;
+        for part in skipped {
+            eprintln!("{}", format!("Skipping {} of the spec", part).yellow());
+        }
+
+        // the --url option wins over the servers of the spec
+        if project.main_url.is_empty() {
+            project.main_url = openapi.servers.first().map(server_url).unwrap_or_default();
+            if !project.main_url.contains("://") {
+                anyhow::bail!("No absolute server url in the openapi spec, use --url");
             }
         }
-        Ok(())
+
+        let (actions, schemes) = Self::to_actions(&openapi, project);
+        // credentials of the security schemes, to be filled in the project conf
+        let mut conf = project.get_project_conf()?;
+        let missing = schemes
+            .iter()
+            .filter(|scheme| !conf.contains_key(*scheme))
+            .cloned()
+            .collect_vec();
+        for scheme in schemes {
+            conf.entry(scheme).or_default();
+        }
+        if !conf.is_empty() {
+            project.conf = Some(serde_json::to_string(&conf)?);
+        }
+
+        if !missing.is_empty() {
+            println!(
+                "Credentials are expected in the project conf: {}, e.g. -c {}:xxx",
+                missing.join(", ").yellow(),
+                missing[0]
+            );
+        }
+        Ok(actions)
+    }
+
+    async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
+        let actions = self.actions(input, project).await?;
+        save(self.db_handler, project, &actions).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_yaml_to_actions() {
+        let (openapi, _) = OpenapiV3Importer::parse(
+            r#"openapi: 3.0.0
+info:
+  title: Pets
+  version: 1.0.0
+servers:
+  - url: https://{env}.example.com/v1/
+    variables:
+      env:
+        default: api
+security:
+  - bearerAuth: []
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      parameters:
+        - $ref: '#/components/parameters/limit'
+        - name: tag
+          in: query
+          schema:
+            type: string
+      responses:
+        '200':
+          description: pets
+          content:
+            application/json:
+              examples:
+                two:
+                  value: [{"id": 1}, {"id": 2}]
+    post:
+      security:
+        - apiKey: []
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/Pet'
+      responses:
+        201:
+          $ref: '#/components/responses/Created'
+  /pets/{petId}:
+    parameters:
+      - name: petId
+        in: path
+        required: true
+        schema:
+          type: integer
+    patch:
+      operationId: updatePet
+      parameters:
+        - name: X-Request-Id
+          in: header
+          required: true
+          schema:
+            type: string
+      responses:
+        default:
+          description: error
+    head:
+      responses:
+        '200':
+          description: exists
+components:
+  securitySchemes:
+    bearerAuth:
+      type: http
+      scheme: bearer
+    apiKey:
+      type: apiKey
+      in: header
+      name: X-Api-Key
+  parameters:
+    limit:
+      name: limit
+      in: query
+      schema:
+        type: integer
+        default: 20
+  schemas:
+    Pet:
+      type: object
+      example:
+        name: rex
+  responses:
+    Created:
+      description: created
+      content:
+        application/json:
+          example: {"id": 3}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            server_url(&openapi.servers[0]),
+            "https://api.example.com/v1"
+        );
+
+        let project = Project {
+            id: None,
+            name: "pets".to_string(),
+            main_url: server_url(&openapi.servers[0]),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let (actions, schemes) = OpenapiV3Importer::to_actions(&openapi, &project);
+        assert_eq!(
+            schemes.into_iter().collect_vec(),
+            vec!["apiKey", "bearerAuth"]
+        );
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.name.clone().unwrap())
+                .collect_vec(),
+            vec!["listPets", "post-pets", "head-pets-petid", "updatePet"]
+        );
+
+        let list_pets = actions[0].get_run_action_args().unwrap();
+        assert_eq!(list_pets.url.as_deref(), Some("pets"));
+        assert_eq!(list_pets.query_params, Some(vec!["limit:20".to_string()]));
+        assert_eq!(
+            list_pets.header,
+            Some(vec!["Authorization:Bearer {{bearerAuth}}".to_string()])
+        );
+        assert_eq!(
+            actions[0].response_example.as_deref(),
+            Some("[\n  {\n    \"id\": 1\n  },\n  {\n    \"id\": 2\n  }\n]")
+        );
+
+        let post_pets = actions[1].get_run_action_args().unwrap();
+        assert_eq!(
+            post_pets.header,
+            Some(vec!["X-Api-Key:{{apiKey}}".to_string()])
+        );
+        assert_eq!(
+            actions[1].body_example.as_deref(),
+            Some("{\n  \"name\": \"rex\"\n}")
+        );
+        assert_eq!(
+            actions[1].response_example.as_deref(),
+            Some("{\n  \"id\": 3\n}")
+        );
+
+        let pet_exists = actions[2].get_run_action_args().unwrap();
+        assert_eq!(pet_exists.verb.as_deref(), Some("HEAD"));
+        assert_eq!(pet_exists.url.as_deref(), Some("pets/{petId}"));
+
+        let update_pet = actions[3].get_run_action_args().unwrap();
+        assert_eq!(update_pet.verb.as_deref(), Some("PATCH"));
+        assert_eq!(update_pet.url.as_deref(), Some("pets/{petId}"));
+        assert_eq!(update_pet.path_params, None);
+        assert_eq!(
+            update_pet.header,
+            Some(vec![
+                "Authorization:Bearer {{bearerAuth}}".to_string(),
+                "X-Request-Id:{{X-Request-Id}}".to_string()
+            ])
+        );
     }
 }
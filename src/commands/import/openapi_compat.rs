@@ -0,0 +1,597 @@
+use serde_json::{json, Map, Value};
+
+/// Swagger 2.0 parameter fields moved to the parameter schema in openapi 3
+const SCHEMA_FIELDS: [&str; 16] = [
+    "type",
+    "format",
+    "items",
+    "default",
+    "enum",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "multipleOf",
+];
+
+/// Swagger 2.0 definitions renamed in openapi 3
+const REFS: [(&str, &str); 3] = [
+    ("#/definitions/", "#/components/schemas/"),
+    ("#/parameters/", "#/components/parameters/"),
+    ("#/responses/", "#/components/responses/"),
+];
+
+/// openapi version the specs are converted to
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Convert swagger 2.0 and openapi 3.1 specs to openapi 3.0, the parts that
+/// cannot be converted being collected
+#[derive(Default)]
+pub struct Converter {
+    pub skipped: Vec<String>,
+}
+
+fn array(value: Option<&Value>) -> Vec<&str> {
+    value
+        .and_then(Value::as_array)
+        .map(|values| values.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// rename the swagger 2.0 $refs of any nested value
+fn rename_refs(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(reference) if key == "$ref" => {
+                        if let Some((from, to)) =
+                            REFS.iter().find(|(from, _)| reference.starts_with(from))
+                        {
+                            *reference = reference.replacen(from, to, 1);
+                        }
+                    }
+                    value => rename_refs(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(rename_refs),
+        _ => {}
+    }
+}
+
+/// swagger 2.0 file type, openapi 3 binary string
+fn file_to_binary(schema: &mut Map<String, Value>) {
+    if schema.get("type").and_then(Value::as_str) == Some("file") {
+        schema.insert("type".to_string(), json!("string"));
+        schema.insert("format".to_string(), json!("binary"));
+    }
+}
+
+impl Converter {
+    fn skip(&mut self, part: String) {
+        if !self.skipped.contains(&part) {
+            self.skipped.push(part);
+        }
+    }
+
+    /// fields of a swagger 2.0 parameter or header moved to its schema
+    fn with_schema(&mut self, mut object: Map<String, Value>) -> Map<String, Value> {
+        let mut schema = Map::new();
+        for field in SCHEMA_FIELDS {
+            if let Some(value) = object.remove(field) {
+                schema.insert(field.to_string(), value);
+            }
+        }
+        file_to_binary(&mut schema);
+        if let Some(example) = object.remove("x-example") {
+            object.insert("example".to_string(), example);
+        }
+        match object
+            .remove("collectionFormat")
+            .as_ref()
+            .and_then(Value::as_str)
+        {
+            None | Some("csv") => {}
+            Some("multi") => {
+                object.insert("explode".to_string(), json!(true));
+            }
+            Some(format) => self.skip(format!(
+                "{} collection format of {}",
+                format,
+                object
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("a header")
+            )),
+        }
+        if !schema.is_empty() {
+            object.insert("schema".to_string(), Value::Object(schema));
+        }
+        object
+    }
+
+    fn response(&mut self, response: Value, produces: &[&str]) -> Value {
+        let Value::Object(mut response) = response else {
+            return response;
+        };
+        if response.contains_key("$ref") {
+            return Value::Object(response);
+        }
+        let schema = response.remove("schema");
+        let examples = response.remove("examples");
+        let mut content = Map::new();
+        if let Some(schema) = schema {
+            for mime in produces {
+                content.insert(mime.to_string(), json!({ "schema": schema }));
+            }
+        }
+        for (mime, example) in examples.iter().filter_map(Value::as_object).flatten() {
+            let media = content.entry(mime.clone()).or_insert_with(|| json!({}));
+            media["example"] = example.clone();
+        }
+        if !content.is_empty() {
+            response.insert("content".to_string(), Value::Object(content));
+        }
+        if let Some(Value::Object(headers)) = response.remove("headers") {
+            let headers = headers
+                .into_iter()
+                .map(|(name, header)| match header {
+                    Value::Object(header) => (name, Value::Object(self.with_schema(header))),
+                    header => (name, header),
+                })
+                .collect();
+            response.insert("headers".to_string(), Value::Object(headers));
+        }
+        // description is required by openapi 3
+        response.entry("description").or_insert_with(|| json!(""));
+        Value::Object(response)
+    }
+
+    /// form parameters as an object schema, its example being made of their defaults
+    fn form_body(form: Vec<Map<String, Value>>, consumes: &[&str]) -> Value {
+        let is_multipart = consumes.contains(&"multipart/form-data")
+            || form
+                .iter()
+                .any(|p| p.get("type").and_then(Value::as_str) == Some("file"));
+        let (mut properties, mut required, mut example) = (Map::new(), vec![], Map::new());
+        for mut parameter in form {
+            let name = parameter
+                .remove("name")
+                .and_then(|n| n.as_str().map(str::to_string))
+                .unwrap_or_default();
+            if parameter.remove("required") == Some(json!(true)) {
+                required.push(json!(name));
+            }
+            if let Some(default) = parameter
+                .get("x-example")
+                .or_else(|| parameter.get("default"))
+            {
+                example.insert(name.clone(), default.clone());
+            }
+            let mut schema = parameter
+                .into_iter()
+                .filter(|(k, _)| SCHEMA_FIELDS.contains(&k.as_str()) || k == "description")
+                .collect();
+            file_to_binary(&mut schema);
+            properties.insert(name, Value::Object(schema));
+        }
+        let mime = match is_multipart {
+            true => "multipart/form-data",
+            false => "application/x-www-form-urlencoded",
+        };
+        let mut media = json!({
+            "schema": {"type": "object", "properties": properties, "required": required}
+        });
+        if !example.is_empty() {
+            media["example"] = Value::Object(example);
+        }
+        json!({ "content": { mime: media } })
+    }
+
+    /// body and form parameters become a request body, the other ones get a schema
+    fn operation(
+        &mut self,
+        op: Value,
+        parameters: &Map<String, Value>,
+        consumes: &[&str],
+        produces: &[&str],
+    ) -> Value {
+        let Value::Object(mut op) = op else {
+            return op;
+        };
+        let op_consumes = op.remove("consumes");
+        let consumes = match op_consumes.as_ref() {
+            Some(c) => array(Some(c)),
+            None => consumes.to_vec(),
+        };
+        let op_produces = op.remove("produces");
+        let produces = match op_produces.as_ref() {
+            Some(p) => array(Some(p)),
+            None => produces.to_vec(),
+        };
+        if op.remove("schemes").is_some() {
+            self.skip("schemes of operations".to_string());
+        }
+
+        let (mut params, mut form) = (vec![], vec![]);
+        for parameter in array_values(op.remove("parameters")) {
+            // shared body and form parameters are inlined
+            let resolved = parameter
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|r| r.rsplit('/').next())
+                .and_then(|name| parameters.get(name))
+                .filter(|p| matches!(p["in"].as_str(), Some("body" | "formData")))
+                .cloned()
+                .unwrap_or(parameter);
+            let Value::Object(mut parameter) = resolved else {
+                continue;
+            };
+            match parameter.get("in").and_then(Value::as_str) {
+                Some("body") => {
+                    let schema = parameter.remove("schema").unwrap_or_else(|| json!({}));
+                    let content: Map<String, Value> = consumes
+                        .iter()
+                        .map(|mime| (mime.to_string(), json!({ "schema": schema })))
+                        .collect();
+                    let mut body = json!({ "content": content });
+                    if let Some(required) = parameter.remove("required") {
+                        body["required"] = required;
+                    }
+                    op.insert("requestBody".to_string(), body);
+                }
+                Some("formData") => form.push(parameter),
+                Some(_) => params.push(Value::Object(self.with_schema(parameter))),
+                // $ref to a shared query, path or header parameter
+                None => params.push(Value::Object(parameter)),
+            }
+        }
+        if !form.is_empty() {
+            op.insert("requestBody".to_string(), Self::form_body(form, &consumes));
+        }
+        if !params.is_empty() {
+            op.insert("parameters".to_string(), Value::Array(params));
+        }
+        if let Some(Value::Object(responses)) = op.remove("responses") {
+            let responses = responses
+                .into_iter()
+                .map(|(status, r)| (status, self.response(r, &produces)))
+                .collect();
+            op.insert("responses".to_string(), Value::Object(responses));
+        }
+        Value::Object(op)
+    }
+
+    fn security_scheme(&mut self, name: &str, scheme: Value) -> Option<Value> {
+        let Value::Object(mut scheme) = scheme else {
+            return None;
+        };
+        match scheme.get("type").and_then(Value::as_str) {
+            Some("basic") => {
+                scheme.insert("type".to_string(), json!("http"));
+                scheme.insert("scheme".to_string(), json!("basic"));
+            }
+            Some("oauth2") => {
+                let flow = scheme.remove("flow");
+                let mut urls = json!({ "scopes": scheme.remove("scopes").unwrap_or(json!({})) });
+                for url in ["authorizationUrl", "tokenUrl"] {
+                    if let Some(value) = scheme.remove(url) {
+                        urls[url] = value;
+                    }
+                }
+                let flow = match flow.as_ref().and_then(Value::as_str) {
+                    Some("implicit") => "implicit",
+                    Some("password") => "password",
+                    Some("application") => "clientCredentials",
+                    Some("accessCode") => "authorizationCode",
+                    _ => {
+                        self.skip(format!("oauth2 flow of the {} security scheme", name));
+                        return None;
+                    }
+                };
+                scheme.insert("flows".to_string(), json!({ flow: urls }));
+            }
+            _ => {}
+        }
+        Some(Value::Object(scheme))
+    }
+
+    pub fn convert_swagger2(&mut self, mut swagger: Value) -> Value {
+        rename_refs(&mut swagger);
+        let Value::Object(mut swagger) = swagger else {
+            return swagger;
+        };
+        let consumes = swagger.remove("consumes");
+        let consumes = match array(consumes.as_ref()) {
+            c if c.is_empty() => vec!["application/json"],
+            c => c,
+        };
+        let produces = swagger.remove("produces");
+        let produces = match array(produces.as_ref()) {
+            p if p.is_empty() => vec!["application/json"],
+            p => p,
+        };
+        let base_path = swagger
+            .remove("basePath")
+            .and_then(|b| b.as_str().map(|b| b.trim_end_matches('/').to_string()))
+            .unwrap_or_default();
+        let schemes = swagger.remove("schemes");
+        let scheme = array(schemes.as_ref()).first().copied().unwrap_or("https");
+        // without host, the paths keep their base path, the project url being given with --url
+        let (servers, path_prefix) = match swagger.remove("host") {
+            Some(Value::String(host)) => (
+                json!([{ "url": format!("{}://{}{}", scheme, host, base_path) }]),
+                "".to_string(),
+            ),
+            _ => (json!([]), base_path),
+        };
+
+        let parameters = match swagger.remove("parameters") {
+            Some(Value::Object(parameters)) => parameters,
+            _ => Map::new(),
+        };
+        let mut components = Map::new();
+        if let Some(definitions) = swagger.remove("definitions") {
+            components.insert("schemas".to_string(), definitions);
+        }
+        let shared_parameters: Map<String, Value> = parameters
+            .iter()
+            .filter(|(_, p)| !matches!(p["in"].as_str(), Some("body" | "formData")))
+            .filter_map(|(name, p)| Some((name.clone(), p.as_object()?.clone())))
+            .map(|(name, p)| (name, Value::Object(self.with_schema(p))))
+            .collect();
+        components.insert("parameters".to_string(), Value::Object(shared_parameters));
+        if let Some(Value::Object(responses)) = swagger.remove("responses") {
+            let responses = responses
+                .into_iter()
+                .map(|(name, r)| (name, self.response(r, &produces)))
+                .collect();
+            components.insert("responses".to_string(), Value::Object(responses));
+        }
+        if let Some(Value::Object(definitions)) = swagger.remove("securityDefinitions") {
+            let schemes: Map<String, Value> = definitions
+                .into_iter()
+                .filter_map(|(name, s)| Some((name.clone(), self.security_scheme(&name, s)?)))
+                .collect();
+            components.insert("securitySchemes".to_string(), Value::Object(schemes));
+        }
+
+        let mut paths = Map::new();
+        if let Some(Value::Object(swagger_paths)) = swagger.remove("paths") {
+            for (path, item) in swagger_paths {
+                let Value::Object(item) = item else {
+                    continue;
+                };
+                let item = item
+                    .into_iter()
+                    .map(|(key, value)| match key.as_str() {
+                        "parameters" => {
+                            let params = array_values(Some(value))
+                                .into_iter()
+                                .filter_map(|p| match p {
+                                    Value::Object(p) if p.contains_key("in") => {
+                                        if matches!(p["in"].as_str(), Some("body" | "formData")) {
+                                            self.skip(format!("body parameters of {}", path));
+                                            None
+                                        } else {
+                                            Some(Value::Object(self.with_schema(p)))
+                                        }
+                                    }
+                                    p => Some(p),
+                                })
+                                .collect();
+                            (key, Value::Array(params))
+                        }
+                        k if k.starts_with("x-") || k == "$ref" => (key, value),
+                        _ => {
+                            let op = self.operation(value, &parameters, &consumes, &produces);
+                            (key, op)
+                        }
+                    })
+                    .collect();
+                paths.insert(format!("{}{}", path_prefix, path), Value::Object(item));
+            }
+        }
+
+        swagger.remove("swagger");
+        swagger.insert("openapi".to_string(), json!(OPENAPI_VERSION));
+        swagger.insert("servers".to_string(), servers);
+        swagger.insert("paths".to_string(), Value::Object(paths));
+        swagger.insert("components".to_string(), Value::Object(components));
+        Value::Object(swagger)
+    }
+
+    /// json schema keywords of openapi 3.1 expressed with openapi 3.0 ones
+    fn downgrade_schemas(&mut self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::Array(types)) = object.remove("type") {
+                    let null = json!("null");
+                    if types.contains(&null) {
+                        object.insert("nullable".to_string(), json!(true));
+                    }
+                    let types = types.into_iter().filter(|t| *t != null).collect::<Vec<_>>();
+                    match types.as_slice() {
+                        [] => {}
+                        [t] => {
+                            object.insert("type".to_string(), t.clone());
+                        }
+                        types => {
+                            let any_of = types.iter().map(|t| json!({ "type": t })).collect();
+                            object.insert("anyOf".to_string(), Value::Array(any_of));
+                        }
+                    }
+                }
+                if object.get("type") == Some(&json!("null")) {
+                    object.remove("type");
+                    object.insert("nullable".to_string(), json!(true));
+                }
+                if let Some(value) = object.remove("const") {
+                    object.insert("enum".to_string(), json!([value]));
+                }
+                if let Some(Value::Array(mut examples)) = object.remove("examples") {
+                    if !examples.is_empty() {
+                        object.insert("example".to_string(), examples.swap_remove(0));
+                    }
+                }
+                for (bound, limit) in [
+                    ("exclusiveMinimum", "minimum"),
+                    ("exclusiveMaximum", "maximum"),
+                ] {
+                    if let Some(value) = object.get(bound).filter(|v| v.is_number()).cloned() {
+                        object.insert(limit.to_string(), value);
+                        object.insert(bound.to_string(), json!(true));
+                    }
+                }
+                object
+                    .values_mut()
+                    .for_each(|value| self.downgrade_schemas(value));
+            }
+            Value::Array(values) => values
+                .iter_mut()
+                .for_each(|value| self.downgrade_schemas(value)),
+            _ => {}
+        }
+    }
+
+    pub fn convert_openapi31(&mut self, mut openapi: Value) -> Value {
+        self.downgrade_schemas(&mut openapi);
+        let Value::Object(mut openapi) = openapi else {
+            return openapi;
+        };
+        if let Some(Value::Object(webhooks)) = openapi.remove("webhooks") {
+            let names = webhooks.keys().cloned().collect::<Vec<_>>();
+            self.skip(format!("webhooks {}", names.join(", ")));
+        }
+        if let Some(Value::Object(components)) = openapi.get_mut("components") {
+            if components.remove("pathItems").is_some() {
+                self.skip("path items of the components".to_string());
+            }
+        }
+        openapi.remove("jsonSchemaDialect");
+        // paths are optional in openapi 3.1
+        openapi.entry("paths").or_insert_with(|| json!({}));
+        openapi.insert("openapi".to_string(), json!(OPENAPI_VERSION));
+        Value::Object(openapi)
+    }
+}
+
+fn array_values(value: Option<Value>) -> Vec<Value> {
+    match value {
+        Some(Value::Array(values)) => values,
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openapiv3::{OpenAPI, ReferenceOr, SchemaKind, Type};
+
+    use super::*;
+
+    #[test]
+    fn test_swagger2_and_openapi31_conversion() {
+        let swagger = json!({
+            "swagger": "2.0",
+            "info": {"title": "pets", "version": "1"},
+            "host": "api.example.com",
+            "basePath": "/v1",
+            "schemes": ["https"],
+            "securityDefinitions": {"key": {"type": "apiKey", "in": "header", "name": "X-Key"}},
+            "paths": {
+                "/pets/{id}": {
+                    "parameters": [{"name": "id", "in": "path", "required": true, "type": "integer"}],
+                    "put": {
+                        "operationId": "updatePet",
+                        "parameters": [
+                            {"name": "pet", "in": "body", "schema": {"$ref": "#/definitions/Pet"}},
+                            {"$ref": "#/parameters/trace"}
+                        ],
+                        "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}}
+                    }
+                },
+                "/avatars": {
+                    "post": {
+                        "consumes": ["multipart/form-data"],
+                        "parameters": [
+                            {"name": "user", "in": "formData", "type": "string", "default": "marco"},
+                            {"name": "file", "in": "formData", "type": "file"}
+                        ],
+                        "responses": {"201": {"description": "created"}}
+                    }
+                }
+            },
+            "parameters": {"trace": {"name": "X-Trace", "in": "header", "type": "string", "default": "on"}},
+            "definitions": {"Pet": {"type": "object", "example": {"name": "rex"}}}
+        });
+        let mut converter = Converter::default();
+        let openapi: OpenAPI = serde_json::from_value(converter.convert_swagger2(swagger)).unwrap();
+        assert!(converter.skipped.is_empty());
+        assert_eq!(openapi.servers[0].url, "https://api.example.com/v1");
+        assert!(openapi
+            .components
+            .as_ref()
+            .unwrap()
+            .security_schemes
+            .contains_key("key"));
+
+        let update = openapi.paths.paths["/pets/{id}"].as_item().unwrap();
+        assert_eq!(
+            update.parameters[0]
+                .as_item()
+                .unwrap()
+                .parameter_data_ref()
+                .name,
+            "id"
+        );
+        let put = update.put.as_ref().unwrap();
+        let body = put.request_body.as_ref().unwrap().as_item().unwrap();
+        assert!(matches!(
+            body.content["application/json"].schema,
+            Some(ReferenceOr::Reference { ref reference }) if reference == "#/components/schemas/Pet"
+        ));
+        assert!(matches!(
+            put.parameters[0],
+            ReferenceOr::Reference { ref reference } if reference == "#/components/parameters/trace"
+        ));
+
+        let avatars = openapi.paths.paths["/avatars"].as_item().unwrap();
+        let body = avatars
+            .post
+            .as_ref()
+            .unwrap()
+            .request_body
+            .as_ref()
+            .unwrap();
+        let form = &body.as_item().unwrap().content["multipart/form-data"];
+        assert_eq!(form.example, Some(json!({"user": "marco"})));
+
+        let openapi31 = json!({
+            "openapi": "3.1.0",
+            "info": {"title": "pets", "version": "1"},
+            "webhooks": {"newPet": {}},
+            "components": {"schemas": {"Pet": {
+                "type": ["string", "null"],
+                "examples": ["rex"]
+            }}}
+        });
+        let mut converter = Converter::default();
+        let openapi: OpenAPI =
+            serde_json::from_value(converter.convert_openapi31(openapi31)).unwrap();
+        assert_eq!(converter.skipped, vec!["webhooks newPet"]);
+        let pet = openapi.components.unwrap().schemas["Pet"]
+            .clone()
+            .into_item()
+            .unwrap();
+        assert!(pet.schema_data.nullable);
+        assert_eq!(pet.schema_data.example, Some(json!("rex")));
+        assert!(matches!(pet.schema_kind, SchemaKind::Type(Type::String(_))));
+    }
+}
@@ -1,145 +1,713 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use base64::Engine;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
 use crate::commands::run::action::RunActionArgs;
 use crate::db::db_handler::DBHandler;
 use crate::db::dto::{Action, Project};
-use async_recursion::async_recursion;
-use async_trait::async_trait;
-use indicatif::{MultiProgress, ProgressBar};
-use postman_collection::v2_1_0::{self, Url};
-use postman_collection::v2_1_0::{HeaderUnion, Items, RequestUnion};
+use crate::utils::SEP;
 
-use super::import::Import;
+use super::import::{relative_url, save, slug, Import};
 
-fn replace_postman_path(path: &str) -> &str {
-    if let Some(stripped) = path.strip_prefix(':') {
-        stripped
-    } else {
-        path
-    }
+lazy_static! {
+    /// pm.response.to.have.status(200)
+    static ref STATUS: Regex = Regex::new(r"pm\.response\.to\.have\.status\((\d{3})\)").unwrap();
+    /// var json = pm.response.json();
+    static ref JSON_VAR: Regex =
+        Regex::new(r"(?:var|let|const)\s+(\w+)\s*=\s*pm\.response\.json\(\)").unwrap();
+    /// pm.environment.set("token", json.data.token)
+    static ref SET_VAR: Regex = Regex::new(
+        r#"pm\.(?:environment|collectionVariables|globals|variables)\.set\(\s*["']([\w.-]+)["']\s*,\s*(pm\.response\.json\(\)|\w+)((?:\.\w+|\[\d+\])*)\s*\)"#
+    )
+    .unwrap();
+    /// blank lines, comments and pm.test wrappers
+    static ref NOOP: Regex =
+        Regex::new(r#"^\s*(//.*|pm\.test\(.*function\s*\(\)\s*\{|\}\s*\)?;?)?\s*$"#).unwrap();
 }
 
-pub struct PostmanImporter<'a> {
-    pub db_handler: &'a DBHandler,
+/// Postman v2.0 / v2.1 collection, only the parts used by apikrab are deserialized
+#[derive(Deserialize)]
+struct Collection {
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    variable: Vec<KeyValue>,
+    auth: Option<Auth>,
+    #[serde(default)]
+    event: Vec<Event>,
 }
 
-impl<'a> PostmanImporter<'a> {
-    pub fn get_url(url: &Url) -> Option<String> {
-        let r = match url {
-            v2_1_0::Url::String(s) => s.clone(),
-            v2_1_0::Url::UrlClass(u_cls) => u_cls
-                .path
-                .as_ref()
-                .map(|p| match p {
-                    v2_1_0::UrlPath::String(s) => s
-                        .split('/')
-                        .map(|s| replace_postman_path(s).to_string())
-                        .collect::<Vec<String>>(),
-                    v2_1_0::UrlPath::UnionArray(p_cls) => p_cls
-                        .iter()
-                        .map(|p| match p {
-                            v2_1_0::PathElement::String(s) => replace_postman_path(s).to_string(),
-                            v2_1_0::PathElement::PathClass(uv_cls) => {
-                                uv_cls.value.as_ref().unwrap_or(&"".to_string()).to_string()
-                            }
-                        })
-                        .collect(),
-                })
-                .map(|v| v.join("/"))
-                .unwrap_or("".to_string()),
-        };
-        Some(r)
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Item {
+    name: String,
+    /// folder items
+    item: Option<Vec<Item>>,
+    request: Option<Request>,
+    auth: Option<Auth>,
+    event: Vec<Event>,
+    response: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Request {
+    Url(String),
+    Request(Box<RequestClass>),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RequestClass {
+    method: Option<String>,
+    url: Option<Url>,
+    header: Vec<KeyValue>,
+    body: Option<Body>,
+    auth: Option<Auth>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Url {
+    Raw(String),
+    Url(UrlClass),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct UrlClass {
+    raw: Option<String>,
+    protocol: Option<String>,
+    host: Value,
+    path: Value,
+    query: Vec<KeyValue>,
+    variable: Vec<KeyValue>,
+}
+
+/// variables, headers, query params and form fields
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct KeyValue {
+    key: Option<String>,
+    value: Value,
+    disabled: bool,
+    /// environments disable values with enabled: false
+    enabled: Option<bool>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    src: Value,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Body {
+    mode: String,
+    disabled: bool,
+    raw: Option<String>,
+    urlencoded: Vec<KeyValue>,
+    formdata: Vec<KeyValue>,
+    graphql: Option<Graphql>,
+    file: Option<KeyValue>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Graphql {
+    query: Option<String>,
+    /// json text
+    variables: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Auth {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(flatten)]
+    attributes: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Event {
+    listen: String,
+    disabled: bool,
+    script: Option<Script>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Script {
+    exec: Value,
+}
+
+/// Postman environment export
+#[derive(Deserialize)]
+struct Environment {
+    #[serde(default)]
+    values: Vec<KeyValue>,
+}
+
+/// strings are not quoted
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "".to_string(),
+        value => value.to_string(),
+    }
+}
+
+fn lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => s.lines().map(str::to_string).collect(),
+        Value::Array(lines) => lines.iter().map(value_to_string).collect(),
+        _ => vec![],
     }
+}
+
+fn enabled(params: &[KeyValue]) -> impl Iterator<Item = (&str, String)> {
+    params
+        .iter()
+        .filter(|p| !p.disabled && p.enabled != Some(false))
+        .filter_map(|p| Some((p.key.as_deref()?, value_to_string(&p.value))))
+}
 
-    pub fn get_headers(header: &HeaderUnion) -> Option<Vec<String>> {
-        let r = match header {
-            HeaderUnion::HeaderArray(h_cls) => h_cls
+impl Auth {
+    /// attribute of the auth kind, a list of key/value in v2.1 and an object in v2.0
+    fn attribute(&self, key: &str) -> String {
+        let attributes = self.attributes.get(&self.kind);
+        let value = match attributes {
+            Some(Value::Array(attributes)) => attributes
                 .iter()
-                .map(|h| vec![h.key.clone(), h.value.clone()].join(":"))
-                .collect(),
-            HeaderUnion::String(s) => vec![s.clone()],
+                .find(|a| a.get("key").and_then(Value::as_str) == Some(key))
+                .and_then(|a| a.get("value")),
+            Some(Value::Object(attributes)) => attributes.get(key),
+            _ => None,
         };
-        Some(r)
+        value.map(value_to_string).unwrap_or_default()
     }
+}
 
-    pub fn items_to_action(item: &Items, current_name: &str, project_name: &str) -> Option<Action> {
-        match item.request.as_ref()? {
-            RequestUnion::RequestClass(r_cls) => {
-                let run_action_args = RunActionArgs {
-                    url: r_cls.url.as_ref().and_then(Self::get_url),
-                    verb: r_cls.method.clone(),
-                    header: r_cls.header.as_ref().and_then(Self::get_headers),
-                    ..Default::default()
+impl Url {
+    /// url without its query, :param path segments becoming {param}
+    fn path(&self) -> String {
+        let raw = match self {
+            Url::Raw(raw) => raw.clone(),
+            Url::Url(url) => url.raw.clone().unwrap_or_else(|| {
+                let join = |value: &Value, sep: &str| match value {
+                    Value::Array(parts) => parts.iter().map(value_to_string).join(sep),
+                    value => value_to_string(value),
                 };
-                Some(Action {
-                    name: Some(current_name.to_string()),
-                    run_action_args: Some(
-                        serde_json::to_string(&run_action_args).unwrap_or("{}".to_string()),
-                    ),
-                    project_name: Some(project_name.to_string()),
-                    ..Default::default()
-                })
+                let host = join(&url.host, ".");
+                let path = join(&url.path, "/");
+                match url.protocol.as_deref() {
+                    Some(protocol) => format!("{}://{}/{}", protocol, host, path),
+                    None => format!("{}/{}", host, path),
+                }
+            }),
+        };
+        let path = raw.split(['?', '#']).next().unwrap_or_default();
+        path.split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => format!("{{{}}}", param),
+                None => segment.to_string(),
+            })
+            .join("/")
+    }
+}
+
+/// extractions and expectations of a test script, and the lines not translated
+fn translate_script(lines: &[String]) -> (Vec<String>, Vec<String>, usize) {
+    let (mut extract_path, mut expect, mut skipped) = (vec![], vec![], 0);
+    let mut json_vars = vec![];
+    for line in lines {
+        let mut translated = false;
+        if let Some(captures) = JSON_VAR.captures(line) {
+            json_vars.push(captures[1].to_string());
+            translated = true;
+        }
+        for captures in STATUS.captures_iter(line) {
+            expect.push(format!("STATUS:{}", &captures[1]));
+            translated = true;
+        }
+        for captures in SET_VAR.captures_iter(line) {
+            let source = &captures[2];
+            if source == "pm.response.json()" || json_vars.iter().any(|v| v == source) {
+                extract_path.push(format!("${}:{}", &captures[3], &captures[1]));
+                translated = true;
             }
-            RequestUnion::String(_) => None,
+        }
+        if !translated && !NOOP.is_match(line) {
+            skipped += 1;
         }
     }
+    (extract_path, expect, skipped)
+}
 
-    #[async_recursion]
-    pub async fn insert_actions(
-        &self,
-        items: &[Items],
+/// Import a Postman v2.0 or v2.1 collection, folders being action name prefixes
+pub struct PostmanImporter<'a> {
+    pub db_handler: &'a DBHandler,
+    /// environment file added to the project conf
+    pub env: Option<String>,
+}
+
+impl<'a> PostmanImporter<'a> {
+    /// scripts of a collection, folder or request, the simple test ones being translated
+    fn scripts(
+        events: &[Event],
         name: &str,
-        project_name: &str,
-        p: Option<ProgressBar>,
+        run_action_args: Option<&mut RunActionArgs>,
+        report: &mut Vec<String>,
     ) {
-        let mut m: Option<MultiProgress> = None;
-        if p.is_none() {
-            m = Some(MultiProgress::new());
+        let mut run_action_args = run_action_args;
+        for event in events.iter().filter(|e| !e.disabled) {
+            let lines = lines(
+                event
+                    .script
+                    .as_ref()
+                    .map(|s| &s.exec)
+                    .unwrap_or(&Value::Null),
+            );
+            if lines.iter().all(|l| NOOP.is_match(l)) {
+                continue;
+            }
+            match (event.listen.as_str(), run_action_args.as_deref_mut()) {
+                ("test", Some(args)) => {
+                    let (extract_path, expect, skipped) = translate_script(&lines);
+                    if !extract_path.is_empty() {
+                        args.extract_path = Some(extract_path);
+                    }
+                    if !expect.is_empty() {
+                        args.expect = Some(expect);
+                    }
+                    if skipped > 0 {
+                        report.push(format!(
+                            "test script of {}: {} lines not translated",
+                            name, skipped
+                        ));
+                    }
+                }
+                ("test", None) => report.push(format!("test script of {}", name)),
+                _ => report.push(format!("pre-request script of {}", name)),
+            }
         }
-        let p1 = p.unwrap_or_else(|| {
-            m.as_ref()
-                .unwrap()
-                .add(indicatif::ProgressBar::new(items.len() as u64))
-        });
-        for (i, item) in items.iter().enumerate() {
-            let fallback = &item
-                .name
-                .as_ref()
-                .map(|n| n.to_ascii_lowercase().replace(' ', "-"))
-                .unwrap_or(format!("{}-{}", name, i));
-            let name: &String = item.id.as_ref().unwrap_or(fallback);
-            if item.item.is_some() {
-                let sub_p = m.as_ref().unwrap().add(indicatif::ProgressBar::new(
-                    item.item.as_ref().unwrap().len() as u64,
+    }
+
+    fn authorization(
+        auth: &Auth,
+        name: &str,
+        headers: &mut Vec<String>,
+        query_params: &mut Vec<String>,
+    ) {
+        match auth.kind.as_str() {
+            "noauth" => {}
+            "bearer" => headers.push(format!("Authorization:Bearer {}", auth.attribute("token"))),
+            "apikey" => {
+                let key = match auth.attribute("key") {
+                    key if key.is_empty() => "X-API-Key".to_string(),
+                    key => key,
+                };
+                let param = format!("{}:{}", key, auth.attribute("value"));
+                match auth.attribute("in").as_str() {
+                    "query" => query_params.push(param),
+                    _ => headers.push(param),
+                }
+            }
+            // credentials are encoded at import, vars can not be resolved later
+            "basic"
+                if !auth.attribute("username").contains("{{")
+                    && !auth.attribute("password").contains("{{") =>
+            {
+                let credentials = format!(
+                    "{}:{}",
+                    auth.attribute("username"),
+                    auth.attribute("password")
+                );
+                headers.push(format!(
+                    "Authorization:Basic {}",
+                    base64::engine::general_purpose::STANDARD.encode(credentials)
                 ));
-                self.insert_actions(item.item.as_ref().unwrap(), name, project_name, Some(sub_p))
-                    .await;
-            } else if let Some(action) = Self::items_to_action(item, name, project_name) {
-                let r = self.db_handler.upsert_action(&action).await;
-                if r.is_err() {
-                    println!("error upserting action: {}", r.err().unwrap());
+            }
+            kind => eprintln!(
+                "{}",
+                format!("{} authentication of {} is not imported", kind, name).yellow()
+            ),
+        }
+    }
+
+    fn to_action(
+        item: &Item,
+        name: &str,
+        auth: Option<&Auth>,
+        variables: &HashMap<String, String>,
+        project: &Project,
+        report: &mut Vec<String>,
+    ) -> Option<Action> {
+        let default = RequestClass::default();
+        let raw;
+        let (request, url) = match item.request.as_ref()? {
+            Request::Url(url) => {
+                raw = Url::Raw(url.clone());
+                (&default, Some(&raw))
+            }
+            Request::Request(request) => (request.as_ref(), request.url.as_ref()),
+        };
+        let (mut query_params, mut path_params) = (vec![], vec![]);
+        match url {
+            Some(Url::Url(url)) => {
+                query_params.extend(enabled(&url.query).map(|(k, v)| format!("{}:{}", k, v)));
+                path_params.extend(
+                    enabled(&url.variable)
+                        .filter(|(_, v)| !v.is_empty())
+                        .map(|(k, v)| format!("{}:{}", k, v)),
+                );
+            }
+            Some(Url::Raw(raw)) => {
+                let query = raw.split_once('?').map(|(_, q)| q).unwrap_or_default();
+                query_params.extend(
+                    url::form_urlencoded::parse(query.as_bytes())
+                        .map(|(k, v)| format!("{}:{}", k, v)),
+                );
+            }
+            None => {}
+        }
+        let url = url.map(Url::path).unwrap_or_default();
+        let mut run_action_args = RunActionArgs {
+            name: Some(name.to_string()),
+            url: Some(relative_url(&url, variables, &project.main_url)),
+            verb: Some(request.method.as_deref().unwrap_or("GET").to_uppercase()),
+            path_params: (!path_params.is_empty()).then_some(path_params),
+            ..Default::default()
+        };
+
+        let body = request.body.as_ref().filter(|b| !b.disabled);
+        let mut body_example = None;
+        if let Some(body) = body {
+            let fields = |params: &[KeyValue]| {
+                params
+                    .iter()
+                    .filter(|p| !p.disabled)
+                    .filter_map(|p| {
+                        let key = p.key.as_deref()?;
+                        Some(match (p.kind.as_deref(), &p.src) {
+                            (Some("file"), Value::String(src)) => format!("{}:@{}", key, src),
+                            _ => format!("{}:{}", key, value_to_string(&p.value)),
+                        })
+                    })
+                    .join(SEP)
+            };
+            match body.mode.as_str() {
+                "raw" => {
+                    let raw = body.raw.clone().unwrap_or_default();
+                    if !raw.trim().is_empty() {
+                        run_action_args.body = Some(vec![raw.clone()]);
+                        body_example = Some(raw);
+                    }
+                }
+                "urlencoded" => {
+                    run_action_args.body = Some(vec![fields(&body.urlencoded)]);
+                    run_action_args.url_encoded = true;
+                }
+                "formdata" => {
+                    run_action_args.body = Some(vec![fields(&body.formdata)]);
+                    run_action_args.form_data = true;
+                }
+                "graphql" => {
+                    let graphql = body.graphql.as_ref();
+                    run_action_args.query = graphql.and_then(|g| g.query.clone());
+                    run_action_args.variables = graphql
+                        .and_then(|g| g.variables.clone())
+                        .filter(|v| !v.trim().is_empty());
+                }
+                "file" => {
+                    if let Some(Value::String(src)) = body.file.as_ref().map(|f| &f.src) {
+                        run_action_args.body = Some(vec![format!("@{}", src)]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut headers = enabled(&request.header)
+            // the multipart boundary is generated when sending
+            .filter(|(k, _)| !run_action_args.form_data || !k.eq_ignore_ascii_case("content-type"))
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect_vec();
+        let auth = request.auth.as_ref().or(auth);
+        if let Some(auth) = auth {
+            Self::authorization(auth, name, &mut headers, &mut query_params);
+        }
+        run_action_args.header = (!headers.is_empty()).then_some(headers);
+        run_action_args.query_params = (!query_params.is_empty()).then_some(query_params);
+        Self::scripts(&item.event, name, Some(&mut run_action_args), report);
+
+        let response_example = item
+            .response
+            .iter()
+            .find_map(|r| r.get("body").and_then(Value::as_str))
+            .map(str::to_string);
+        Some(Action {
+            name: Some(name.to_string()),
+            run_action_args: serde_json::to_string(&run_action_args).ok(),
+            body_example,
+            response_example,
+            project_name: Some(project.name.clone()),
+            ..Default::default()
+        })
+    }
+
+    /// actions of the items and their folders, the closest auth being inherited
+    #[allow(clippy::too_many_arguments)]
+    fn items_to_actions(
+        items: &[Item],
+        folders: &[&str],
+        auth: Option<&Auth>,
+        variables: &HashMap<String, String>,
+        project: &Project,
+        actions: &mut Vec<Action>,
+        report: &mut Vec<String>,
+    ) {
+        for item in items {
+            let mut path = folders.to_vec();
+            path.push(&item.name);
+            let name = slug(&path.join(" "));
+            let auth = item.auth.as_ref().or(auth);
+            match item.item.as_ref() {
+                Some(sub_items) => {
+                    Self::scripts(&item.event, &name, None, report);
+                    Self::items_to_actions(
+                        sub_items, &path, auth, variables, project, actions, report,
+                    )
                 }
+                None => actions.extend(Self::to_action(
+                    item, &name, auth, variables, project, report,
+                )),
             }
-            p1.inc(1);
         }
     }
+
+    /// collection variables extended with the environment ones
+    fn variables(
+        collection: &Collection,
+        environment: Option<&Environment>,
+    ) -> HashMap<String, String> {
+        enabled(&collection.variable)
+            .chain(environment.into_iter().flat_map(|e| enabled(&e.values)))
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    fn to_actions(
+        collection: &Collection,
+        variables: &HashMap<String, String>,
+        project: &Project,
+    ) -> (Vec<Action>, Vec<String>) {
+        let (mut actions, mut report) = (vec![], vec![]);
+        Self::scripts(&collection.event, "the collection", None, &mut report);
+        Self::items_to_actions(
+            &collection.item,
+            &[],
+            collection.auth.as_ref(),
+            variables,
+            project,
+            &mut actions,
+            &mut report,
+        );
+        (actions, report)
+    }
 }
 
 #[async_trait]
 impl<'a> Import for PostmanImporter<'a> {
+    async fn actions(&self, input: &str, project: &mut Project) -> anyhow::Result<Vec<Action>> {
+        let collection = serde_json::from_str::<Collection>(input)
+            .map_err(|e| anyhow::anyhow!("Invalid postman collection: {}", e))?;
+        let environment = match self.env.as_deref() {
+            Some(env) => Some(
+                serde_json::from_str::<Environment>(&self.load(env).await?)
+                    .map_err(|e| anyhow::anyhow!("Invalid postman environment: {}", e))?,
+            ),
+            None => None,
+        };
+        let mut variables = Self::variables(&collection, environment.as_ref());
+        let (actions, report) = Self::to_actions(&collection, &variables, project);
+        // conf given on the command line wins
+        variables.extend(project.get_project_conf()?);
+        if !variables.is_empty() {
+            project.conf = Some(serde_json::to_string(&variables)?);
+        }
+
+        if !report.is_empty() {
+            eprintln!("{}", "Scripts not translated:".yellow());
+            for script in report {
+                eprintln!("{}", format!("  {}", script).yellow());
+            }
+        }
+        Ok(actions)
+    }
+
     async fn import(&self, input: &str, project: &mut Project) -> anyhow::Result<()> {
-        let spec = serde_json::from_str::<v2_1_0::Spec>(input);
-        if spec.is_err() {
-            return Err(anyhow::anyhow!(
-                "Error loading file or url: {}",
-                spec.err().unwrap()
-            ));
+        let actions = self.actions(input, project).await?;
+        save(self.db_handler, project, &actions).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postman_to_actions() {
+        let collection = serde_json::from_str::<Collection>(
+            r#"{
+  "info": {"name": "users", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"},
+  "variable": [{"key": "baseUrl", "value": "https://api.example.com"}, {"key": "page", "value": 1}],
+  "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]},
+  "item": [
+    {
+      "name": "Users",
+      "event": [{"listen": "prerequest", "script": {"exec": ["pm.variables.set('ts', Date.now());"]}}],
+      "item": [
+        {
+          "name": "Get user",
+          "request": {
+            "method": "GET",
+            "url": {
+              "raw": "{{baseUrl}}/users/:id?expand=roles",
+              "host": ["{{baseUrl}}"],
+              "path": ["users", ":id"],
+              "query": [{"key": "expand", "value": "roles"}, {"key": "debug", "value": "1", "disabled": true}],
+              "variable": [{"key": "id", "value": "42"}]
+            }
+          },
+          "event": [{"listen": "test", "script": {"exec": [
+            "pm.test(\"ok\", function () {",
+            "    pm.response.to.have.status(200);",
+            "});",
+            "var json = pm.response.json();",
+            "pm.environment.set(\"email\", json.data[0].email);",
+            "console.log(json);"
+          ]}}],
+          "response": [{"name": "ok", "code": 200, "body": "{\"id\": 42}"}]
         }
-        let collection = spec.unwrap();
+      ]
+    },
+    {
+      "name": "Login",
+      "request": {
+        "method": "POST",
+        "auth": {"type": "apikey", "apikey": [{"key": "key", "value": "X-Key"}, {"key": "value", "value": "{{apiKey}}"}]},
+        "header": [{"key": "Content-Type", "value": "application/x-www-form-urlencoded"}],
+        "body": {"mode": "urlencoded", "urlencoded": [{"key": "user", "value": "marco"}, {"key": "password", "value": "{{password}}"}]},
+        "url": "{{baseUrl}}/login"
+      }
+    },
+    {
+      "name": "Avatar",
+      "request": {
+        "method": "POST",
+        "header": [{"key": "Content-Type", "value": "multipart/form-data"}],
+        "body": {"mode": "formdata", "formdata": [{"key": "file", "type": "file", "src": "./me.png"}, {"key": "user", "value": "42", "type": "text"}]},
+        "url": "{{baseUrl}}/avatars"
+      }
+    },
+    {
+      "name": "Me",
+      "request": {
+        "method": "POST",
+        "body": {"mode": "graphql", "graphql": {"query": "{ me { id } }", "variables": "{\"x\": 1}"}},
+        "url": "{{baseUrl}}/graphql"
+      }
+    }
+  ]
+}"#,
+        )
+        .unwrap();
+        let environment = serde_json::from_str::<Environment>(
+            r#"{"name": "prod", "values": [{"key": "token", "value": "abc", "enabled": true}, {"key": "page", "value": "2", "enabled": true}]}"#,
+        )
+        .unwrap();
+        let variables = PostmanImporter::variables(&collection, Some(&environment));
+        assert_eq!(variables["baseUrl"], "https://api.example.com");
+        assert_eq!(variables["page"], "2");
+        assert_eq!(variables["token"], "abc");
+
+        let project = Project {
+            id: None,
+            name: "users".to_string(),
+            main_url: "https://api.example.com".to_string(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let (actions, report) = PostmanImporter::to_actions(&collection, &variables, &project);
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.name.clone().unwrap())
+                .collect_vec(),
+            vec!["users-get-user", "login", "avatar", "me"]
+        );
+        assert_eq!(
+            report,
+            vec![
+                "pre-request script of users",
+                "test script of users-get-user: 1 lines not translated"
+            ]
+        );
+
+        let get_user = actions[0].get_run_action_args().unwrap();
+        assert_eq!(get_user.url.as_deref(), Some("users/{id}"));
+        assert_eq!(get_user.path_params, Some(vec!["id:42".to_string()]));
+        assert_eq!(
+            get_user.query_params,
+            Some(vec!["expand:roles".to_string()])
+        );
+        assert_eq!(
+            get_user.header,
+            Some(vec!["Authorization:Bearer {{token}}".to_string()])
+        );
+        assert_eq!(get_user.expect, Some(vec!["STATUS:200".to_string()]));
+        assert_eq!(
+            get_user.extract_path,
+            Some(vec!["$.data[0].email:email".to_string()])
+        );
+        assert_eq!(actions[0].response_example.as_deref(), Some("{\"id\": 42}"));
+
+        let login = actions[1].get_run_action_args().unwrap();
+        assert!(login.url_encoded);
+        assert_eq!(
+            login.body,
+            Some(vec!["user:marco;password:{{password}}".to_string()])
+        );
+        assert_eq!(
+            login.header,
+            Some(vec![
+                "Content-Type:application/x-www-form-urlencoded".to_string(),
+                "X-Key:{{apiKey}}".to_string()
+            ])
+        );
 
-        // upsert project first
-        self.db_handler.upsert_project(project).await?;
+        let avatar = actions[2].get_run_action_args().unwrap();
+        assert!(avatar.form_data);
+        assert_eq!(
+            avatar.header,
+            Some(vec!["Authorization:Bearer {{token}}".to_string()])
+        );
+        assert_eq!(
+            avatar.body,
+            Some(vec!["file:@./me.png;user:42".to_string()])
+        );
 
-        self.insert_actions(&collection.item, &collection.info.name, &project.name, None)
-            .await;
-        Ok(())
+        let me = actions[3].get_run_action_args().unwrap();
+        assert_eq!(me.query.as_deref(), Some("{ me { id } }"));
+        assert_eq!(me.variables.as_deref(), Some("{\"x\": 1}"));
     }
 }
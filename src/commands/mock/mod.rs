@@ -0,0 +1,117 @@
+mod route;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Args;
+use colored::Colorize;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+
+use crate::db::db_handler::DBHandler;
+use route::{find_route, MockRoute};
+
+#[derive(Args)]
+pub struct MockArgs {
+    /// Project name
+    project_name: String,
+
+    /// port to listen on
+    #[arg(short, long, default_value_t = 8080)]
+    port: u16,
+
+    /// status of the mocked responses
+    #[arg(short, long, default_value_t = 200)]
+    status: u16,
+
+    /// latency added to each response in milliseconds
+    #[arg(short, long)]
+    latency: Option<u64>,
+}
+
+/// Reply with the response example of the matching action
+fn respond(routes: &[MockRoute], status: u16, request: &Request<Body>) -> Response<Body> {
+    let (verb, path) = (request.method().as_str(), request.uri().path());
+    let Some(route) = find_route(routes, verb, path) else {
+        println!("{} {} {}", verb.yellow(), path, "no matching action".red());
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "error": format!("No action matches {} {}", verb, path) })
+                    .to_string(),
+            ))
+            .unwrap();
+    };
+    println!("{} {} {}", verb.yellow(), path, route.name.green());
+
+    let response = route.response.clone().unwrap_or_default();
+    let content_type = if serde_json::from_str::<serde_json::Value>(&response).is_ok() {
+        "application/json"
+    } else {
+        "text/plain"
+    };
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, content_type)
+        .body(Body::from(response))
+        .unwrap_or_else(|e| {
+            let mut response = Response::new(Body::from(e.to_string()));
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        })
+}
+
+impl MockArgs {
+    /// Serve the response examples of the project actions until interrupted
+    pub async fn serve(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let project = db_handler.get_project(&self.project_name).await?;
+        let routes = db_handler
+            .get_actions(Some(&project.name))
+            .await?
+            .iter()
+            .filter_map(|action| MockRoute::from_action(action, &project.main_url))
+            .collect::<Vec<_>>();
+        routes.iter().for_each(|r| {
+            println!(
+                "  {} /{}{}",
+                r.verb.yellow(),
+                r.segments.join("/"),
+                if r.response.is_none() {
+                    " (no response example)".bright_black().to_string()
+                } else {
+                    "".to_string()
+                }
+            )
+        });
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
+        let (status, latency) = (self.status, self.latency);
+        let routes = Arc::new(routes);
+        let make_service = make_service_fn(move |_| {
+            let routes = routes.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let routes = routes.clone();
+                    async move {
+                        if let Some(latency) = latency {
+                            tokio::time::sleep(Duration::from_millis(latency)).await;
+                        }
+                        Ok::<_, Infallible>(respond(&routes, status, &request))
+                    }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&addr)?.serve(make_service);
+        println!(
+            "Mock server listening on {}",
+            format!("http://{}", addr).blue()
+        );
+        server.await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::db::dto::Action;
+use crate::utils::{SINGLE_INTERPOL_END, SINGLE_INTERPOL_START};
+use reqwest::Url;
+
+/// Path segments of an url, without scheme, host and query
+pub fn path_segments(url: &str) -> Vec<String> {
+    let path = if url.contains("://") {
+        Url::parse(url)
+            .map(|u| u.path().to_string())
+            .unwrap_or_default()
+    } else {
+        url.split('?').next().unwrap_or_default().to_string()
+    };
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// A saved action served by the mock server
+#[derive(Debug)]
+pub struct MockRoute {
+    pub name: String,
+    pub verb: String,
+    /// path segments of the project url, optional in requests
+    pub prefix: Vec<String>,
+    /// path segments of the action url, {param} matching any segment
+    pub segments: Vec<String>,
+    pub response: Option<String>,
+}
+
+impl MockRoute {
+    pub fn from_action(action: &Action, project_url: &str) -> Option<MockRoute> {
+        let run_action_args = action.get_run_action_args().ok()?;
+        Some(MockRoute {
+            name: action.name.clone()?,
+            verb: run_action_args.verb?,
+            prefix: path_segments(project_url),
+            segments: path_segments(run_action_args.url.as_deref().unwrap_or_default()),
+            response: action.response_example.clone(),
+        })
+    }
+
+    fn is_param(segment: &str) -> bool {
+        segment.starts_with(SINGLE_INTERPOL_START) && segment.ends_with(SINGLE_INTERPOL_END)
+    }
+
+    fn matches_segments(&self, path: &[&str]) -> bool {
+        path.len() == self.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(path)
+                .all(|(expected, s)| Self::is_param(expected) || expected == s)
+    }
+
+    /// match verb and path, the project url path being optional
+    pub fn matches(&self, verb: &str, path: &str) -> bool {
+        if !self.verb.eq_ignore_ascii_case(verb) {
+            return false;
+        }
+        let path = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let without_prefix = path
+            .starts_with(&self.prefix.iter().map(String::as_str).collect::<Vec<_>>())
+            .then(|| &path[self.prefix.len()..]);
+        self.matches_segments(&path) || without_prefix.is_some_and(|p| self.matches_segments(p))
+    }
+}
+
+/// Routes with fewer path params are preferred, /users/me wins over /users/{id}
+pub fn find_route<'a>(routes: &'a [MockRoute], verb: &str, path: &str) -> Option<&'a MockRoute> {
+    routes
+        .iter()
+        .filter(|r| r.matches(verb, path))
+        .min_by_key(|r| r.segments.iter().filter(|s| MockRoute::is_param(s)).count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(name: &str, verb: &str, url: &str) -> MockRoute {
+        MockRoute {
+            name: name.to_string(),
+            verb: verb.to_string(),
+            prefix: path_segments("https://api.example.com/v1"),
+            segments: path_segments(url),
+            response: None,
+        }
+    }
+
+    #[test]
+    fn test_find_route() {
+        let routes = [
+            route("get-user", "GET", "users/{id}"),
+            route("get-me", "GET", "users/me"),
+            route("create-user", "POST", "users"),
+        ];
+        let name = |verb, path| find_route(&routes, verb, path).map(|r| r.name.as_str());
+        assert_eq!(name("GET", "/users/42"), Some("get-user"));
+        assert_eq!(name("GET", "/v1/users/42"), Some("get-user"));
+        assert_eq!(name("GET", "/users/me"), Some("get-me"));
+        assert_eq!(name("POST", "/users/"), Some("create-user"));
+        assert_eq!(name("DELETE", "/users/42"), None);
+    }
+}
@@ -1,5 +1,10 @@
+pub(crate) mod db;
+pub(crate) mod export;
 pub(crate) mod history;
 pub(crate) mod import;
+pub(crate) mod mock;
 pub(crate) mod project;
+pub(crate) mod record;
 pub(crate) mod run;
 pub(crate) mod ts;
+pub(crate) mod workspace;
@@ -18,7 +18,7 @@ pub struct AddActionArgs {
     pub url: String,
 
     /// verb of the action
-    #[arg(short, long, value_parser = ["GET", "POST", "PUT", "DELETE"])]
+    #[arg(short, long, value_parser = ["GET", "POST", "PUT", "PATCH", "DELETE", "WS", "GRPC"])]
     pub verb: String,
 
     /// maybe a static body
@@ -1,6 +1,12 @@
-//use crate::commands::import::openapi::Import;
+use crate::commands::import::bruno::BrunoImporter;
+use crate::commands::import::graphql::GraphqlImporter;
+use crate::commands::import::import::{save, Import};
+use crate::commands::import::insomnia::InsomniaImporter;
+use crate::commands::import::openapi::OpenapiV3Importer;
+use crate::commands::import::postman::PostmanImporter;
 use crate::db;
 use crate::db::db_handler::DBHandler;
+use crate::db::dto::{ProjectSource, SourceKind};
 use clap::Args;
 
 #[derive(Args)]
@@ -8,62 +14,102 @@ pub struct CreateProjectArgs {
     /// project name unique
     pub name: String,
 
-    /// prod url for this project
-    #[arg(short, long)]
-    pub url: String,
+    /// prod url for this project, the first server of an openapi spec if missing
+    #[arg(short, long, required_unless_present = "from_openapi")]
+    pub url: Option<String>,
 
     /// Possible configuration for this project
     #[arg(short, long)]
     pub conf: Option<Vec<String>>,
 
     /// url or path to openapi file
-    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    #[arg(long, value_hint = clap::ValueHint::FilePath, group = "importer")]
     pub from_openapi: Option<String>,
 
     /// url or path to postman collection file
-    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    #[arg(long, value_hint = clap::ValueHint::FilePath, group = "importer")]
     pub from_postman: Option<String>,
+
+    /// url or path to graphql schema introspection file
+    #[arg(long, value_hint = clap::ValueHint::FilePath, group = "importer")]
+    pub from_graphql: Option<String>,
+
+    /// url or path to insomnia v4 export file
+    #[arg(long, value_hint = clap::ValueHint::FilePath, group = "importer")]
+    pub from_insomnia: Option<String>,
+
+    /// path to bruno collection folder
+    #[arg(long, value_hint = clap::ValueHint::DirPath, group = "importer")]
+    pub from_bruno: Option<String>,
+
+    /// insomnia or bruno environment name, or postman environment file, added to the project conf
+    #[arg(long)]
+    pub env: Option<String>,
+}
+
+/// files are stored with their absolute path, urls as is
+fn absolute(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// importer of a project source
+pub fn get_importer<'a>(
+    source: &ProjectSource,
+    db_handler: &'a DBHandler,
+) -> Box<dyn Import + Sync + 'a> {
+    let env = source.env.clone();
+    match source.kind {
+        SourceKind::Openapi => Box::new(OpenapiV3Importer { db_handler }),
+        SourceKind::Postman => Box::new(PostmanImporter { db_handler, env }),
+        SourceKind::Graphql => Box::new(GraphqlImporter { db_handler }),
+        SourceKind::Insomnia => Box::new(InsomniaImporter { db_handler, env }),
+        SourceKind::Bruno => Box::new(BrunoImporter { db_handler, env }),
+    }
 }
 
 impl CreateProjectArgs {
-    /*
-    pub fn get_importer<'a>(
-        &'a self,
-        db_handler: &'a DBHandler,
-    ) -> Option<(Box<dyn Import + Sync + 'a>, &'a str)> {
-        #[allow(clippy::manual_map)]
-        if self.from_openapi.is_some() && self.from_postman.is_some() {
-            return None;
-        }
-        if self.from_openapi.is_some() {
-            return Some((
-                Box::new(commands::import::openapi::OpenapiV3Importer { db_handler }),
-                self.from_openapi.as_ref().unwrap(),
-            ));
-        }
-        if self.from_postman.is_some() {
-            return Some((
-                Box::new(commands::import::postman::PostmanImporter { db_handler }),
-                self.from_postman.as_ref().unwrap(),
-            ));
-        }
-        None
+    /// spec or collection given with one of the --from-* options
+    pub fn get_source(&self) -> Option<ProjectSource> {
+        let (kind, path) = [
+            (SourceKind::Openapi, &self.from_openapi),
+            (SourceKind::Postman, &self.from_postman),
+            (SourceKind::Graphql, &self.from_graphql),
+            (SourceKind::Insomnia, &self.from_insomnia),
+            (SourceKind::Bruno, &self.from_bruno),
+        ]
+        .into_iter()
+        .find_map(|(kind, path)| Some((kind, path.as_deref()?)))?;
+        // postman environments are files, other ones are names
+        let env = match kind {
+            SourceKind::Postman => self.env.as_deref().map(absolute),
+            _ => self.env.clone(),
+        };
+        Some(ProjectSource {
+            kind,
+            path: absolute(path),
+            env,
+            actions: vec![],
+        })
     }
-    */
+
     pub async fn create(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
-        /*
-        match self.get_importer(db_handler) {
-            Some((importer, path)) => {
-                let mut project = self.into();
-                let content = importer.load(path).await?;
-                importer.import(&content, &mut project).await?;
+        let mut project: db::dto::Project = self.into();
+        match self.get_source() {
+            Some(mut source) => {
+                let importer = get_importer(&source, db_handler);
+                let content = importer.load(&source.path).await?;
+                let actions = importer.actions(&content, &mut project).await?;
+                // imported actions are the ones a sync may update or remove
+                source.actions = actions.iter().filter_map(|a| a.name.clone()).collect();
+                project.source = Some(serde_json::to_string(&source)?);
+                save(db_handler, &project, &actions).await
             }
             None => {
-            */
-        let project: db::dto::Project = self.into();
-        db_handler.upsert_project(&project).await?;
-        //}
-        //}
-        Ok(())
+                db_handler.upsert_project(&project).await?;
+                Ok(())
+            }
+        }
     }
 }
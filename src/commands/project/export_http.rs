@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use clap::Args;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+
+use crate::commands::export::snippet::SnippetFormat;
+use crate::commands::export::snippet_request;
+use crate::commands::run::_run_helper::get_computed_urls;
+use crate::db::db_handler::DBHandler;
+
+/// variable holding the project url in exported files
+const BASE_URL_VAR: &str = "baseUrl";
+
+#[derive(Args)]
+pub struct ExportHttpArgs {
+    /// project name
+    pub project_name: String,
+
+    /// output file, e.g. api.http, stdout if missing
+    #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+}
+
+impl ExportHttpArgs {
+    pub async fn export(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let project = db_handler.get_project(&self.project_name).await?;
+        let actions = db_handler.get_actions(Some(&self.project_name)).await?;
+        // action urls are prefixed by the completed project url
+        let base_url = get_computed_urls("", Some(&project.main_url), "", &HashMap::new())
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        let mut variables = vec![format!("@{} = {}", BASE_URL_VAR, project.main_url)];
+        variables.extend(
+            project
+                .get_project_conf()?
+                .iter()
+                .filter(|(k, _)| k.as_str() != BASE_URL_VAR)
+                .sorted()
+                .map(|(k, v)| format!("@{} = {}", k, v)),
+        );
+        let mut requests = vec![];
+        for action in actions.iter().sorted_by_key(|a| a.name.clone()) {
+            let name = action.name.clone().unwrap_or_default();
+            // {{vars}} are kept, the project conf being exported as variables
+            match snippet_request(action, Some(&project), &HashMap::new()) {
+                Ok(mut request) => {
+                    if let Some(path) = request.url.strip_prefix(&base_url) {
+                        request.url = format!("{{{{{}}}}}{}", BASE_URL_VAR, path);
+                    }
+                    requests.push(format!(
+                        "### {}\n{}\n",
+                        name,
+                        request.render(SnippetFormat::Http)
+                    ));
+                }
+                Err(e) => eprintln!("{}", format!("Skipping {}: {}", name, e).yellow()),
+            }
+        }
+
+        let content = format!("{}\n\n{}", variables.join("\n"), requests.join("\n"));
+        match self.output.as_ref() {
+            Some(path) => {
+                std::fs::write(path, content)?;
+                println!(
+                    "{} actions exported to {}",
+                    requests.len(),
+                    path.display().to_string().green()
+                );
+            }
+            None => print!("{}", content),
+        }
+        Ok(())
+    }
+}
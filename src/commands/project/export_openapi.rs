@@ -0,0 +1,397 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use openapiv3::OpenAPI;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::utils::{parse_body_fields, SEP};
+
+lazy_static! {
+    /// {{var}} of an url, documented as a path parameter
+    static ref VARIABLE: Regex = Regex::new(r"\{\{\s*([^{}\s]+)\s*\}\}").unwrap();
+    static ref PATH_PARAM: Regex = Regex::new(r"\{([^{}/]+)\}").unwrap();
+}
+
+/// headers described elsewhere in the document
+const SKIPPED_HEADERS: [&str; 3] = ["accept", "content-type", "authorization"];
+
+#[derive(Args)]
+pub struct ExportOpenapiArgs {
+    /// project name
+    pub project_name: String,
+
+    /// output file, e.g. spec.yaml, stdout if missing
+    #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+
+    /// json document instead of yaml
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// `k:v` pairs of all the entries of a param list, e.g. ["id:1;job:2", "page:3"]
+pub(super) fn pairs(values: Option<&Vec<String>>) -> Vec<(String, String)> {
+    values
+        .iter()
+        .flat_map(|v| v.iter())
+        .flat_map(|v| v.split(SEP))
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            let (k, v) = v.split_once(':').unwrap_or((v, ""));
+            (k.trim().to_string(), v.trim().to_string())
+        })
+        .collect()
+}
+
+/// json schema of an example value
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "nullable": true }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => json!({
+            "type": "array",
+            "items": items.first().map(infer_schema).unwrap_or(json!({})),
+        }),
+        Value::Object(fields) => json!({
+            "type": "object",
+            "properties": fields
+                .iter()
+                .map(|(k, v)| (k.clone(), infer_schema(v)))
+                .collect::<Map<_, _>>(),
+        }),
+    }
+}
+
+/// `{{var}}` placeholders are not examples
+fn is_example(value: &str) -> bool {
+    !value.is_empty() && !VARIABLE.is_match(value)
+}
+
+/// media type object of an example, json if it parses
+fn media_type(example: &str, mime: &str) -> (String, Value) {
+    match serde_json::from_str::<Value>(example) {
+        Ok(value) => {
+            let mut media = json!({ "schema": infer_schema(&value) });
+            if is_example(example) {
+                media["example"] = value;
+            }
+            (mime.to_string(), media)
+        }
+        Err(_) => (
+            "text/plain".to_string(),
+            json!({ "schema": { "type": "string" }, "example": example }),
+        ),
+    }
+}
+
+/// example of the request body of an action
+fn body_example(action: &Action, args: &RunActionArgs) -> Option<String> {
+    if let Some(example) = action.body_example.as_ref().filter(|b| !b.is_empty()) {
+        return Some(example.clone());
+    }
+    if let Some(query) = args.query.as_ref() {
+        return Some(json!({ "query": query }).to_string());
+    }
+    let body = args.body.as_ref()?.first()?;
+    // @file or @- bodies are only known when running the action
+    if body.is_empty() || body.starts_with('@') {
+        return None;
+    }
+    if serde_json::from_str::<Value>(body).is_ok() {
+        return Some(body.clone());
+    }
+    parse_body_fields(body).ok().map(|v| v.to_string())
+}
+
+/// (path, origin if different from the project url) of an action url
+pub(super) fn split_url<'u>(url: &'u str, main_url: &str) -> (&'u str, Option<&'u str>) {
+    let main_url = main_url.trim_end_matches('/');
+    if let Some(path) = url.strip_prefix(main_url).filter(|_| !main_url.is_empty()) {
+        return (path, None);
+    }
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let index = rest.find('/').unwrap_or(rest.len());
+            (&rest[index..], Some(&url[..scheme.len() + 3 + index]))
+        }
+        None => (url, None),
+    }
+}
+
+/// {param} templates of urls parsed by the importers are percent encoded
+pub(super) fn decode_templates(path: &str) -> String {
+    path.replace("%7B", "{")
+        .replace("%7D", "}")
+        .replace("%7b", "{")
+        .replace("%7d", "}")
+}
+
+/// (scheme name, scheme) of an Authorization header
+fn security_scheme(value: &str) -> (&'static str, Value) {
+    let kind = value.split_whitespace().next().unwrap_or_default();
+    if kind.eq_ignore_ascii_case("bearer") {
+        ("bearerAuth", json!({ "type": "http", "scheme": "bearer" }))
+    } else if kind.eq_ignore_ascii_case("basic") {
+        ("basicAuth", json!({ "type": "http", "scheme": "basic" }))
+    } else {
+        (
+            "apiKeyAuth",
+            json!({ "type": "apiKey", "in": "header", "name": "Authorization" }),
+        )
+    }
+}
+
+fn parameter(location: &str, name: &str, value: &str, required: bool) -> Value {
+    let mut parameter = json!({
+        "name": name,
+        "in": location,
+        "required": required,
+        "schema": { "type": "string" },
+    });
+    if is_example(value) {
+        parameter["example"] = json!(value);
+    }
+    parameter
+}
+
+/// (path, verb, operation) of an action, with the security schemes it uses
+fn operation(
+    project: &Project,
+    action: &Action,
+    schemes: &mut Map<String, Value>,
+) -> anyhow::Result<(String, String, Value)> {
+    let name = action.name.clone().unwrap_or_default();
+    let args = action.get_run_action_args()?;
+    let verb = args
+        .verb
+        .clone()
+        .unwrap_or("GET".to_string())
+        .to_lowercase();
+    let url = args.url.clone().unwrap_or_default();
+    let (url, origin) = split_url(&url, &project.main_url);
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    // project variables of the path become parameters too
+    let path = decode_templates(path);
+    let path = VARIABLE.replace_all(&path, "{$1}");
+    let path = format!("/{}", path.trim_start_matches('/'));
+
+    let path_params = pairs(args.path_params.as_ref());
+    let mut parameters = PATH_PARAM
+        .captures_iter(&path)
+        .map(|c| c[1].to_string())
+        .unique()
+        .map(|param| {
+            let value = path_params
+                .iter()
+                .find(|(k, _)| *k == param)
+                .map(|(_, v)| v.as_str())
+                .unwrap_or_default();
+            parameter("path", &param, value, true)
+        })
+        .collect_vec();
+    let url_query = query
+        .split('&')
+        .filter(|q| !q.is_empty())
+        .map(|q| {
+            let (k, v) = q.split_once('=').unwrap_or((q, ""));
+            (k.to_string(), v.to_string())
+        })
+        .collect_vec();
+    parameters.extend(
+        url_query
+            .into_iter()
+            .chain(pairs(args.query_params.as_ref()))
+            .unique_by(|(k, _)| k.clone())
+            .map(|(k, v)| parameter("query", &k, &v, !is_example(&v))),
+    );
+
+    let mut security = vec![];
+    for (k, v) in pairs(args.header.as_ref()) {
+        let lowercase = k.to_lowercase();
+        if lowercase == "authorization" {
+            let (scheme_name, scheme) = security_scheme(&v);
+            schemes.insert(scheme_name.to_string(), scheme);
+            security.push(json!({ scheme_name: [] }));
+        } else if !SKIPPED_HEADERS.contains(&lowercase.as_str()) {
+            parameters.push(parameter("header", &k, &v, !is_example(&v)));
+        }
+    }
+
+    let mut operation = json!({
+        "operationId": name,
+        "parameters": parameters,
+    });
+    if let Some(example) = body_example(action, &args) {
+        let mime = match (args.form_data, args.url_encoded) {
+            (true, _) => "multipart/form-data",
+            (_, true) => "application/x-www-form-urlencoded",
+            _ => args.content_type.as_deref().unwrap_or("application/json"),
+        };
+        let (mime, media) = media_type(&example, mime);
+        operation["requestBody"] = json!({ "content": { mime: media } });
+    }
+    let status = pairs(args.expect.as_ref())
+        .into_iter()
+        .find(|(k, _)| k == "STATUS")
+        .map(|(_, v)| v)
+        .unwrap_or("200".to_string());
+    let mut response = json!({ "description": format!("{} response", name) });
+    if let Some(example) = action.response_example.as_ref().filter(|r| !r.is_empty()) {
+        let (mime, media) = media_type(example, "application/json");
+        response["content"] = json!({ mime: media });
+    }
+    operation["responses"] = json!({ status: response });
+    if !security.is_empty() {
+        operation["security"] = json!(security);
+    }
+    if let Some(origin) = origin {
+        operation["servers"] = json!([{ "url": origin }]);
+    }
+    Ok((path, verb, operation))
+}
+
+/// openapi document describing the project actions
+pub fn build_openapi(project: &Project, actions: &[Action]) -> anyhow::Result<OpenAPI> {
+    let mut paths = Map::new();
+    let mut schemes = Map::new();
+    for action in actions.iter().sorted_by_key(|a| a.name.clone()) {
+        let name = action.name.clone().unwrap_or_default();
+        let (path, verb, operation) = match operation(project, action, &mut schemes) {
+            Ok(o) => o,
+            Err(e) => {
+                eprintln!("{}", format!("Skipping {}: {}", name, e).yellow());
+                continue;
+            }
+        };
+        let item = paths
+            .entry(path.clone())
+            .or_insert(json!({}))
+            .as_object_mut()
+            .unwrap();
+        if item.contains_key(&verb) {
+            eprintln!(
+                "{}",
+                format!(
+                    "Skipping {}: {} {} is already described",
+                    name,
+                    verb.to_uppercase(),
+                    path
+                )
+                .yellow()
+            );
+            continue;
+        }
+        item.insert(verb, operation);
+    }
+    let mut document = json!({
+        "openapi": "3.0.3",
+        "info": { "title": project.name, "version": "1.0.0" },
+        "paths": paths,
+    });
+    if !project.main_url.is_empty() {
+        document["servers"] = json!([{ "url": project.main_url }]);
+    }
+    if !schemes.is_empty() {
+        document["components"] = json!({ "securitySchemes": schemes });
+    }
+    Ok(serde_json::from_value(document)?)
+}
+
+impl ExportOpenapiArgs {
+    pub async fn export(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let project = db_handler.get_project(&self.project_name).await?;
+        let actions = db_handler.get_actions(Some(&self.project_name)).await?;
+        let openapi = build_openapi(&project, &actions)?;
+        let content = if self.json {
+            serde_json::to_string_pretty(&openapi)?
+        } else {
+            serde_yaml::to_string(&openapi)?
+        };
+        match self.output.as_ref() {
+            Some(path) => {
+                std::fs::write(path, content)?;
+                println!(
+                    "{} paths exported to {}",
+                    openapi.paths.paths.len(),
+                    path.display().to_string().green()
+                );
+            }
+            None => println!("{}", content),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_openapi() {
+        let project = Project {
+            name: "users".to_string(),
+            main_url: "https://api.example.com/v1".to_string(),
+            id: None,
+            conf: None,
+            created_at: None,
+            updated_at: None,
+            source: None,
+        };
+        let args = RunActionArgs {
+            url: Some("https://api.example.com/v1/users/{id}?expand=roles".to_string()),
+            verb: Some("PUT".to_string()),
+            path_params: Some(vec!["id:42".to_string()]),
+            header: Some(vec![
+                "Authorization:Bearer {{token}};X-Tenant:{{tenant}}".to_string()
+            ]),
+            expect: Some(vec!["STATUS:201".to_string()]),
+            ..Default::default()
+        };
+        let action = Action {
+            name: Some("update-user".to_string()),
+            run_action_args: Some(serde_json::to_string(&args).unwrap()),
+            body_example: Some(r#"{"name": "marco", "age": 18, "tags": ["a"]}"#.to_string()),
+            response_example: Some(r#"{"id": 42}"#.to_string()),
+            ..Default::default()
+        };
+
+        let openapi = serde_json::to_value(build_openapi(&project, &[action]).unwrap()).unwrap();
+        let operation = &openapi["paths"]["/users/{id}"]["put"];
+        assert_eq!(operation["operationId"], "update-user");
+        assert_eq!(
+            operation["parameters"],
+            json!([
+                {"name": "id", "in": "path", "required": true, "schema": {"type": "string"}, "example": "42", "style": "simple"},
+                {"name": "expand", "in": "query", "schema": {"type": "string"}, "example": "roles", "style": "form"},
+                {"name": "X-Tenant", "in": "header", "required": true, "schema": {"type": "string"}, "style": "simple"}
+            ])
+        );
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["schema"],
+            json!({"type": "object", "properties": {
+                "age": {"type": "integer"},
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }})
+        );
+        assert_eq!(
+            operation["responses"]["201"]["content"]["application/json"]["example"],
+            json!({"id": 42})
+        );
+        assert_eq!(operation["security"], json!([{"bearerAuth": []}]));
+        assert_eq!(
+            openapi["components"]["securitySchemes"]["bearerAuth"],
+            json!({"type": "http", "scheme": "bearer"})
+        );
+    }
+}
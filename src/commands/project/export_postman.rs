@@ -0,0 +1,425 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::commands::project::export_openapi::{decode_templates, pairs, split_url};
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+use crate::utils::{parse_body_fields, SEP};
+
+/// variable holding the project url in exported collections
+const BASE_URL_VAR: &str = "baseUrl";
+const SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+/// verbs of the actions postman can not send
+const SKIPPED_VERBS: [&str; 2] = ["WS", "GRPC"];
+
+lazy_static! {
+    /// json paths that are plain property accesses, e.g. $.data[0].token
+    static ref SIMPLE_PATH: Regex = Regex::new(r"^\$((?:\.\w+|\[\d+\])*)$").unwrap();
+}
+
+#[derive(Args)]
+pub struct ExportPostmanArgs {
+    /// project name
+    pub project_name: String,
+
+    /// output file, e.g. collection.json, stdout if missing
+    #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+}
+
+fn key_values(pairs: &[(String, String)]) -> Vec<Value> {
+    pairs
+        .iter()
+        .map(|(k, v)| json!({ "key": k, "value": v }))
+        .collect()
+}
+
+/// postman url object, the project url being the {{baseUrl}} variable
+fn url(args: &RunActionArgs, main_url: &str) -> Value {
+    let url = args.url.clone().unwrap_or_default();
+    let (url, origin) = split_url(&url, main_url);
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    // {param} templates are :param path variables
+    let segments = decode_templates(path)
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(
+            |s| match s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(param) if !param.contains(['{', '}']) => format!(":{}", param),
+                _ => s.to_string(),
+            },
+        )
+        .collect_vec();
+    let query = query
+        .split('&')
+        .filter(|q| !q.is_empty())
+        .map(|q| {
+            let (k, v) = q.split_once('=').unwrap_or((q, ""));
+            (k.to_string(), v.to_string())
+        })
+        .chain(pairs(args.query_params.as_ref()))
+        .collect_vec();
+    let path_params = pairs(args.path_params.as_ref());
+    let variables = segments
+        .iter()
+        .filter_map(|s| s.strip_prefix(':'))
+        .map(|param| {
+            let value = path_params
+                .iter()
+                .find(|(k, _)| k == param)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default();
+            (param.to_string(), value)
+        })
+        .collect_vec();
+
+    let (protocol, host) = match origin {
+        Some(origin) => {
+            let (protocol, host) = origin.split_once("://").unwrap_or(("https", origin));
+            (Some(protocol), host.to_string())
+        }
+        None => (None, format!("{{{{{}}}}}", BASE_URL_VAR)),
+    };
+    let mut raw = match protocol {
+        Some(protocol) => format!("{}://{}", protocol, host),
+        None => host.clone(),
+    };
+    if !segments.is_empty() {
+        raw = format!("{}/{}", raw, segments.join("/"));
+    }
+    if !query.is_empty() {
+        raw = format!(
+            "{}?{}",
+            raw,
+            query.iter().map(|(k, v)| format!("{}={}", k, v)).join("&")
+        );
+    }
+    let mut url = json!({
+        "raw": raw,
+        "host": host.split('.').collect_vec(),
+        "path": segments,
+    });
+    if origin.is_none() {
+        url["host"] = json!([host]);
+    }
+    if let Some(protocol) = protocol {
+        url["protocol"] = json!(protocol);
+    }
+    if !query.is_empty() {
+        url["query"] = json!(key_values(&query));
+    }
+    if !variables.is_empty() {
+        url["variable"] = json!(key_values(&variables));
+    }
+    url
+}
+
+/// postman body object of an action
+fn body(action: &Action, args: &RunActionArgs) -> Option<Value> {
+    if let Some(query) = args.query.as_ref() {
+        return Some(json!({
+            "mode": "graphql",
+            "graphql": { "query": query, "variables": args.variables.clone().unwrap_or_default() },
+        }));
+    }
+    let body = args
+        .body
+        .as_ref()
+        .and_then(|b| b.first())
+        .filter(|b| !b.is_empty())
+        .or(action.body_example.as_ref())?;
+    if args.form_data || args.url_encoded {
+        let fields = body
+            .split(SEP)
+            .filter(|f| !f.is_empty())
+            .map(|f| {
+                let (k, v) = f.split_once(':').unwrap_or((f, ""));
+                match v.strip_prefix('@') {
+                    Some(src) if args.form_data => json!({ "key": k, "type": "file", "src": src }),
+                    _ => json!({ "key": k, "value": v, "type": "text" }),
+                }
+            })
+            .collect_vec();
+        return Some(match args.form_data {
+            true => json!({ "mode": "formdata", "formdata": fields }),
+            false => json!({ "mode": "urlencoded", "urlencoded": fields }),
+        });
+    }
+    if let Some(src) = body.strip_prefix('@').filter(|src| *src != "-") {
+        return Some(json!({ "mode": "file", "file": { "src": src } }));
+    }
+    // body fields are sent as json
+    let raw = match serde_json::from_str::<Value>(body) {
+        Ok(_) => body.clone(),
+        Err(_) => parse_body_fields(body)
+            .ok()
+            .and_then(|v| serde_json::to_string_pretty(&v).ok())
+            .unwrap_or(body.clone()),
+    };
+    let mut body = json!({ "mode": "raw", "raw": raw });
+    if serde_json::from_str::<Value>(&raw).is_ok() {
+        body["options"] = json!({ "raw": { "language": "json" } });
+    }
+    Some(body)
+}
+
+/// test script of the expectations and extractions, the reverse of the import translation
+fn test_script(args: &RunActionArgs) -> Vec<String> {
+    let mut lines = vec![];
+    for expect in args.expect.iter().flatten().flat_map(|e| e.split(SEP)) {
+        match expect.strip_prefix("STATUS:") {
+            Some(status) => lines.extend([
+                format!("pm.test(\"status is {}\", function () {{", status),
+                format!("    pm.response.to.have.status({});", status),
+                "});".to_string(),
+            ]),
+            None if !expect.is_empty() => lines.push(format!("// expect {}", expect)),
+            None => {}
+        }
+    }
+    for extract in args
+        .extract_path
+        .iter()
+        .flatten()
+        .flat_map(|e| e.split(SEP))
+    {
+        let captures = extract
+            .rsplit_once(':')
+            .and_then(|(path, name)| Some((SIMPLE_PATH.captures(path)?, name)));
+        match captures {
+            Some((path, name)) => lines.push(format!(
+                "pm.collectionVariables.set(\"{}\", pm.response.json(){});",
+                name, &path[1]
+            )),
+            None if !extract.is_empty() => lines.push(format!("// extract {}", extract)),
+            None => {}
+        }
+    }
+    lines
+}
+
+/// postman request item of an action
+fn item(project: &Project, action: &Action, name: &str) -> anyhow::Result<Value> {
+    let args = action.get_run_action_args()?;
+    let verb = args.verb.clone().unwrap_or("GET".to_string());
+    if SKIPPED_VERBS.contains(&verb.as_str()) {
+        anyhow::bail!("{} actions are not supported by postman", verb);
+    }
+    let mut request = json!({
+        "method": verb,
+        "url": url(&args, &project.main_url),
+    });
+    let mut headers = vec![];
+    for (k, v) in pairs(args.header.as_ref()) {
+        match v.strip_prefix("Bearer ") {
+            Some(token) if k.eq_ignore_ascii_case("authorization") => {
+                request["auth"] = json!({
+                    "type": "bearer",
+                    "bearer": [{ "key": "token", "value": token, "type": "string" }],
+                });
+            }
+            _ => headers.push((k, v)),
+        }
+    }
+    if let Some(content_type) = args.content_type.as_ref() {
+        if !headers
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        {
+            headers.push(("Content-Type".to_string(), content_type.clone()));
+        }
+    }
+    request["header"] = json!(key_values(&headers));
+    if let Some(body) = body(action, &args) {
+        request["body"] = body;
+    }
+
+    let mut item = json!({ "name": name, "request": request });
+    let script = test_script(&args);
+    if !script.is_empty() {
+        item["event"] =
+            json!([{ "listen": "test", "script": { "type": "text/javascript", "exec": script } }]);
+    }
+    if let Some(example) = action.response_example.as_ref().filter(|r| !r.is_empty()) {
+        let code = pairs(args.expect.as_ref())
+            .into_iter()
+            .find(|(k, _)| k == "STATUS")
+            .and_then(|(_, v)| v.parse::<u16>().ok())
+            .unwrap_or(200);
+        let language = match serde_json::from_str::<Value>(example) {
+            Ok(_) => "json",
+            Err(_) => "text",
+        };
+        item["response"] = json!([{
+            "name": format!("{} example", name),
+            "originalRequest": item["request"].clone(),
+            "code": code,
+            "_postman_previewlanguage": language,
+            "header": [],
+            "body": example,
+        }]);
+    }
+    Ok(item)
+}
+
+/// folder of an action, the first static segment of its path
+fn folder(action: &Action, main_url: &str) -> Option<String> {
+    let url = action.get_run_action_args().ok()?.url?;
+    let (path, _) = split_url(&url, main_url);
+    path.split(['/', '?'])
+        .find(|s| !s.is_empty())
+        .filter(|s| !s.contains(['{', '}', '%']))
+        .map(str::to_string)
+}
+
+/// postman v2.1 collection of the project actions, grouped by resource
+pub fn build_collection(project: &Project, actions: &[Action]) -> anyhow::Result<Value> {
+    let mut items = vec![];
+    let groups = actions
+        .iter()
+        .sorted_by_key(|a| (folder(a, &project.main_url), a.name.clone()))
+        .group_by(|a| folder(a, &project.main_url));
+    for (folder, group) in groups.into_iter() {
+        let mut folder_items = vec![];
+        for action in group {
+            let name = action.name.clone().unwrap_or_default();
+            // folders are prefixed to the action names when importing
+            let item_name = folder
+                .as_ref()
+                .and_then(|f| name.strip_prefix(&format!("{}-", f)))
+                .unwrap_or(&name);
+            match item(project, action, item_name) {
+                Ok(item) => folder_items.push(item),
+                Err(e) => eprintln!("{}", format!("Skipping {}: {}", name, e).yellow()),
+            }
+        }
+        match folder {
+            Some(folder) if !folder_items.is_empty() => {
+                items.push(json!({ "name": folder, "item": folder_items }))
+            }
+            _ => items.extend(folder_items),
+        }
+    }
+
+    let mut variables = vec![(BASE_URL_VAR.to_string(), project.main_url.clone())];
+    variables.extend(
+        project
+            .get_project_conf()?
+            .into_iter()
+            .filter(|(k, _)| k.as_str() != BASE_URL_VAR)
+            .sorted(),
+    );
+    Ok(json!({
+        "info": { "name": project.name, "schema": SCHEMA },
+        "item": items,
+        "variable": key_values(&variables),
+    }))
+}
+
+impl ExportPostmanArgs {
+    pub async fn export(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let project = db_handler.get_project(&self.project_name).await?;
+        let actions = db_handler.get_actions(Some(&self.project_name)).await?;
+        let collection = build_collection(&project, &actions)?;
+        let content = serde_json::to_string_pretty(&collection)?;
+        match self.output.as_ref() {
+            Some(path) => {
+                std::fs::write(path, content)?;
+                println!(
+                    "Collection of project {} exported to {}",
+                    self.project_name,
+                    path.display().to_string().green()
+                );
+            }
+            None => println!("{}", content),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::import::import::Import;
+    use crate::commands::import::postman::PostmanImporter;
+
+    #[tokio::test]
+    async fn test_export_postman_round_trip() {
+        let mut project = Project {
+            id: None,
+            name: "users".to_string(),
+            main_url: "https://api.example.com".to_string(),
+            conf: Some(r#"{"token": "abc"}"#.to_string()),
+            created_at: None,
+            updated_at: None,
+            source: None,
+        };
+        let args = RunActionArgs {
+            url: Some("users/{id}".to_string()),
+            verb: Some("PUT".to_string()),
+            path_params: Some(vec!["id:42".to_string()]),
+            query_params: Some(vec!["expand:roles".to_string()]),
+            header: Some(vec!["Authorization:Bearer {{token}}".to_string()]),
+            body: Some(vec!["name:marco;age:=18".to_string()]),
+            extract_path: Some(vec!["$.data[0].email:email".to_string()]),
+            expect: Some(vec!["STATUS:201".to_string()]),
+            ..Default::default()
+        };
+        let action = Action {
+            name: Some("users-update".to_string()),
+            run_action_args: Some(serde_json::to_string(&args).unwrap()),
+            response_example: Some(r#"{"id": 42}"#.to_string()),
+            ..Default::default()
+        };
+
+        let collection = build_collection(&project, &[action]).unwrap();
+        assert_eq!(collection["item"][0]["name"], "users");
+        assert_eq!(collection["item"][0]["item"][0]["name"], "update");
+        assert_eq!(
+            collection["item"][0]["item"][0]["request"]["url"]["raw"],
+            "{{baseUrl}}/users/:id?expand=roles"
+        );
+        assert_eq!(
+            collection["variable"],
+            json!([
+                {"key": "baseUrl", "value": "https://api.example.com"},
+                {"key": "token", "value": "abc"}
+            ])
+        );
+
+        let db_handler = DBHandler::default();
+        let importer = PostmanImporter {
+            db_handler: &db_handler,
+            env: None,
+        };
+        let actions = importer
+            .actions(&collection.to_string(), &mut project)
+            .await
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].name.as_deref(), Some("users-update"));
+        assert_eq!(
+            actions[0].response_example.as_deref(),
+            Some(r#"{"id": 42}"#)
+        );
+        let imported = actions[0].get_run_action_args().unwrap();
+        assert_eq!(imported.url, args.url);
+        assert_eq!(imported.verb, args.verb);
+        assert_eq!(imported.path_params, args.path_params);
+        assert_eq!(imported.query_params, args.query_params);
+        assert_eq!(imported.header, args.header);
+        assert_eq!(imported.extract_path, args.extract_path);
+        assert_eq!(imported.expect, args.expect);
+        assert_eq!(
+            serde_json::from_str::<Value>(&imported.body.unwrap()[0]).unwrap(),
+            json!({"name": "marco", "age": 18})
+        );
+    }
+}
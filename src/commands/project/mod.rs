@@ -1,16 +1,24 @@
 pub mod add_action;
 pub mod create;
+pub mod export_http;
+pub mod export_openapi;
+pub mod export_postman;
 pub mod info;
 pub mod list;
 pub mod project_ui;
 mod rm_action;
+pub mod sync;
 
 //pub mod project {
 use crate::commands::project::add_action::AddActionArgs;
 use crate::commands::project::create::CreateProjectArgs;
+use crate::commands::project::export_http::ExportHttpArgs;
+use crate::commands::project::export_openapi::ExportOpenapiArgs;
+use crate::commands::project::export_postman::ExportPostmanArgs;
 use crate::commands::project::info::ProjectInfoArgs;
 use crate::commands::project::list::ListProjects;
 use crate::commands::project::rm_action::RmActionArgs;
+use crate::commands::project::sync::SyncProjectArgs;
 use clap::{Args, Subcommand};
 
 #[derive(Args)]
@@ -31,6 +39,14 @@ pub enum ProjectCommands {
     Info(ProjectInfoArgs),
     /// List projects
     List(ListProjects),
+    /// Export the project actions as a JetBrains / VS Code .http file
+    ExportHttp(ExportHttpArgs),
+    /// Export the project actions as an openapi 3 document
+    ExportOpenapi(ExportOpenapiArgs),
+    /// Export the project actions as a postman v2.1 collection
+    ExportPostman(ExportPostmanArgs),
+    /// Re-import a project from its openapi, postman... source, previewing the changes
+    Sync(SyncProjectArgs),
     /// Run project ui
     Ui,
 }
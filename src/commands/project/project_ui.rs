@@ -1,9 +1,12 @@
+use crate::commands::export::export_action;
+use crate::commands::export::snippet::SnippetFormat;
 use crate::db::db_handler::DBHandler;
 use crate::db::dto::{Action, Project};
 use crate::ui::helpers::{Stateful, StatefulList};
 use crate::ui::run_ui::UIRunner;
 use crate::utils::{human_readable_date, random_emoji};
 use crate::DEFAULT_PROJECT;
+use arboard::Clipboard;
 use crossterm::event::{self};
 use ratatui::backend::Backend;
 use ratatui::Frame;
@@ -34,6 +37,8 @@ pub(crate) struct ProjectUI<'a> {
     body_ex_text_area: Option<tui_textarea::TextArea<'a>>,
     resp_ex_text_area: Option<tui_textarea::TextArea<'a>>,
     current_action_index: (String, bool, bool),
+    /// result of the last copy of an action
+    copy_status: Option<String>,
 }
 
 impl<'a> ProjectUI<'a> {
@@ -49,6 +54,7 @@ impl<'a> ProjectUI<'a> {
             body_ex_text_area: None,
             resp_ex_text_area: None,
             current_action_index: ("".to_string(), false, false),
+            copy_status: None,
         }
     }
 
@@ -105,6 +111,41 @@ impl<'a> ProjectUI<'a> {
         self.actions = StatefulList::with_items(actions);
     }
 
+    /// copy the selected action as a curl command
+    fn copy_action_as_curl(&mut self) {
+        let Some(action_name) = self
+            .actions
+            .state
+            .selected()
+            .and_then(|i| self.actions.items.get(i))
+            .and_then(|a| a.name.clone())
+        else {
+            return;
+        };
+        let handle = Handle::current();
+        let db = self.db.clone();
+        let name = action_name.clone();
+        let snippet = thread::spawn(move || {
+            handle.block_on(async move {
+                export_action(&name, SnippetFormat::Curl, true, &db)
+                    .await
+                    .map_err(|e| e.to_string())
+            })
+        })
+        .join()
+        .unwrap();
+        self.copy_status = Some(
+            match snippet.and_then(|s| {
+                Clipboard::new()
+                    .and_then(|mut c| c.set_text(s))
+                    .map_err(|e| e.to_string())
+            }) {
+                Ok(_) => format!("{} copied as curl", action_name),
+                Err(e) => format!("copy failed: {}", e),
+            },
+        );
+    }
+
     fn set_current_action_index(&mut self) {
         self.current_action_index = (
             self.actions
@@ -187,6 +228,8 @@ impl<'a> ProjectUI<'a> {
                         .get_run_action_args()
                         .expect("Error getting run action args");
                     let v = r.verb.unwrap_or("UNKNOWN".to_string());
+                    let is_websocket = v == "WS";
+                    let is_grpc = v == "GRPC";
                     let url = r.url.unwrap_or("UNKNOWN".to_string());
                     ListItem::new(vec![
                         Line::styled(
@@ -235,6 +278,14 @@ impl<'a> ProjectUI<'a> {
                                     "(form)"
                                 } else if r.url_encoded {
                                     "(url encoded)"
+                                } else if r.query.is_some() {
+                                    "(graphql)"
+                                } else if is_websocket {
+                                    "(websocket)"
+                                } else if is_grpc {
+                                    "(grpc)"
+                                } else if r.stream {
+                                    "(stream)"
                                 } else {
                                     "(json)"
                                 },
@@ -263,7 +314,13 @@ impl<'a> ProjectUI<'a> {
         )
         .block(
             Block::default()
-                .title("Actions".gray())
+                .title(Span::styled(
+                    format!(
+                        "Actions ({})",
+                        self.copy_status.as_deref().unwrap_or("c: copy as curl")
+                    ),
+                    Style::default().fg(Color::Gray),
+                ))
                 .style(Style::reset())
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(self.get_color(ActiveArea::ActionPane))),
@@ -295,12 +352,12 @@ impl<'a> ProjectUI<'a> {
             Self::payload_as_str_pretty(current_action.response_example.as_deref())
                 .unwrap_or("FAILED TO PARSE RESPONSE EXAMPLE".to_string());
 
-        vec![
+        [
             self.body_ex_text_area.as_mut(),
             self.resp_ex_text_area.as_mut(),
         ]
         .iter_mut()
-        .zip(vec![ActiveArea::BodyExample, ActiveArea::ResponseExample].iter())
+        .zip([ActiveArea::BodyExample, ActiveArea::ResponseExample].iter())
         .for_each(|(text_area, area)| {
             let is_body_example = area == &ActiveArea::BodyExample;
             if let Some(t) = text_area.as_mut() {
@@ -475,6 +532,11 @@ impl UIRunner for ProjectUI<'_> {
                         ctrl: true,
                         ..
                     } => self.active_area = ActiveArea::BodyExample,
+                    Input {
+                        key: Key::Char('c'),
+                        ctrl: false,
+                        alt: false,
+                    } => self.copy_action_as_curl(),
                     Input {
                         key: Key::Char('q'),
                         ctrl: false,
@@ -0,0 +1,374 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use clap::Args;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+
+use crate::commands::project::create::get_importer;
+use crate::commands::run::action::RunActionArgs;
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::{Action, Project};
+
+#[derive(Args)]
+pub struct SyncProjectArgs {
+    /// project name
+    pub project_name: String,
+
+    /// apply the changes without confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+fn join(values: Option<&Vec<String>>) -> String {
+    values.map(|v| v.join(",")).unwrap_or_default()
+}
+
+/// header names, lowercased
+fn header_names(args: &RunActionArgs) -> HashSet<String> {
+    args.header
+        .iter()
+        .flatten()
+        .filter_map(|h| h.split_once(':').map(|(k, _)| k.trim().to_lowercase()))
+        .collect()
+}
+
+/// current action updated with the imported one: the request is the source one, while
+/// headers, extract paths, expectations and other options saved by the user are kept
+fn merge(current: &RunActionArgs, imported: &RunActionArgs) -> RunActionArgs {
+    let mut merged = current.clone();
+    merged.url = imported.url.clone();
+    merged.verb = imported.verb.clone();
+    merged.path_params = imported.path_params.clone();
+    merged.query_params = imported.query_params.clone();
+    merged.form_data = imported.form_data;
+    merged.url_encoded = imported.url_encoded;
+    // specs often only describe bodies, the saved ones are kept
+    if imported.body.is_some() {
+        merged.body = imported.body.clone();
+    }
+    if imported.query.is_some() {
+        merged.query = imported.query.clone();
+        merged.variables = imported.variables.clone();
+    }
+    let names = header_names(current);
+    let added = imported
+        .header
+        .iter()
+        .flatten()
+        .filter(|h| {
+            h.split_once(':')
+                .is_some_and(|(k, _)| !names.contains(&k.trim().to_lowercase()))
+        })
+        .cloned()
+        .collect_vec();
+    if !added.is_empty() {
+        merged.header = Some(
+            current
+                .header
+                .iter()
+                .flatten()
+                .cloned()
+                .chain(added)
+                .collect(),
+        );
+    }
+    merged
+}
+
+/// imported action with the merged run action args, examples missing from the source
+/// being kept
+fn merge_action(
+    current: &Action,
+    imported: &Action,
+    merged: &RunActionArgs,
+) -> anyhow::Result<Action> {
+    Ok(Action {
+        run_action_args: Some(serde_json::to_string(merged)?),
+        body_example: imported
+            .body_example
+            .clone()
+            .or_else(|| current.body_example.clone()),
+        response_example: imported
+            .response_example
+            .clone()
+            .or_else(|| current.response_example.clone()),
+        ..imported.clone()
+    })
+}
+
+/// changed fields of an action, e.g. `url: users -> users/{id}`
+fn changes(current: &RunActionArgs, merged: &RunActionArgs) -> Vec<String> {
+    [
+        ("verb", current.verb.clone(), merged.verb.clone()),
+        ("url", current.url.clone(), merged.url.clone()),
+        (
+            "path params",
+            Some(join(current.path_params.as_ref())),
+            Some(join(merged.path_params.as_ref())),
+        ),
+        (
+            "query params",
+            Some(join(current.query_params.as_ref())),
+            Some(join(merged.query_params.as_ref())),
+        ),
+        (
+            "headers",
+            Some(join(current.header.as_ref())),
+            Some(join(merged.header.as_ref())),
+        ),
+        (
+            "body",
+            Some(join(current.body.as_ref())),
+            Some(join(merged.body.as_ref())),
+        ),
+        ("graphql query", current.query.clone(), merged.query.clone()),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| before != after)
+    .map(|(field, before, after)| {
+        format!(
+            "{}: {} -> {}",
+            field,
+            before.unwrap_or_default(),
+            after.unwrap_or_default()
+        )
+    })
+    .collect()
+}
+
+/// main url and conf values set by the importer, e.g. credentials of new security schemes
+fn project_changes(current: &Project, imported: &Project) -> anyhow::Result<Vec<String>> {
+    let mut changes = vec![];
+    if current.main_url != imported.main_url {
+        changes.push(format!(
+            "main url: {} -> {}",
+            current.main_url, imported.main_url
+        ));
+    }
+    let conf = current.get_project_conf()?;
+    for (key, value) in imported.get_project_conf()?.into_iter().sorted() {
+        match conf.get(&key) {
+            Some(before) if *before == value => {}
+            before => changes.push(format!(
+                "conf {}: {} -> {}",
+                key,
+                before.cloned().unwrap_or_default(),
+                value
+            )),
+        }
+    }
+    Ok(changes)
+}
+
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+impl SyncProjectArgs {
+    pub async fn sync(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let project = db_handler.get_project(&self.project_name).await?;
+        let Some(mut source) = project.get_source()? else {
+            anyhow::bail!(
+                "Project {} was not imported from a spec or a collection, nothing to sync",
+                self.project_name
+            );
+        };
+        let importer = get_importer(&source, db_handler);
+        let content = importer.load(&source.path).await?;
+        // the importer completes the project, saved only once confirmed
+        let mut imported_project = project.clone();
+        let imported = importer.actions(&content, &mut imported_project).await?;
+        let project_changes = project_changes(&project, &imported_project)?;
+        if !project_changes.is_empty() {
+            println!("{}", format!("~ project {}", self.project_name).yellow());
+            for change in project_changes.iter() {
+                println!("    {}", change);
+            }
+        }
+        let current: HashMap<String, Action> = db_handler
+            .get_actions(Some(&self.project_name))
+            .await?
+            .into_iter()
+            .filter_map(|a| Some((a.name.clone()?, a)))
+            .collect();
+
+        let (mut added, mut changed) = (vec![], vec![]);
+        for action in imported.iter() {
+            let name = action.name.clone().unwrap_or_default();
+            let imported_args = action.get_run_action_args()?;
+            match current.get(&name) {
+                None => {
+                    println!(
+                        "{}",
+                        format!(
+                            "+ {} {} {}",
+                            name,
+                            imported_args.verb.unwrap_or_default(),
+                            imported_args.url.unwrap_or_default()
+                        )
+                        .green()
+                    );
+                    added.push(action.clone());
+                }
+                Some(current) => {
+                    let current_args = current.get_run_action_args().unwrap_or_default();
+                    let merged = merge(&current_args, &imported_args);
+                    let changes = changes(&current_args, &merged);
+                    if changes.is_empty() {
+                        continue;
+                    }
+                    println!("{}", format!("~ {}", name).yellow());
+                    for change in changes {
+                        println!("    {}", change);
+                    }
+                    changed.push(merge_action(current, action, &merged)?);
+                }
+            }
+        }
+        let names = imported.iter().filter_map(|a| a.name.clone()).collect_vec();
+        // actions added by the user are never removed
+        let removed = source
+            .actions
+            .iter()
+            .filter(|name| current.contains_key(*name) && !names.contains(name))
+            .cloned()
+            .collect_vec();
+        for name in removed.iter() {
+            println!("{}", format!("- {}", name).red());
+        }
+
+        if added.is_empty()
+            && changed.is_empty()
+            && removed.is_empty()
+            && project_changes.is_empty()
+        {
+            println!(
+                "Project {} is up to date",
+                self.project_name.clone().green()
+            );
+            return Ok(());
+        }
+        if !self.yes && !confirm("Apply these changes?")? {
+            return Ok(());
+        }
+        for action in added.iter().chain(changed.iter()) {
+            db_handler.upsert_action(action).await?;
+        }
+        for name in removed.iter() {
+            db_handler
+                .rm_project_action(Some(&self.project_name), name)
+                .await?;
+        }
+        source.actions = names;
+        imported_project.source = Some(serde_json::to_string(&source)?);
+        db_handler.upsert_project(&imported_project).await?;
+        println!(
+            "{} added, {} changed, {} removed in project {}",
+            added.len(),
+            changed.len(),
+            removed.len(),
+            self.project_name.clone().green()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_user_changes() {
+        let current = RunActionArgs {
+            url: Some("users/{id}".to_string()),
+            verb: Some("GET".to_string()),
+            header: Some(vec!["authorization:Bearer {{token}}".to_string()]),
+            body: Some(vec!["{\"name\": \"marco\"}".to_string()]),
+            extract_path: Some(vec!["$.id:ID".to_string()]),
+            expect: Some(vec!["STATUS:200".to_string()]),
+            ..Default::default()
+        };
+        let imported = RunActionArgs {
+            url: Some("users/{userId}".to_string()),
+            verb: Some("GET".to_string()),
+            query_params: Some(vec!["expand:roles".to_string()]),
+            header: Some(vec![
+                "Authorization:Bearer {{bearerAuth}}".to_string(),
+                "X-Request-Id:{{X-Request-Id}}".to_string(),
+            ]),
+            ..Default::default()
+        };
+
+        let merged = merge(&current, &imported);
+        assert_eq!(merged.url.as_deref(), Some("users/{userId}"));
+        assert_eq!(merged.query_params, imported.query_params);
+        assert_eq!(merged.body, current.body);
+        assert_eq!(merged.extract_path, current.extract_path);
+        assert_eq!(merged.expect, current.expect);
+        assert_eq!(
+            merged.header,
+            Some(vec![
+                "authorization:Bearer {{token}}".to_string(),
+                "X-Request-Id:{{X-Request-Id}}".to_string()
+            ])
+        );
+        assert_eq!(
+            changes(&current, &merged),
+            vec![
+                "url: users/{id} -> users/{userId}",
+                "query params:  -> expand:roles",
+                "headers: authorization:Bearer {{token}} -> authorization:Bearer {{token}},X-Request-Id:{{X-Request-Id}}"
+            ]
+        );
+        assert!(changes(&merged, &merge(&merged, &imported)).is_empty());
+    }
+
+    #[test]
+    fn test_project_changes() {
+        let current = Project {
+            id: None,
+            name: "pets".to_string(),
+            main_url: "".to_string(),
+            conf: Some(r#"{"token": "abc"}"#.to_string()),
+            created_at: None,
+            updated_at: None,
+            source: None,
+        };
+        let imported = Project {
+            main_url: "https://api.example.com".to_string(),
+            conf: Some(r#"{"token": "abc", "bearerAuth": ""}"#.to_string()),
+            ..current.clone()
+        };
+        assert_eq!(
+            project_changes(&current, &imported).unwrap(),
+            vec![
+                "main url:  -> https://api.example.com",
+                "conf bearerAuth:  -> "
+            ]
+        );
+        assert!(project_changes(&imported, &imported).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_action_keeps_examples() {
+        let current = Action {
+            name: Some("get-user".to_string()),
+            body_example: Some("{\"name\": \"marco\"}".to_string()),
+            response_example: Some("{\"id\": 1}".to_string()),
+            ..Default::default()
+        };
+        let imported = Action {
+            name: Some("get-user".to_string()),
+            response_example: Some("{\"id\": 2}".to_string()),
+            ..Default::default()
+        };
+        let merged = merge_action(&current, &imported, &RunActionArgs::default()).unwrap();
+        assert_eq!(merged.body_example, current.body_example);
+        assert_eq!(merged.response_example, imported.response_example);
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa, KeyPair};
+use tokio_native_tls::TlsAcceptor;
+
+const CA_NAME: &str = "apikrab local CA";
+const CA_CERT_FILE: &str = "ca.pem";
+const CA_KEY_FILE: &str = "ca-key.pem";
+
+/// Local certificate authority signing a certificate per intercepted host.
+/// It is generated once and must be trusted by the recorded client
+pub struct CertificateAuthority {
+    ca: Certificate,
+    pub cert_path: PathBuf,
+    acceptors: Mutex<HashMap<String, TlsAcceptor>>,
+}
+
+/// the key is readable by its owner only, any other local user could intercept
+/// the traffic of the clients trusting the authority
+fn write_private_key(path: &Path, pem: &str) -> anyhow::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // an existing file keeps its mode when opened
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(pem.as_bytes())?;
+    Ok(())
+}
+
+impl CertificateAuthority {
+    fn ca_params(key_pair: Option<KeyPair>) -> CertificateParams {
+        let mut params = CertificateParams::new(vec![]);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.distinguished_name.push(DnType::CommonName, CA_NAME);
+        params.key_pair = key_pair;
+        params
+    }
+
+    /// load the authority stored in dir, generate it if needed
+    pub fn load_or_create(dir: &Path) -> anyhow::Result<Self> {
+        let (cert_path, key_path) = (dir.join(CA_CERT_FILE), dir.join(CA_KEY_FILE));
+        let ca = if cert_path.exists() && key_path.exists() {
+            // same name and key than the stored certificate, issued certificates match it
+            let key_pair = KeyPair::from_pem(&std::fs::read_to_string(&key_path)?)?;
+            Certificate::from_params(Self::ca_params(Some(key_pair)))?
+        } else {
+            let ca = Certificate::from_params(Self::ca_params(None))?;
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(&cert_path, ca.serialize_pem()?)?;
+            write_private_key(&key_path, &ca.serialize_private_key_pem())?;
+            ca
+        };
+        Ok(CertificateAuthority {
+            ca,
+            cert_path,
+            acceptors: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// tls acceptor presenting a certificate for host
+    pub fn acceptor(&self, host: &str) -> anyhow::Result<TlsAcceptor> {
+        let mut acceptors = self.acceptors.lock().unwrap();
+        if let Some(acceptor) = acceptors.get(host) {
+            return Ok(acceptor.clone());
+        }
+        let cert = Certificate::from_params(CertificateParams::new(vec![host.to_string()]))?;
+        let identity = native_tls::Identity::from_pkcs8(
+            cert.serialize_pem_with_signer(&self.ca)?.as_bytes(),
+            cert.serialize_private_key_pem().as_bytes(),
+        )?;
+        let acceptor = TlsAcceptor::from(native_tls::TlsAcceptor::new(identity)?);
+        acceptors.insert(host.to_string(), acceptor.clone());
+        Ok(acceptor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_or_create() {
+        let dir = std::env::temp_dir().join("apikrab_ca_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let created = CertificateAuthority::load_or_create(&dir).unwrap();
+        assert!(created.cert_path.exists());
+        assert!(created.acceptor("api.example.com").is_ok());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let key = std::fs::metadata(dir.join(CA_KEY_FILE)).unwrap();
+            assert_eq!(key.permissions().mode() & 0o777, 0o600);
+        }
+
+        // reloading keeps the stored certificate
+        let cert = std::fs::read_to_string(&created.cert_path).unwrap();
+        let loaded = CertificateAuthority::load_or_create(&dir).unwrap();
+        assert_eq!(std::fs::read_to_string(&loaded.cert_path).unwrap(), cert);
+        assert!(loaded.acceptor("localhost").is_ok());
+    }
+}
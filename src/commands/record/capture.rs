@@ -0,0 +1,230 @@
+use crate::commands::run::action::RunActionArgs;
+use crate::db::dto::{Action, Project};
+use crate::utils::SEP;
+use crossterm::style::Stylize;
+use itertools::Itertools;
+use reqwest::Url;
+
+/// Headers set by the client or the proxy, not worth replaying
+const SKIPPED_HEADERS: [&str; 10] = [
+    "host",
+    "connection",
+    "proxy-connection",
+    "proxy-authorization",
+    "keep-alive",
+    "content-length",
+    "accept-encoding",
+    "transfer-encoding",
+    "user-agent",
+    "cookie",
+];
+
+/// `name:value` arguments of headers, and the content type when it has parameters, e.g. a
+/// charset, as it is kept whole apart. Parameters of other headers would be split as several
+/// headers, they are dropped with a warning
+pub fn header_args<'a>(
+    headers: impl Iterator<Item = &'a (String, String)>,
+) -> (Vec<String>, Option<String>) {
+    let (mut args, mut content_type) = (vec![], None);
+    for (name, value) in headers {
+        match value.split_once(SEP) {
+            Some(_) if name.eq_ignore_ascii_case("content-type") => {
+                content_type = Some(value.clone());
+            }
+            Some((kept, _)) => {
+                eprintln!(
+                    "{}",
+                    format!("Header {}: {} is cut to {}", name, value, kept.trim_end()).yellow()
+                );
+                args.push(format!("{}:{}", name, kept.trim_end()));
+            }
+            None => args.push(format!("{}:{}", name, value)),
+        }
+    }
+    (args, content_type)
+}
+
+/// A request forwarded by the proxy and its response
+pub struct Exchange {
+    pub verb: String,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub status: u16,
+    pub response: String,
+}
+
+impl Exchange {
+    /// one action per verb and path relative to the project, e.g. get-users-42
+    pub fn action_name(&self, project: &Project) -> String {
+        let action_url = self.action_url(project);
+        let path = if action_url.contains("://") {
+            self.url.path()
+        } else {
+            &action_url
+        };
+        let path = path
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|s| !s.is_empty())
+            .join("-");
+        format!(
+            "{}-{}",
+            self.verb,
+            if path.is_empty() { "index" } else { &path }
+        )
+        .to_lowercase()
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// url relative to the project url when possible
+    fn action_url(&self, project: &Project) -> String {
+        let mut url = self.url.clone();
+        url.set_query(None);
+        let url = url.to_string();
+        let main_url = project.main_url.trim_end_matches('/');
+        match url.strip_prefix(main_url) {
+            Some(path) if !main_url.is_empty() => path.trim_matches('/').to_string(),
+            _ => url,
+        }
+    }
+
+    /// replayable body and whether it is url encoded, other bodies are only kept as example
+    fn action_body(&self) -> (Option<String>, bool) {
+        if self.body.is_empty() {
+            return (None, false);
+        }
+        if serde_json::from_str::<serde_json::Value>(&self.body).is_ok() {
+            return (Some(self.body.clone()), false);
+        }
+        let is_url_encoded = self
+            .header("content-type")
+            .is_some_and(|c| c.starts_with("application/x-www-form-urlencoded"));
+        if is_url_encoded {
+            let fields = url::form_urlencoded::parse(self.body.as_bytes())
+                .map(|(k, v)| format!("{}:{}", k, v))
+                .join(SEP);
+            return (Some(fields), true);
+        }
+        (None, false)
+    }
+
+    /// replayable arguments of the request
+    pub fn run_action_args(&self, project: &Project) -> RunActionArgs {
+        let (headers, content_type) = header_args(
+            self.headers
+                .iter()
+                .filter(|(k, _)| !SKIPPED_HEADERS.contains(&k.to_lowercase().as_str())),
+        );
+        let query_params = self
+            .url
+            .query_pairs()
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect_vec();
+        let (body, url_encoded) = self.action_body();
+        RunActionArgs {
+            name: Some(self.action_name(project)),
+            url: Some(self.action_url(project)),
+            verb: Some(self.verb.clone()),
+            header: (!headers.is_empty()).then_some(headers),
+            query_params: (!query_params.is_empty()).then_some(query_params),
+            body: body.map(|b| vec![b]),
+            url_encoded,
+            content_type,
+            ..Default::default()
+        }
+    }
+
+    pub fn to_action(&self, project: &Project) -> Action {
+        let name = self.action_name(project);
+        let run_action_args = self.run_action_args(project);
+        let is_success = (200..300).contains(&self.status);
+        Action {
+            name: Some(name),
+            run_action_args: serde_json::to_string(&run_action_args).ok(),
+            body_example: (!self.body.is_empty()).then(|| self.body.clone()),
+            response_example: is_success.then(|| self.response.clone()),
+            project_name: Some(project.name.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_action() {
+        let project = Project {
+            id: None,
+            name: "users".to_string(),
+            main_url: "https://api.example.com/v1/".to_string(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let exchange = Exchange {
+            verb: "POST".to_string(),
+            url: Url::parse("https://api.example.com/v1/users/42/roles?dry_run=true").unwrap(),
+            headers: vec![
+                ("Host".to_string(), "api.example.com".to_string()),
+                (
+                    "Content-Type".to_string(),
+                    "application/x-www-form-urlencoded".to_string(),
+                ),
+            ],
+            body: "role=admin&scope=all".to_string(),
+            status: 201,
+            response: r#"{"id": 1}"#.to_string(),
+        };
+
+        let action = exchange.to_action(&project);
+        assert_eq!(action.name.as_deref(), Some("post-users-42-roles"));
+        assert_eq!(action.response_example.as_deref(), Some(r#"{"id": 1}"#));
+        let run_action_args = action.get_run_action_args().unwrap();
+        assert_eq!(run_action_args.url.as_deref(), Some("users/42/roles"));
+        assert_eq!(
+            run_action_args.header,
+            Some(vec![
+                "Content-Type:application/x-www-form-urlencoded".to_string()
+            ])
+        );
+        assert_eq!(
+            run_action_args.query_params,
+            Some(vec!["dry_run:true".to_string()])
+        );
+        assert_eq!(
+            run_action_args.body,
+            Some(vec!["role:admin;scope:all".to_string()])
+        );
+        assert!(run_action_args.url_encoded);
+    }
+
+    #[test]
+    fn test_header_args() {
+        let headers = [
+            (
+                "Content-Type".to_string(),
+                "application/json; charset=utf-8".to_string(),
+            ),
+            (
+                "Accept".to_string(),
+                "application/json; version=2".to_string(),
+            ),
+            ("X-Request-Id".to_string(), "42".to_string()),
+        ];
+        let (args, content_type) = header_args(headers.iter());
+        assert_eq!(args, vec!["Accept:application/json", "X-Request-Id:42"]);
+        assert_eq!(
+            content_type.as_deref(),
+            Some("application/json; charset=utf-8")
+        );
+    }
+}
@@ -0,0 +1,265 @@
+mod ca;
+pub(crate) mod capture;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use clap::Args;
+use colored::Colorize;
+use hyper::header::{HeaderName, CONNECTION, CONTENT_LENGTH, HOST, TRANSFER_ENCODING};
+use hyper::server::conn::Http;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use reqwest::Url;
+use tokio::net::TcpStream;
+
+use crate::db::db_handler::DBHandler;
+use crate::db::dto::Project;
+use crate::HOME_DIR;
+use ca::CertificateAuthority;
+use capture::Exchange;
+
+/// Headers of a single connection, not forwarded
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "proxy-connection",
+    "keep-alive",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[derive(Args)]
+pub struct RecordArgs {
+    /// Project receiving the recorded actions
+    #[arg(long)]
+    project: String,
+
+    /// Project url, needed if the project does not exist yet
+    #[arg(short, long)]
+    url: Option<String>,
+
+    /// port to listen on
+    #[arg(short, long, default_value_t = 8888)]
+    port: u16,
+
+    /// intercept https traffic using a generated local certificate authority
+    #[arg(long)]
+    mitm: bool,
+
+    /// do not check upstream ssl certificates
+    #[arg(short = 'k', long)]
+    insecure: bool,
+}
+
+struct Recorder {
+    db_handler: DBHandler,
+    project: Project,
+    client: reqwest::Client,
+    ca: Option<CertificateAuthority>,
+}
+
+fn error_response(status: StatusCode, message: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    response
+}
+
+impl Recorder {
+    /// only requests to the project url are recorded
+    fn is_recorded(&self, url: &Url) -> bool {
+        self.project.main_url.is_empty() || url.as_str().starts_with(&self.project.main_url)
+    }
+
+    /// forward a request upstream, record the exchange and return the response
+    async fn forward(&self, request: Request<Body>, url: Url) -> anyhow::Result<Response<Body>> {
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+
+        let mut upstream_request = self.client.request(parts.method.clone(), url.clone());
+        for (name, value) in parts.headers.iter().filter(|(name, _)| {
+            // ask for a plain response to record it as is
+            !HOP_BY_HOP_HEADERS.contains(&name.as_str())
+                && ![
+                    HOST,
+                    CONTENT_LENGTH,
+                    HeaderName::from_static("accept-encoding"),
+                ]
+                .contains(name)
+        }) {
+            upstream_request = upstream_request.header(name, value);
+        }
+        let upstream_response = upstream_request.body(body.to_vec()).send().await?;
+
+        let status = upstream_response.status();
+        let mut response = Response::builder().status(status);
+        for (name, value) in upstream_response.headers().iter().filter(|(name, _)| {
+            !HOP_BY_HOP_HEADERS.contains(&name.as_str())
+                && ![CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING].contains(name)
+        }) {
+            response = response.header(name, value);
+        }
+        let response_body = upstream_response.bytes().await?;
+
+        if self.is_recorded(&url) {
+            let exchange = Exchange {
+                verb: parts.method.to_string(),
+                url: url.clone(),
+                headers: parts
+                    .headers
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+                    .collect(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+                status: status.as_u16(),
+                response: String::from_utf8_lossy(&response_body).into_owned(),
+            };
+            let action = exchange.to_action(&self.project);
+            self.db_handler.upsert_action(&action).await?;
+            println!(
+                "{} {} {} saved as {}",
+                parts.method.as_str().yellow(),
+                url,
+                status.as_u16(),
+                action.name.unwrap_or_default().green()
+            );
+        } else {
+            println!(
+                "{}",
+                format!("{} {} {}", parts.method, url, status.as_u16()).bright_black()
+            );
+        }
+        Ok(response.body(Body::from(response_body))?)
+    }
+
+    /// CONNECT requests are tunneled, or decrypted then forwarded in mitm mode
+    async fn connect(self: Arc<Self>, request: Request<Body>) -> anyhow::Result<()> {
+        let authority = request
+            .uri()
+            .authority()
+            .ok_or(anyhow::anyhow!("Missing authority in CONNECT request"))?
+            .clone();
+        let mut upgraded = hyper::upgrade::on(request).await?;
+
+        let Some(ca) = self.ca.as_ref() else {
+            let mut upstream = TcpStream::connect(authority.as_str()).await?;
+            tokio::io::copy_bidirectional(&mut upgraded, &mut upstream).await?;
+            return Ok(());
+        };
+        let tls_stream = ca.acceptor(authority.host())?.accept(upgraded).await?;
+        let recorder = self.clone();
+        Http::new()
+            .http1_only(true)
+            .serve_connection(
+                tls_stream,
+                service_fn(move |request: Request<Body>| {
+                    let recorder = recorder.clone();
+                    let url = format!(
+                        "https://{}{}",
+                        authority,
+                        request
+                            .uri()
+                            .path_and_query()
+                            .map(|p| p.as_str())
+                            .unwrap_or("/")
+                    );
+                    async move { Ok::<_, Infallible>(recorder.handle(request, &url).await) }
+                }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn handle(&self, request: Request<Body>, url: &str) -> Response<Body> {
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        self.forward(request, url)
+            .await
+            .unwrap_or_else(|e| error_response(StatusCode::BAD_GATEWAY, e.to_string()))
+    }
+}
+
+impl RecordArgs {
+    async fn get_or_create_project(&self, db_handler: &DBHandler) -> anyhow::Result<Project> {
+        if let Ok(project) = db_handler.get_project(&self.project).await {
+            return Ok(project);
+        }
+        let Some(url) = self.url.as_ref() else {
+            anyhow::bail!(
+                "Project {} not found, specify its url with --url",
+                self.project
+            );
+        };
+        let project = Project {
+            id: None,
+            name: self.project.clone(),
+            main_url: url.clone(),
+            conf: None,
+            source: None,
+            created_at: None,
+            updated_at: None,
+        };
+        db_handler.upsert_project(&project).await?;
+        Ok(project)
+    }
+
+    /// Run the recording proxy until interrupted
+    pub async fn record(&self, db_handler: &DBHandler) -> anyhow::Result<()> {
+        let ca = if self.mitm {
+            let ca = CertificateAuthority::load_or_create(&HOME_DIR.join(".config/qapi"))?;
+            println!(
+                "Intercepting https traffic, trust the certificate {}",
+                ca.cert_path.display().to_string().blue()
+            );
+            Some(ca)
+        } else {
+            None
+        };
+        let recorder = Arc::new(Recorder {
+            db_handler: db_handler.clone(),
+            project: self.get_or_create_project(db_handler).await?,
+            client: reqwest::Client::builder()
+                .no_proxy()
+                .danger_accept_invalid_certs(self.insecure)
+                .build()?,
+            ca,
+        });
+
+        let make_service = make_service_fn(move |_| {
+            let recorder = recorder.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let recorder = recorder.clone();
+                    async move {
+                        if request.method() == Method::CONNECT {
+                            tokio::spawn(async move {
+                                if let Err(e) = recorder.connect(request).await {
+                                    eprintln!("{}", e.to_string().red());
+                                }
+                            });
+                            return Ok::<_, Infallible>(Response::new(Body::empty()));
+                        }
+                        // a forward proxy receives absolute urls
+                        let url = request.uri().to_string();
+                        Ok(recorder.handle(request, &url).await)
+                    }
+                }))
+            }
+        });
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
+        let server = Server::try_bind(&addr)?.serve(make_service);
+        println!(
+            "Recording proxy listening on {}, recording into {}",
+            format!("http://{}", addr).blue(),
+            self.project.green()
+        );
+        server.await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::process::exit;
+
+use crossterm::style::Stylize;
+use serde_json::{json, Value};
+
+use super::_run_helper::{get_body, load_body_reference};
+use crate::utils::{replace_with_conf, Interpol};
+
+/// Build the standard graphql POST envelope
+/// query can reference a file (@query.graphql), variables can be
+/// json, a file reference or body fields (id:=1)
+pub fn get_graphql_body(
+    query: &str,
+    variables: Option<&str>,
+    ctx: &HashMap<String, String>,
+) -> Option<String> {
+    let query = match load_body_reference(query) {
+        Ok(loaded) => loaded.unwrap_or(query.to_string()),
+        Err(e) => {
+            eprintln!("{}", format!("Invalid graphql query: {}", e).dark_red());
+            exit(1);
+        }
+    };
+    let query = replace_with_conf(&query, ctx, Interpol::MultiInterpol);
+    let variables = variables
+        .and_then(|v| get_body(v, ctx))
+        .map(|v| serde_json::from_str::<Value>(&v).unwrap_or(Value::String(v.into_owned())))
+        .unwrap_or(Value::Null);
+
+    let mut envelope = json!({ "query": query });
+    if !variables.is_null() {
+        envelope["variables"] = variables;
+    }
+    Some(envelope.to_string())
+}
+
+/// Messages of the errors[] array of a graphql response
+pub fn graphql_errors(response: &str) -> Vec<String> {
+    serde_json::from_str::<Value>(response)
+        .ok()
+        .and_then(|r| r.get("errors").and_then(|e| e.as_array()).cloned())
+        .unwrap_or_default()
+        .iter()
+        .map(|e| {
+            e.get("message")
+                .and_then(|m| m.as_str())
+                .map(String::from)
+                .unwrap_or_else(|| e.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_graphql_body() {
+        let ctx = HashMap::from([("ID".to_string(), "1".to_string())]);
+        let body = get_graphql_body("{ user(id: {{ID}}) { name } }", Some("lang:fr"), &ctx);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body.unwrap()).unwrap(),
+            json!({"query": "{ user(id: 1) { name } }", "variables": {"lang": "fr"}})
+        );
+    }
+
+    #[test]
+    fn test_graphql_errors() {
+        let response = r#"{"data": null, "errors": [{"message": "Unknown field"}]}"#;
+        assert_eq!(graphql_errors(response), vec!["Unknown field"]);
+        assert!(graphql_errors(r#"{"data": {"user": null}}"#).is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::commands::run::_graphql::graphql_errors;
 use crate::commands::run::_printer::Printer;
 use crate::http::FetchResult;
 use crate::json_path;
@@ -10,19 +11,14 @@ use std::collections::HashMap;
 pub struct HttpResult<'a> {
     pub(crate) fetch_result: anyhow::Result<&'a FetchResult, &'a anyhow::Error>,
     pub(crate) printer: &'a mut Printer,
+    pub(crate) is_graphql: bool,
+    pub(crate) is_stream: bool,
 }
 
 impl<'a> HttpResult<'a> {
-    fn extract_pattern(
-        &mut self,
-        (pattern_to_extract, value_name): (&str, Option<&str>),
-        response: &str,
-        pb: &ProgressBar,
-    ) -> Option<String> {
-        //anyhow::Result<String> {
-        let extracted = json_path::json_path(response, pattern_to_extract);
-
-        let extracted_as_string = extracted
+    /// extracted value as printed, strings being unquoted
+    fn extract_value(pattern_to_extract: &str, response: &str) -> Option<String> {
+        json_path::json_path(response, pattern_to_extract)
             .map(|value| match serde_json::to_string_pretty(&value) {
                 Ok(v) => {
                     if v.starts_with('\"') {
@@ -33,7 +29,38 @@ impl<'a> HttpResult<'a> {
                 }
                 Err(_) => "".to_owned(),
             })
-            .unwrap_or("".to_owned());
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Messages received from a websocket session or a stream,
+    /// the whole response otherwise
+    fn documents(&self, response: &str) -> Vec<String> {
+        if !self.is_stream {
+            return vec![response.to_string()];
+        }
+        serde_json::from_str::<Vec<serde_json::Value>>(response)
+            .unwrap_or_default()
+            .iter()
+            .map(|m| match m {
+                serde_json::Value::String(s) => s.clone(),
+                _ => m.to_string(),
+            })
+            .collect()
+    }
+
+    fn extract_pattern(
+        &mut self,
+        (pattern_to_extract, value_name): (&str, Option<&str>),
+        response: &str,
+        pb: &ProgressBar,
+    ) -> Option<String> {
+        // for streams, the pattern is extracted from each message
+        let extracted_as_string = self
+            .documents(response)
+            .iter()
+            .filter_map(|document| Self::extract_value(pattern_to_extract, document))
+            .collect::<Vec<String>>()
+            .join("\n");
 
         if extracted_as_string.is_empty() {
             pb.suspend(|| {
@@ -99,6 +126,13 @@ impl<'a> HttpResult<'a> {
     ) -> anyhow::Result<()> {
         match self.fetch_result {
             Ok(FetchResult { response, .. }) => {
+                // graphql errors are reported apart from http errors
+                if self.is_graphql {
+                    for error in graphql_errors(response) {
+                        self.printer
+                            .p_error(&format!("GraphQL error: {}", error), pb);
+                    }
+                }
                 match extract_pattern {
                     Some(pattern) => {
                         // qualify extract
@@ -110,7 +144,14 @@ impl<'a> HttpResult<'a> {
                                 if let (Some(value_name), Some(extracted_pattern)) =
                                     (value_name, extracted_pattern.as_ref())
                                 {
-                                    ctx.insert(value_name.to_string(), extracted_pattern.clone());
+                                    // the last message wins for streams
+                                    let last_value = self
+                                        .documents(response)
+                                        .iter()
+                                        .rev()
+                                        .find_map(|d| Self::extract_value(pattern, d))
+                                        .unwrap_or(extracted_pattern.clone());
+                                    ctx.insert(value_name.to_string(), last_value);
                                 }
 
                                 extracted_pattern
@@ -1,6 +1,7 @@
 use colored::Colorize;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
+use crate::commands::run::_graphql::graphql_errors;
 use crate::http::FetchResult;
 
 /// Create a new progress bar with custom style
@@ -36,6 +37,7 @@ pub fn add_progress_bar_for_request(multi_bar: &MultiProgress, message: &str) ->
 pub fn finish_progress_bar(
     pb: &ProgressBar,
     fetch_result: anyhow::Result<&FetchResult, &anyhow::Error>,
+    is_graphql: bool,
     message: &str,
 ) {
     pb.set_style(
@@ -46,10 +48,17 @@ pub fn finish_progress_bar(
     match fetch_result.as_ref() {
         Ok(fetch_result) => {
             let status = fetch_result.status.to_string();
-            let formatted_str = if fetch_result.is_success() {
-                format!("{} ✅", status.green())
+            let errors_count = if is_graphql {
+                graphql_errors(&fetch_result.response).len()
             } else {
+                0
+            };
+            let formatted_str = if !fetch_result.is_success() {
                 format!("{} ❌", status.red())
+            } else if errors_count > 0 {
+                format!("{} ⚠️  {} graphql errors", status.yellow(), errors_count)
+            } else {
+                format!("{} ✅", status.green())
             };
             pb.finish_with_message(format!("{}  {}", formatted_str, message));
         }
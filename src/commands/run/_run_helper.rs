@@ -1,16 +1,33 @@
 use crossterm::style::Stylize;
+use lazy_static::lazy_static;
 
 use crate::utils::{
-    parse_multiple_conf, parse_multiple_conf_as_opt_with_grouping_and_interpolation,
+    parse_body_fields, parse_multiple_conf_as_opt_with_grouping_and_interpolation,
     parse_multiple_conf_with_opt, replace_with_conf, Interpol,
 };
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::process::exit;
 
 use super::action::RunActionArgs;
 
 static ANONYMOUS_ACTION: &str = "UNKNOWN";
 
+/// a body starting with @ references a file, @- references stdin
+const BODY_REFERENCE_PREFIX: char = '@';
+const STDIN_REFERENCE: &str = "-";
+
+lazy_static! {
+    /// stdin can be consumed only once, keep it for all requests
+    static ref STDIN_BODY: String = {
+        let mut content = String::new();
+        let _ = io::stdin().read_to_string(&mut content);
+        content
+    };
+}
+
 /// Check if action is anonymous
 pub fn is_anonymous_action(action_name: &str) -> bool {
     action_name == ANONYMOUS_ACTION
@@ -54,18 +71,59 @@ impl<'bt> From<&'bt str> for BodyType<'bt> {
 }
 
 /// private method for getting body
-fn _get_body<'a>(str: &str, interpolated_body: Cow<'a, str>, body: &str) -> Option<Cow<'a, str>> {
-    if str.contains('{') {
+/// a json body is sent as is, otherwise body fields are parsed
+fn _get_body<'a>(str: &str, interpolated_body: Cow<'a, str>) -> Option<Cow<'a, str>> {
+    let trimmed = str.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
         return Some(interpolated_body);
     }
-    let body_as_map = parse_multiple_conf(body);
-    Some(Cow::Owned(
-        serde_json::to_string(&body_as_map).ok().unwrap(),
-    ))
+    match parse_body_fields(str) {
+        Ok(body_as_json) => Some(Cow::Owned(body_as_json.to_string())),
+        Err(e) => {
+            eprintln!("{}", format!("Invalid body: {}", e).dark_red());
+            exit(1);
+        }
+    }
+}
+
+/// Check if the body references a file or stdin
+pub fn is_body_reference(body: &str) -> bool {
+    body.starts_with(BODY_REFERENCE_PREFIX)
+}
+
+/// Load the content of a body reference, None if the body is not a reference.
+/// A referenced file that can not be read as utf-8 (binary payload) is None too,
+/// the reference is then sent as raw bytes. A missing file is an error
+pub fn load_body_reference(body: &str) -> anyhow::Result<Option<String>> {
+    let Some(reference) = body.strip_prefix(BODY_REFERENCE_PREFIX) else {
+        return Ok(None);
+    };
+    if reference == STDIN_REFERENCE {
+        return Ok(Some(STDIN_BODY.clone()));
+    }
+    match fs::read_to_string(reference) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => Ok(None),
+        Err(e) => anyhow::bail!("Cannot read {}: {}", reference, e),
+    }
 }
 
 /// Body interpolation
+/// A referenced body (@file or @-) is loaded then interpolated as a template,
+/// the reference itself is what is saved in actions
 pub fn get_body<'a>(body: &'a str, ctx: &HashMap<String, String>) -> Option<Cow<'a, str>> {
+    if is_body_reference(body) {
+        return match load_body_reference(body) {
+            Ok(Some(content)) => Some(Cow::Owned(
+                replace_with_conf(&content, ctx, Interpol::MultiInterpol).into_owned(),
+            )),
+            Ok(None) => Some(Cow::Borrowed(body)),
+            Err(e) => {
+                eprintln!("{}", format!("Invalid body: {}", e).dark_red());
+                exit(1);
+            }
+        };
+    }
     let interpolated_body = replace_with_conf(body, ctx, Interpol::MultiInterpol);
 
     match &interpolated_body {
@@ -75,15 +133,39 @@ pub fn get_body<'a>(body: &'a str, ctx: &HashMap<String, String>) -> Option<Cow<
             match body_type {
                 BodyType::Empty => None,
                 BodyType::Default(default_body_as_str) => {
-                    _get_body(default_body_as_str, interpolated_body.clone(), body)
+                    _get_body(default_body_as_str, interpolated_body.clone())
                 }
             }
         }
         // body had some interpolated value
-        Cow::Owned(body_value) => _get_body(body_value, interpolated_body.clone(), body),
+        Cow::Owned(body_value) => _get_body(body_value, interpolated_body.clone()),
     }
 }
 
+/// Websocket messages interpolation
+/// a referenced file (@path) sends one message per line, a missing one is an error
+pub fn get_ws_messages(
+    messages: &[String],
+    ctx: &HashMap<String, String>,
+) -> anyhow::Result<Vec<String>> {
+    let mut all = vec![];
+    for message in messages {
+        match load_body_reference(message)? {
+            Some(content) => all.extend(
+                content
+                    .lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(String::from),
+            ),
+            None => all.push(message.clone()),
+        }
+    }
+    Ok(all
+        .iter()
+        .map(|message| replace_with_conf(message, ctx, Interpol::MultiInterpol).into_owned())
+        .collect())
+}
+
 /// complete an url with http if not present
 /// if requested url starts with http or https, do nothing
 /// if requested url starts with :, add http://localhost
@@ -92,9 +174,9 @@ pub fn get_body<'a>(body: &'a str, ctx: &HashMap<String, String>) -> Option<Cow<
 /// assert_eq!(complete_url("http://localhost:8080"), "http://localhost:8080");
 /// assert_eq!(complete_url(":8080"), "https://localhost:8080");
 /// ```
-fn complete_url(url: &str) -> Cow<str> {
-    // if url starts with http, do nothing
-    if url.starts_with("http") {
+fn complete_url(url: &str) -> Cow<'_, str> {
+    // if url starts with http or has a scheme, do nothing
+    if url.starts_with("http") || url.contains("://") {
         return Cow::Borrowed(url);
     }
     if url.starts_with(':') {
@@ -106,6 +188,9 @@ fn complete_url(url: &str) -> Cow<str> {
 
 fn get_full_url<'a>(project_url: Option<&'a str>, action_url: &'a str) -> Cow<'a, str> {
     match project_url {
+        Some(main_url) if action_url.is_empty() => complete_url(main_url),
+        // absolute action url, e.g. recorded from another host
+        Some(_) if action_url.contains("://") => Cow::Borrowed(action_url),
         Some(main_url) => Cow::Owned(format!("{}/{}", complete_url(main_url), action_url)),
         None => complete_url(action_url),
     }
@@ -120,7 +205,13 @@ pub fn get_computed_urls(
     action_url: &str,
     ctx: &HashMap<String, String>,
 ) -> HashSet<String> {
-    let full_url = get_full_url(project_url, action_url).into_owned();
+    // {{vars}} of the url, e.g. imported from .http files
+    let full_url = replace_with_conf(
+        &get_full_url(project_url, action_url),
+        ctx,
+        Interpol::MultiInterpol,
+    )
+    .into_owned();
 
     // returning url with no interpolation
     // to be checked later
@@ -225,6 +316,33 @@ pub fn merge_with(run_actions_args: &RunActionArgs, o: &RunActionArgs) -> RunAct
     if o.url_encoded {
         clone.url_encoded = o.url_encoded;
     }
+    if o.query.is_some() {
+        clone.query = o.query.clone();
+    }
+    if o.variables.is_some() {
+        clone.variables = o.variables.clone();
+    }
+    if o.message.is_some() {
+        clone.message = o.message.clone();
+    }
+    if o.listen.is_some() {
+        clone.listen = o.listen.clone();
+    }
+    if o.proto.is_some() {
+        clone.proto = o.proto.clone();
+    }
+    if o.stream {
+        clone.stream = o.stream;
+    }
+    if o.max_messages.is_some() {
+        clone.max_messages = o.max_messages;
+    }
+    if o.until.is_some() {
+        clone.until = o.until.clone();
+    }
+    if o.content_type.is_some() {
+        clone.content_type = o.content_type.clone();
+    }
     clone
 }
 
@@ -240,6 +358,7 @@ mod tests {
         );
         assert_eq!(complete_url(":8080"), "http://localhost:8080");
         assert_eq!(complete_url("google.com"), "https://google.com");
+        assert_eq!(complete_url("wss://echo.org"), "wss://echo.org");
     }
 
     #[test]
@@ -250,4 +369,45 @@ mod tests {
         );
         assert_eq!(get_full_url(None, ":8080"), "http://localhost:8080");
     }
+
+    #[test]
+    fn test_get_computed_urls_with_vars() {
+        let ctx = HashMap::from([("id".to_string(), "42".to_string())]);
+        assert_eq!(
+            get_computed_urls("", Some("http://localhost:8080"), "users/{{id}}", &ctx),
+            HashSet::from(["http://localhost:8080/users/42".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_get_body_from_template_file() {
+        let path = std::env::temp_dir().join("apikrab_body_template.json");
+        fs::write(&path, r#"{"name": "{{NAME}}"}"#).unwrap();
+        let ctx = HashMap::from([("NAME".to_string(), "Marco".to_string())]);
+
+        let reference = format!("@{}", path.display());
+        assert_eq!(get_body(&reference, &ctx).unwrap(), r#"{"name": "Marco"}"#);
+        // a missing file is an error, a binary one is sent as raw bytes
+        assert!(load_body_reference("@/does/not/exist.bin").is_err());
+        let binary = std::env::temp_dir().join("apikrab_body_binary.bin");
+        fs::write(&binary, [0xff, 0xfe, 0x00]).unwrap();
+        let reference = format!("@{}", binary.display());
+        assert!(load_body_reference(&reference).unwrap().is_none());
+        assert_eq!(get_body(&reference, &ctx).unwrap(), reference);
+    }
+
+    #[test]
+    fn test_get_ws_messages_from_file() {
+        let path = std::env::temp_dir().join("apikrab_ws_messages.txt");
+        fs::write(&path, "{\"subscribe\": \"{{ROOM}}\"}\n\nping\n").unwrap();
+        let ctx = HashMap::from([("ROOM".to_string(), "news".to_string())]);
+
+        let messages = vec![format!("@{}", path.display()), "bye".to_string()];
+        assert_eq!(
+            get_ws_messages(&messages, &ctx).unwrap(),
+            vec![r#"{"subscribe": "news"}"#, "ping", "bye"]
+        );
+        // a missing file is not sent as a literal message
+        assert!(get_ws_messages(&["@/does/not/exist.json".to_string()], &ctx).is_err());
+    }
 }
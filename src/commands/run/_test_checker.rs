@@ -1,6 +1,10 @@
+use crate::commands::run::_graphql::graphql_errors;
 use crate::commands::run::action::R;
 use crate::http::FetchResult;
-use assert_json_diff::{assert_json_eq, assert_json_include};
+use crate::json_path::json_path_matches;
+use assert_json_diff::{
+    assert_json_eq, assert_json_include, assert_json_matches_no_panic, CompareMode, Config,
+};
 use crossterm::style::Stylize;
 use indicatif::ProgressBar;
 use serde_json::{from_str, Value};
@@ -27,10 +31,16 @@ const INT: &str = "INT";
 const FLOAT: &str = "FLOAT";
 const REGEX: &str = "REGEX";
 const EMAIL: &str = "EMAIL";
+const GRAPHQL_NO_ERRORS: &str = "GRAPHQL_NO_ERRORS";
+const MESSAGE: &str = "MESSAGE";
+const ANY_MESSAGE: &str = "ANY_MESSAGE";
 
 #[derive(Debug)]
 pub enum TestFn {
     StatusCode,
+    GraphqlNoErrors,
+    Message(usize),
+    AnyMessage,
     JsonInclude(String),
     JsonEq(String),
     Int(i64),
@@ -46,6 +56,12 @@ impl FromStr for TestFn {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(if s == STATUS_CODE {
             TestFn::StatusCode
+        } else if s == GRAPHQL_NO_ERRORS {
+            TestFn::GraphqlNoErrors
+        } else if s == ANY_MESSAGE {
+            TestFn::AnyMessage
+        } else if s.starts_with(&format!("{}(", MESSAGE)) {
+            TestFn::Message(get_args::<usize>(s, MESSAGE)?)
         } else if s.starts_with(JSON_INCLUDE) {
             TestFn::JsonInclude(get_args::<String>(s, JSON_INCLUDE)?)
         } else if s.starts_with(JSON_EQ) {
@@ -57,7 +73,7 @@ impl FromStr for TestFn {
         } else if s.starts_with(REGEX) {
             TestFn::Regex(regex::Regex::new(s)?)
         } else if s.starts_with(EMAIL) {
-            TestFn::Email(regex::Regex::new("^[\\w-\\.]+@([\\w-]+\\.)+[\\w-]{2,4}$")?)
+            TestFn::Email(regex::Regex::new("^[\\w.-]+@([\\w-]+\\.)+[\\w-]{2,4}$")?)
         } else {
             TestFn::NoMatch
         })
@@ -65,9 +81,9 @@ impl FromStr for TestFn {
 }
 
 pub struct TestChecker<'a> {
-    pub fetch_results: &'a Vec<R>,
-    pub ctx: &'a HashMap<String, String>,
-    pub expected: &'a HashMap<String, String>,
+    pub fetch_results: &'a [R],
+    /// repeated keys are all checked
+    pub expected: &'a [(String, String)],
 }
 
 impl<'a> TestChecker<'a> {
@@ -107,7 +123,7 @@ impl<'a> TestChecker<'a> {
                 if is_err {
                     self.print_err(key, ctx_value, regex.as_str());
                 }
-                is_err
+                !is_err
             }
             None => {
                 self.print_err(key, "<empty str>", regex.as_str());
@@ -116,6 +132,38 @@ impl<'a> TestChecker<'a> {
         }
     }
 
+    /// Check a websocket message against a json test or a plain value
+    fn message_check(&self, key: &str, message: &Value, expected: &str) -> bool {
+        let json_check = |json_to_test: &str, mode: CompareMode| {
+            from_str::<Value>(json_to_test)
+                .map_err(|e| e.to_string())
+                .and_then(|json| assert_json_matches_no_panic(message, &json, Config::new(mode)))
+        };
+        let r = match TestFn::from_str(expected) {
+            Ok(TestFn::JsonInclude(json_to_test)) => {
+                json_check(&json_to_test, CompareMode::Inclusive)
+            }
+            Ok(TestFn::JsonEq(json_to_test)) => json_check(&json_to_test, CompareMode::Strict),
+            _ => match message {
+                Value::String(s) if s == expected => Ok(()),
+                m if from_str::<Value>(expected).ok().as_ref() == Some(m) => Ok(()),
+                _ => Err("".to_string()),
+            },
+        };
+        if r.is_err() {
+            self.print_err(key, &message.to_string(), expected);
+        }
+        r.is_ok()
+    }
+
+    /// Received websocket messages, the response of a websocket session
+    fn messages(result: &FetchResult) -> Vec<Value> {
+        from_str::<Value>(&result.response)
+            .ok()
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+    }
+
     pub fn _check(&self, result: &FetchResult, ctx: &HashMap<String, String>) -> bool {
         let r = self
             .expected
@@ -129,6 +177,31 @@ impl<'a> TestChecker<'a> {
                     }
                     true
                 }
+                Ok(TestFn::GraphqlNoErrors) => {
+                    let errors = graphql_errors(&result.response);
+                    if !errors.is_empty() {
+                        self.print_err(GRAPHQL_NO_ERRORS, &errors.join(", "), "no errors");
+                        return false;
+                    }
+                    true
+                }
+                Ok(TestFn::Message(n)) => match Self::messages(result).get(n.wrapping_sub(1)) {
+                    Some(message) => self.message_check(key, message, value),
+                    None => {
+                        self.print_err(key, "<no message>", value);
+                        false
+                    }
+                },
+                Ok(TestFn::AnyMessage) => {
+                    // a message matches if the json path extracts something
+                    let is_match = Self::messages(result)
+                        .iter()
+                        .any(|message| json_path_matches(&message.to_string(), value));
+                    if !is_match {
+                        self.print_err(ANY_MESSAGE, "<no matching message>", value);
+                    }
+                    is_match
+                }
                 Ok(TestFn::NoMatch) => match TestFn::from_str(value) {
                     Ok(TestFn::JsonInclude(json_to_test)) => catch_unwind(|| {
                         assert_json_include!(
@@ -154,7 +227,7 @@ impl<'a> TestChecker<'a> {
                             if is_err {
                                 self.print_err(key, ctx_value, value);
                             }
-                            is_err
+                            !is_err
                         }
                         None => {
                             self.print_err(key, "<empty str>", value);
@@ -221,3 +294,64 @@ impl<'a> TestChecker<'a> {
         r
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_check_context_expectations() {
+        let result = FetchResult {
+            response: "{}".to_string(),
+            status: 200,
+            duration: Duration::default(),
+        };
+        let expected = [
+            ("email".to_string(), "EMAIL".to_string()),
+            ("id".to_string(), "1".to_string()),
+        ];
+        let checker = TestChecker {
+            fetch_results: &[],
+            expected: &expected,
+        };
+        let ctx = |email: &str, id: &str| {
+            HashMap::from([
+                ("email".to_string(), email.to_string()),
+                ("id".to_string(), id.to_string()),
+            ])
+        };
+        assert!(checker._check(&result, &ctx("marco@example.com", "1")));
+        // a value not matching its expectation fails the check
+        assert!(!checker._check(&result, &ctx("not an email", "1")));
+        assert!(!checker._check(&result, &ctx("marco@example.com", "2")));
+    }
+
+    #[test]
+    fn test_check_repeated_message_expectations() {
+        let result = FetchResult {
+            response: r#"[{"hello": "marco"}, {"ready": true}]"#.to_string(),
+            status: 101,
+            duration: Duration::default(),
+        };
+        let expected = |first: &str| {
+            vec![
+                ("ANY_MESSAGE".to_string(), first.to_string()),
+                ("ANY_MESSAGE".to_string(), "$.ready".to_string()),
+            ]
+        };
+        let hello = expected("$.hello");
+        let checker = TestChecker {
+            fetch_results: &[],
+            expected: &hello,
+        };
+        assert!(checker._check(&result, &HashMap::new()));
+        // the first expectation is not overridden by the second one
+        let closed = expected("$.closed");
+        let checker = TestChecker {
+            fetch_results: &[],
+            expected: &closed,
+        };
+        assert!(!checker._check(&result, &HashMap::new()));
+    }
+}
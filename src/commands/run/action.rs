@@ -1,3 +1,7 @@
+use crate::callback::{
+    CallbackListener, CALLBACK_EXPECT_PREFIX, CALLBACK_PATH_PREFIX, CALLBACK_URL_KEY,
+};
+use crate::cassette::CassetteArgs;
 use crate::commands::run::_http_result::HttpResult;
 use crate::commands::run::_printer::Printer;
 use crate::commands::run::_run_helper::check_input;
@@ -6,20 +10,23 @@ use crate::db::dto::{Action, Context, Project, TestSuite, TestSuiteInstance};
 use crate::domain::DomainAction;
 use crate::http;
 use crate::http::FetchResult;
-use crate::utils::{parse_cli_conf_to_map, val_or_join, SEP, SINGLE_INTERPOL_START};
+use crate::stream::StopCondition;
+use crate::utils::{parse_cli_conf_to_pairs, val_or_join, SEP, SINGLE_INTERPOL_START};
 use clap::Args;
 use core::panic;
 use crossterm::style::Stylize;
 use indicatif::{MultiProgress, ProgressBar};
 use itertools::Itertools;
+use reqwest::header::CONTENT_TYPE;
 use serde::{Deserialize, Serialize};
 use serde_json::to_string;
 use std::collections::HashMap;
 use std::process::exit;
 use std::time::Duration;
 
+use super::_graphql::get_graphql_body;
 use super::_progress_bar::new_pb;
-use super::_run_helper::{is_anonymous_action, merge_with};
+use super::_run_helper::{get_ws_messages, is_anonymous_action, merge_with};
 use super::_test_checker::TestChecker;
 
 #[derive(Debug)]
@@ -36,6 +43,10 @@ pub struct CurrentActionData<'a> {
     xtract_path: &'a str,
     path_params: &'a str,
     query_params: &'a str,
+    graphql_query: Option<&'a str>,
+    graphql_variables: Option<&'a str>,
+    ws_messages: Option<&'a Vec<String>>,
+    stream: bool,
 }
 
 impl CurrentActionData<'_> {
@@ -45,7 +56,19 @@ impl CurrentActionData<'_> {
         project: Option<&Project>,
         ctx: &HashMap<String, String>,
     ) -> DomainAction {
-        DomainAction::from_current_action_data(
+        // a graphql query replaces the body with the graphql envelope
+        let graphql_body = self
+            .graphql_query
+            .or(run_action_args.query.as_deref())
+            .and_then(|query| {
+                get_graphql_body(
+                    query,
+                    self.graphql_variables
+                        .or(run_action_args.variables.as_deref()),
+                    ctx,
+                )
+            });
+        let mut domain_action = DomainAction::from_current_action_data(
             self.name,
             run_action_args
                 .verb
@@ -59,7 +82,9 @@ impl CurrentActionData<'_> {
                 .as_str(),
             val_or_join(self.header, run_action_args.header.as_ref()).as_ref(),
             (
-                self.body,
+                graphql_body
+                    .as_deref()
+                    .unwrap_or(&val_or_join(self.body, run_action_args.body.as_ref())),
                 run_action_args.url_encoded,
                 run_action_args.form_data,
             ),
@@ -69,7 +94,38 @@ impl CurrentActionData<'_> {
             project,
             None,
             ctx,
-        )
+        );
+        if let Some(content_type) = run_action_args.content_type.as_ref() {
+            domain_action
+                .headers
+                .get_or_insert_with(HashMap::new)
+                .insert(CONTENT_TYPE.to_string(), content_type.clone());
+        }
+        domain_action.messages = self
+            .ws_messages
+            .or(run_action_args.message.as_ref())
+            .map(|messages| {
+                get_ws_messages(messages, ctx).unwrap_or_else(|e| {
+                    eprintln!("{}", format!("Invalid message: {}", e).dark_red());
+                    exit(1);
+                })
+            })
+            .unwrap_or_default();
+        domain_action.protos = run_action_args.proto.clone().unwrap_or_default();
+        domain_action.stream = run_action_args.stream || self.stream;
+        domain_action.stop_condition = StopCondition {
+            max_messages: run_action_args.max_messages,
+            until: run_action_args.until.clone(),
+        };
+        if graphql_body.is_some() {
+            domain_action.is_graphql = true;
+            domain_action
+                .headers
+                .get_or_insert_with(HashMap::new)
+                .entry(CONTENT_TYPE.to_string())
+                .or_insert("application/json".to_string());
+        }
+        domain_action
     }
 }
 
@@ -81,7 +137,7 @@ pub struct RunActionArgs {
     #[arg(short, long)]
     pub(crate) url: Option<String>,
 
-    #[arg(short, long, value_parser = ["GET", "POST", "PUT", "DELETE"])]
+    #[arg(short, long, value_parser = ["GET", "POST", "PUT", "PATCH", "DELETE", "WS", "GRPC"])]
     pub(crate) verb: Option<String>,
 
     /// path params separated by a ,
@@ -92,10 +148,43 @@ pub struct RunActionArgs {
     #[arg(short, long)]
     pub(crate) query_params: Option<Vec<String>>,
 
-    /// body of the action
+    /// body of the action, @path to read it from a file, @- from stdin
     #[arg(short, long)]
     pub(crate) body: Option<Vec<String>>,
 
+    /// content type of the body, e.g. for raw binary bodies
+    #[arg(long)]
+    pub(crate) content_type: Option<String>,
+
+    /// graphql query, @path to read it from a file
+    #[arg(long)]
+    pub(crate) query: Option<String>,
+
+    /// graphql variables as json or key:value
+    #[arg(long)]
+    pub(crate) variables: Option<String>,
+
+    /// websocket message to send, @path to send each line of a file
+    #[arg(short, long)]
+    pub(crate) message: Option<Vec<String>>,
+
+    /// .proto file describing a grpc service, server reflection is used if none
+    #[arg(long)]
+    pub(crate) proto: Option<Vec<String>>,
+
+    /// consume the response as a stream of events (Server-Sent Events, NDJSON...)
+    #[arg(long)]
+    #[serde(default)]
+    pub(crate) stream: bool,
+
+    /// stop a websocket session or a stream after this number of messages
+    #[arg(long)]
+    pub(crate) max_messages: Option<usize>,
+
+    /// stop a websocket session or a stream when a message matches this json path
+    #[arg(long)]
+    pub(crate) until: Option<String>,
+
     /// multipart form data
     #[arg(short = 'H', long)]
     pub(crate) header: Option<Vec<String>>,
@@ -155,28 +244,51 @@ pub struct RunActionArgs {
     #[arg(short, long)]
     #[serde(default)]
     pub(crate) timeout: Option<u64>,
+
+    /// listen for a webhook on this address, e.g. :9000 or 192.168.1.5:9000, exposed as {{$callback_url}}
+    #[arg(long)]
+    pub(crate) listen: Option<String>,
+
+    #[command(flatten)]
+    #[serde(skip)]
+    pub(crate) cassette: CassetteArgs,
 }
 
 impl RunActionArgs {
+    /// expectations are checked against the last http results,
+    /// the CALLBACK ones against the inbound callback if any
     pub async fn run_test_if_needed(
         &self,
         action_results: &[Vec<R>],
-        ctx: &HashMap<String, String>,
+        callback_result: Option<&R>,
         main_pb: &ProgressBar,
     ) -> Vec<bool> {
         // if expect run test check
         let last_results = action_results.last();
         let mut tests_is_success = vec![];
-        if let Some(lr) = last_results {
-            let expected = parse_cli_conf_to_map(self.expect.as_ref());
-            if let Some(ex) = &expected {
-                tests_is_success = TestChecker {
-                    fetch_results: lr,
-                    ctx,
-                    expected: ex,
-                }
-                .check(self.name.as_deref().unwrap_or("flow"), main_pb);
+        let Some(expected) = parse_cli_conf_to_pairs(self.expect.as_ref()) else {
+            return tests_is_success;
+        };
+        let (callback_expected, expected): (Vec<_>, Vec<_>) =
+            expected.into_iter().partition(|(key, _)| {
+                callback_result.is_some() && key.starts_with(CALLBACK_EXPECT_PREFIX)
+            });
+        let flow_name = self.name.as_deref().unwrap_or("flow");
+        if let Some(lr) = last_results.filter(|_| !expected.is_empty()) {
+            tests_is_success = TestChecker {
+                fetch_results: lr,
+                expected: &expected,
             }
+            .check(flow_name, main_pb);
+        }
+        if let Some(callback_result) = callback_result.filter(|_| !callback_expected.is_empty()) {
+            tests_is_success.extend(
+                TestChecker {
+                    fetch_results: std::slice::from_ref(callback_result),
+                    expected: &callback_expected,
+                }
+                .check(&format!("{} callback", flow_name), main_pb),
+            );
         }
         tests_is_success
     }
@@ -332,7 +444,7 @@ impl RunActionArgs {
         Ok(())
     }
 
-    pub fn get_action_data(&self) -> Vec<CurrentActionData> {
+    pub fn get_action_data(&self) -> Vec<CurrentActionData<'_>> {
         // check input and return an error if needed
         if let Err(msg) = check_input(self) {
             eprintln!("{}", msg);
@@ -347,13 +459,19 @@ impl RunActionArgs {
             self.path_params.as_ref().unwrap().iter(),
             self.query_params.as_ref().unwrap().iter(),
         )
-        .map(|d| CurrentActionData {
+        .enumerate()
+        .map(|(i, d)| CurrentActionData {
             name: d.0,
             header: d.1,
             body: d.2,
             xtract_path: d.3,
             path_params: d.4,
             query_params: d.5,
+            // graphql options only apply to the first action of a chain
+            graphql_query: self.query.as_deref().filter(|_| i == 0),
+            graphql_variables: self.variables.as_deref().filter(|_| i == 0),
+            ws_messages: self.message.as_ref().filter(|_| i == 0),
+            stream: self.stream && i == 0,
         })
         .collect_vec()
     }
@@ -385,6 +503,22 @@ impl RunActionArgs {
         // create printer to print results
         let mut printer = Printer::new(self.quiet, self.clipboard, self.grep);
 
+        // webhook listener, its url is available as {{$callback_url}}
+        let mut listener = match self.listen.as_deref() {
+            Some(addr) => match CallbackListener::listen(addr).await {
+                Ok(listener) => {
+                    ctx.insert(CALLBACK_URL_KEY.to_string(), listener.url.clone());
+                    Some(listener)
+                }
+                Err(e) => {
+                    eprintln!("Error listening on {}: {}", addr, e);
+                    exit(1);
+                }
+            },
+            None => None,
+        };
+        let mut callback_extract_path = HashMap::new();
+
         // creating progress bars here
         let multi_bar = multi.cloned().unwrap_or(MultiProgress::new());
 
@@ -447,6 +581,22 @@ impl RunActionArgs {
             if !runnable_action.can_be_run() {
                 continue;
             }
+            // values of the callback are extracted once it is received
+            if listener.is_some() {
+                if let Some(extract_path) = runnable_action.extract_path.as_mut() {
+                    extract_path.retain(|pattern, value_name| {
+                        if !pattern.starts_with(CALLBACK_PATH_PREFIX) {
+                            return true;
+                        }
+                        callback_extract_path.insert(pattern.clone(), value_name.clone());
+                        false
+                    });
+                }
+                runnable_action.extract_path = runnable_action
+                    .extract_path
+                    .take()
+                    .filter(|extract_path| !extract_path.is_empty());
+            }
             action_results.push(
                 runnable_action
                     .run(action.as_ref(), db, http, &multi_bar)
@@ -456,6 +606,8 @@ impl RunActionArgs {
                         let _ = HttpResult {
                             fetch_result: result.as_ref(),
                             printer: &mut printer,
+                            is_graphql: runnable_action.is_graphql,
+                            is_stream: runnable_action.is_stream(),
                         }
                         .handle_result(
                             runnable_action.extract_path.as_ref(),
@@ -473,6 +625,34 @@ impl RunActionArgs {
             main_pb.inc(1);
         } // end for
 
+        // the inbound callback is the last result of the flow
+        let mut callback_result = None;
+        if let Some(mut listener) = listener.take() {
+            let result = listener.wait(http.timeout).await;
+            let _ = HttpResult {
+                fetch_result: result.as_ref(),
+                printer: &mut printer,
+                is_graphql: false,
+                is_stream: false,
+            }
+            .handle_result(
+                Some(&callback_extract_path).filter(|p| !p.is_empty()),
+                &mut ctx,
+                &main_pb,
+            );
+            ctx.remove(CALLBACK_URL_KEY);
+            // values extracted from the callback are checked with the http results
+            for r in action_results.last_mut().into_iter().flatten() {
+                r.ctx = ctx.clone();
+            }
+            callback_result = Some(R {
+                url: listener.url.clone(),
+                result,
+                ctx: ctx.clone(),
+            });
+            listener.close().await;
+        }
+
         // saving current session context
         if db
             .insert_conf(&Context {
@@ -485,8 +665,9 @@ impl RunActionArgs {
         }
         // if expect run test check
         let test_results = self
-            .run_test_if_needed(&action_results, &ctx, &main_pb)
+            .run_test_if_needed(&action_results, callback_result.as_ref(), &main_pb)
             .await;
+        action_results.extend(callback_result.map(|r| vec![r]));
 
         // finishing progress bar
         main_pb.finish();
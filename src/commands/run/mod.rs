@@ -1,3 +1,4 @@
+pub(crate) mod _graphql;
 pub(crate) mod _http_result;
 pub(crate) mod _printer;
 pub(crate) mod _progress_bar;
@@ -27,13 +28,40 @@ pub enum RunCommands {
     /// override verb to PUT
     #[command(alias = "PUT")]
     Put(Box<RunActionArgs>),
+    /// override verb to PATCH
+    #[command(alias = "PATCH")]
+    Patch(Box<RunActionArgs>),
     /// override verb to PUT
     #[command(alias = "DELETE")]
     Delete(Box<RunActionArgs>),
 
+    /// Run a graphql query, verb is POST
+    #[command(alias = "gql")]
+    Graphql(Box<RunActionArgs>),
+
+    /// Open a websocket session, send messages and collect received frames
+    #[command(alias = "WS")]
+    Ws(Box<RunActionArgs>),
+
+    /// Run a unary grpc call
+    #[command(alias = "GRPC")]
+    Grpc(GrpcArgs),
+
     /// Run an action
     Action(Box<RunActionArgs>),
     /// Run a saved test suite
     #[command(alias = "ts")]
     TestSuite(test_suite::TestSuiteArgs),
 }
+
+#[derive(Args)]
+pub struct GrpcArgs {
+    /// server address, e.g. http://localhost:50051 or :50051, https is used without scheme
+    pub target: String,
+
+    /// fully qualified method, e.g. helloworld.Greeter/SayHello
+    pub method: String,
+
+    #[command(flatten)]
+    pub run_action_args: Box<RunActionArgs>,
+}
@@ -1,3 +1,4 @@
+use crate::cassette::CassetteArgs;
 use crate::commands::run::_progress_bar::init_progress_bars;
 use crate::db::db_handler::DBHandler;
 use crate::db::dto::TestSuiteInstance;
@@ -7,6 +8,7 @@ use colored::Colorize;
 use indicatif::MultiProgress;
 use serde_json::from_str;
 
+use super::_run_helper::merge_with;
 use super::action::RunActionArgs;
 
 #[derive(Args, Debug, Clone)]
@@ -17,6 +19,13 @@ pub struct TestSuiteArgs {
     /// Debug output
     #[arg(short, long)]
     debug: bool,
+
+    /// listen for a webhook on this address in every step, e.g. :9000, exposed as {{$callback_url}}
+    #[arg(long)]
+    listen: Option<String>,
+
+    #[command(flatten)]
+    pub(crate) cassette: CassetteArgs,
 }
 
 impl TestSuiteArgs {
@@ -28,7 +37,14 @@ impl TestSuiteArgs {
         multi_progress: &MultiProgress,
         pb: &indicatif::ProgressBar,
     ) -> anyhow::Result<bool> {
-        let mut run_args = from_str::<RunActionArgs>(&test.run_action_args)?;
+        let overrides = RunActionArgs {
+            listen: self.listen.clone(),
+            ..Default::default()
+        };
+        let mut run_args = merge_with(
+            &from_str::<RunActionArgs>(&test.run_action_args)?,
+            &overrides,
+        );
         run_args.force = true;
         run_args.quiet = !self.debug;
         // disable all saving !
@@ -66,3 +82,67 @@ impl TestSuiteArgs {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::workspace::{Resolution, Workspace};
+
+    #[tokio::test]
+    async fn test_step_with_callback_expectation() {
+        let root = std::env::temp_dir().join("apikrab_test_suite_listen_test");
+        let _ = std::fs::remove_dir_all(&root);
+        let mut db = DBHandler::default();
+        db.init_db(&Workspace {
+            resolution: Resolution::Override,
+            files: None,
+            database: root.join("qapi.sqlite"),
+        })
+        .await
+        .unwrap();
+
+        // the step calls the listener itself, standing for the webhook of the api
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let step = |expect: &str| TestSuiteInstance {
+            id: None,
+            test_suite_name: "webhooks".to_string(),
+            run_action_args: serde_json::to_string(&RunActionArgs {
+                url: Some(format!("http://127.0.0.1:{}/hooks", port)),
+                verb: Some("POST".to_string()),
+                body: Some(vec![r#"{"status": "paid"}"#.to_string()]),
+                expect: Some(vec![
+                    "STATUS_CODE:200".to_string(),
+                    format!("CALLBACK:JSON_INCLUDE({})", expect),
+                ]),
+                ..Default::default()
+            })
+            .unwrap(),
+            created_at: None,
+            updated_at: None,
+        };
+        let args = TestSuiteArgs {
+            name: "webhooks".to_string(),
+            debug: false,
+            listen: Some(format!("127.0.0.1:{}", port)),
+            cassette: CassetteArgs::default(),
+        };
+        let api = Api::new(Some(5), false);
+        let (multi, pb) = init_progress_bars(2);
+
+        let paid = step(r#"{"callback": {"path": "/hooks", "body": {"status": "paid"}}}"#);
+        assert!(args
+            .run_test_suite_instance(&api, &db, &paid, &multi, &pb)
+            .await
+            .unwrap());
+        let refunded = step(r#"{"callback": {"body": {"status": "refunded"}}}"#);
+        assert!(!args
+            .run_test_suite_instance(&api, &db, &refunded, &multi, &pb)
+            .await
+            .unwrap());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
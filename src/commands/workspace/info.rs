@@ -0,0 +1,23 @@
+use colored::Colorize;
+
+use crate::db::db_handler::DBHandler;
+use crate::db::workspace::Workspace;
+
+pub async fn show_info(workspace: &Workspace, db_handler: &DBHandler) -> anyhow::Result<()> {
+    println!("Workspace: {}", workspace.resolution.to_string().green());
+    match workspace.files.as_ref() {
+        Some(dir) => println!("  files: {}", dir.display().to_string().yellow()),
+        None => println!(
+            "  database: {}",
+            workspace.database.display().to_string().yellow()
+        ),
+    }
+    if workspace.files.is_some() {
+        println!(
+            "  history and context: {}",
+            workspace.database.display().to_string().yellow()
+        );
+    }
+    println!("  projects: {}", db_handler.get_projects().await?.len());
+    Ok(())
+}
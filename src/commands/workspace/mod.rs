@@ -0,0 +1,15 @@
+pub(crate) mod info;
+
+use clap::{Args, Subcommand};
+
+#[derive(Args)]
+pub struct Workspace {
+    #[command(subcommand)]
+    pub workspace_commands: WorkspaceCommands,
+}
+
+#[derive(Subcommand)]
+pub enum WorkspaceCommands {
+    /// Show the active workspace and how it was found
+    Info,
+}
@@ -1,77 +1,23 @@
+use std::sync::Arc;
+
 use crate::db::dto::{Action, Context, History, Project, TestSuite, TestSuiteInstance};
-use crate::HOME_DIR;
+use crate::db::file_storage::FileStorage;
+use crate::db::migrations::{latest_version, migrate};
+use crate::db::sqlite_storage::SqliteStorage;
+use crate::db::storage::Storage;
+use crate::db::workspace::Workspace;
 use colored::Colorize;
-use sqlx::{sqlite::SqlitePool, Executor};
-
-static INIT_TABLES: &str = r#"
-BEGIN TRANSACTION;
-
-CREATE TABLE projects (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT NOT NULL,
-    main_url TEXT NOT NULL,
-    conf TEXT DEFAULT '{}',
-    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    updated_at DATETIME NULLABLE,
-    CONSTRAINT unique_project_name UNIQUE (name)
-);
-
-CREATE TABLE actions (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT NULLABLE,
-    run_action_args TEXT NULLABLE,
-    body_example TEXT NULLABLE,
-    response_example TEXT NULLABLE,
-    project_name TEXT NULLABLE,
-    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    updated_at DATETIME NULLABLE,
-    FOREIGN KEY(project_name) REFERENCES projects(name)
-    CONSTRAINT unique_action UNIQUE (name, project_name)
-);
-
-CREATE TABLE history (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    action_name TEXT,
-    url TEXT NOT NULL,
-    body TEXT,
-    headers TEXT,
-    response TEXT,
-    status_code INTEGER NOT NULL,
-    duration REAL NOT NULL,
-    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-);
-
-CREATE TABLE test_suite (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT NOT NULL,
-    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    CONSTRAINT unique_test_suite_name UNIQUE (name)
-);
-
-CREATE TABLE test_suite_steps(
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    test_suite_name TEXT NOT NULL,
-    run_action_args TEXT NOT NULL,
-    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    updated_at DATETIME NULLABLE,
-    FOREIGN KEY(test_suite_name) REFERENCES test_suite(name)
-);
-
-CREATE TABLE context (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    value TEXT
-);
-COMMIT;
-"#;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::{Connection, SqliteConnection};
 
 /// Error messages
-static PROJECT_NOT_FOUND: &str =
-    "Project not found. Did you forget to create it running `apikrab project new <project_name>`?";
 static CONNECTION_ERROR: &str = "Connection to database failed";
 
 #[derive(Clone, Default)]
 pub struct DBHandler {
     pub conn: Option<SqlitePool>,
+    /// projects, actions and test suites, the history and the context staying in sqlite
+    pub storage: Option<Arc<dyn Storage>>,
 }
 
 impl DBHandler {
@@ -81,143 +27,99 @@ impl DBHandler {
             .unwrap_or_else(|| panic!("{}", CONNECTION_ERROR))
     }
 
-    /// Create database if needed at the startup of the application
-    pub async fn init_db(&mut self) -> anyhow::Result<()> {
-        let path_as_str = format!("{}/.config/qapi/qapi.sqlite", HOME_DIR.display());
-
-        let path = std::path::Path::new(path_as_str.as_str());
+    fn get_storage(&self) -> &dyn Storage {
+        self.storage
+            .as_deref()
+            .unwrap_or_else(|| panic!("{}", CONNECTION_ERROR))
+    }
 
-        let sqlite_uri = format!("file:{}", path_as_str);
+    /// files of the workspace directory if any, the database otherwise
+    fn init_storage(&mut self, workspace: &Workspace) {
+        self.storage = Some(match workspace.files.as_ref() {
+            Some(dir) => Arc::new(FileStorage::new(dir.clone())),
+            None => Arc::new(SqliteStorage {
+                conn: self.get_conn().clone(),
+            }),
+        });
+    }
 
-        if path.exists() {
-            self.conn = SqlitePool::connect(sqlite_uri.as_str()).await.ok();
-            return Ok(());
-        }
+    /// Create database if needed at the startup of the application,
+    /// existing ones being migrated to the latest schema
+    pub async fn init_db(&mut self, workspace: &Workspace) -> anyhow::Result<()> {
+        let path = &workspace.database;
         let parent = path
             .parent()
             .ok_or(anyhow::anyhow!("Missing parent".red()))?;
         std::fs::create_dir_all(parent)?;
-        std::fs::File::create(path_as_str.clone())?;
-
-        self.conn = SqlitePool::connect(sqlite_uri.as_str()).await.ok();
-
-        let conn = self.get_conn();
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+
+        // a single connection, pooled ones would keep the previous schema
+        let mut conn = SqliteConnection::connect_with(&options).await?;
+        if let Some(backup) = migrate(&mut conn, path).await? {
+            eprintln!(
+                "{}",
+                format!(
+                    "Database upgraded to version {}, previous one saved to {}",
+                    latest_version(),
+                    backup.display()
+                )
+                .yellow()
+            );
+        }
+        conn.close().await?;
 
-        conn.execute(INIT_TABLES).await?;
+        self.conn = SqlitePool::connect_with(options).await.ok();
+        self.init_storage(workspace);
         Ok(())
     }
 
     /// Return the project id if it exists for a given project name
     pub async fn get_project(&self, project_name: &str) -> anyhow::Result<Project> {
-        let project_opt = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE name = ?1")
-            .bind(project_name)
-            .fetch_optional(self.get_conn())
-            .await?;
-        project_opt.ok_or(anyhow::anyhow!(PROJECT_NOT_FOUND.red()))
+        self.get_storage().get_project(project_name).await
     }
 
     /// create a new project with the given name
     pub async fn upsert_project(&self, project: &Project) -> anyhow::Result<i64> {
-        let r = sqlx::query(
-            r#"
-            INSERT INTO projects (id, name, main_url, conf, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6) ON CONFLICT (name)
-            DO UPDATE SET main_url = ?3, conf = ?4, updated_at = CURRENT_TIMESTAMP;
-            "#,
-        )
-        .bind(project.id)
-        .bind(&project.name)
-        .bind(&project.main_url)
-        .bind(&project.conf)
-        .bind(project.created_at)
-        .bind(project.updated_at)
-        .execute(self.get_conn())
-        .await?
-        .last_insert_rowid();
-        Ok(r)
+        self.get_storage().upsert_project(project).await
     }
 
     pub async fn get_projects(&self) -> anyhow::Result<Vec<Project>> {
-        let r = sqlx::query_as::<_, Project>("SELECT * FROM projects")
-            .fetch_all(self.get_conn())
-            .await?;
-        Ok(r)
+        self.get_storage().get_projects().await
+    }
+
+    pub async fn rm_project(&self, project_name: &str) -> anyhow::Result<u64> {
+        self.get_storage().rm_project(project_name).await
     }
 
     pub async fn upsert_action(&self, action: &Action) -> anyhow::Result<()> {
-        let _ = sqlx::query(
-            r#"
-            INSERT INTO actions (
-                id,
-                name,
-                run_action_args,
-                body_example,
-                response_example,
-                project_name,
-                created_at,
-                updated_at
-            )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            ON CONFLICT (name, project_name)
-            DO UPDATE SET
-                run_action_args = ?3,
-                body_example = ?4,
-                response_example = ?5,
-                updated_at = CURRENT_TIMESTAMP;
-            "#,
-        )
-        .bind(action.id)
-        .bind(&action.name)
-        .bind(&action.run_action_args)
-        .bind(&action.body_example)
-        .bind(&action.response_example)
-        .bind(&action.project_name)
-        .execute(self.get_conn())
-        .await?
-        .last_insert_rowid();
-        Ok(())
+        self.get_storage().upsert_action(action).await
     }
 
     pub async fn get_actions(&self, project_name: Option<&str>) -> anyhow::Result<Vec<Action>> {
-        let base = r#"SELECT * FROM actions a "#;
-        let request = match project_name {
-            Some(_) => {
-                format!("{} WHERE a.project_name = ?1", base)
-            }
-            None => format!("{} WHERE a.project_name is NULL", base),
-        };
-        let actions = sqlx::query_as::<_, Action>(&request)
-            .bind(project_name)
-            .fetch_all(self.get_conn())
-            .await?;
-
-        Ok(actions)
+        self.get_storage().get_actions(project_name).await
     }
 
     pub async fn get_action(&self, action_name: &str) -> anyhow::Result<Action> {
-        let action = sqlx::query_as::<_, Action>(
-            r#"
-            SELECT *
-            FROM actions a
-            WHERE a.name = ?1
-            "#,
-        )
-        .bind(action_name)
-        .fetch_one(self.get_conn())
-        .await?;
-
-        Ok(action)
+        self.get_storage().get_action(action_name).await
     }
 
     pub async fn rm_action(&self, action_name: &str) -> anyhow::Result<u64> {
-        let r = sqlx::query("DELETE FROM actions WHERE name = ?1;")
-            .bind(action_name)
-            .execute(self.get_conn())
-            .await?
-            .rows_affected();
-        Ok(r)
+        self.get_storage().rm_action(action_name).await
+    }
+
+    pub async fn rm_project_action(
+        &self,
+        project_name: Option<&str>,
+        action_name: &str,
+    ) -> anyhow::Result<u64> {
+        self.get_storage()
+            .rm_project_action(project_name, action_name)
+            .await
     }
 
+    /// values extracted at runtime, e.g. tokens, never saved with the workspace files
     pub async fn get_conf(&self) -> anyhow::Result<Context> {
         let conf = sqlx::query_as::<_, Context>(
             r#"
@@ -247,11 +149,45 @@ impl DBHandler {
         Ok(r)
     }
 
+    pub async fn upsert_test_suite(&self, test_suite: &TestSuite) -> anyhow::Result<()> {
+        self.get_storage().upsert_test_suite(test_suite).await
+    }
+
+    pub async fn get_test_suite(&self, test_suite_name: &str) -> anyhow::Result<TestSuite> {
+        self.get_storage().get_test_suite(test_suite_name).await
+    }
+
+    pub async fn get_test_suites(&self) -> anyhow::Result<Vec<TestSuite>> {
+        self.get_storage().get_test_suites().await
+    }
+
+    pub async fn rm_test_suite(&self, test_suite_name: &str) -> anyhow::Result<u64> {
+        self.get_storage().rm_test_suite(test_suite_name).await
+    }
+
+    pub async fn upsert_test_suite_instance(
+        &self,
+        test_suite_instance: &TestSuiteInstance,
+    ) -> anyhow::Result<()> {
+        self.get_storage()
+            .upsert_test_suite_instance(test_suite_instance)
+            .await
+    }
+
+    pub async fn get_test_suite_instance(
+        &self,
+        test_suite_name: &str,
+    ) -> anyhow::Result<Vec<TestSuiteInstance>> {
+        self.get_storage()
+            .get_test_suite_instance(test_suite_name)
+            .await
+    }
+
     pub async fn insert_history(&self, history: &History) -> anyhow::Result<i64> {
         let r = sqlx::query(
             r#"
-            INSERT INTO history (id, action_name, url, body, headers, response, status_code, duration)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);
+            INSERT INTO history (id, action_name, url, body, headers, response, status_code, duration, verb, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, COALESCE(?10, CURRENT_TIMESTAMP));
             "#,
         )
         .bind(history.id)
@@ -262,6 +198,8 @@ impl DBHandler {
         .bind(&history.response)
         .bind(history.status_code)
         .bind(history.duration)
+        .bind(&history.verb)
+        .bind(history.created_at)
         .execute(self.get_conn())
         .await?
         .last_insert_rowid();
@@ -269,6 +207,22 @@ impl DBHandler {
         Ok(r)
     }
 
+    /// whole history, oldest first
+    pub async fn get_all_history(&self) -> anyhow::Result<Vec<History>> {
+        let history = sqlx::query_as::<_, History>("SELECT * FROM history ORDER BY created_at, id")
+            .fetch_all(self.get_conn())
+            .await?;
+        Ok(history)
+    }
+
+    pub async fn clear_history(&self) -> anyhow::Result<u64> {
+        let r = sqlx::query("DELETE FROM history;")
+            .execute(self.get_conn())
+            .await?
+            .rows_affected();
+        Ok(r)
+    }
+
     pub async fn get_history(&self, limit: Option<u16>) -> anyhow::Result<Vec<History>> {
         let _limit = limit.unwrap_or(20);
         let history = sqlx::query_as::<_, History>(
@@ -276,7 +230,7 @@ impl DBHandler {
                 r#"
             SELECT *
             FROM history
-            ORDER BY created_at DESC
+            ORDER BY created_at DESC, id DESC
             limit {};
             "#,
                 _limit
@@ -288,80 +242,4 @@ impl DBHandler {
 
         Ok(history)
     }
-
-    pub async fn upsert_test_suite(&self, test_suite: &TestSuite) -> anyhow::Result<()> {
-        let _ = sqlx::query(
-            r#"
-            INSERT INTO test_suite (id, name, created_at)
-            VALUES (?1, ?2, ?3)
-            ON CONFLICT (name)
-            DO NOTHING;
-            "#,
-        )
-        .bind(test_suite.id)
-        .bind(test_suite.name.clone())
-        .bind(test_suite.created_at)
-        .execute(self.get_conn())
-        .await?
-        .last_insert_rowid();
-        Ok(())
-    }
-
-    pub async fn get_test_suite(&self, test_suite_name: &str) -> anyhow::Result<TestSuite> {
-        let r = sqlx::query_as::<_, TestSuite>(
-            r#"
-            SELECT *
-            FROM test_suite
-            WHERE name = ?1
-            "#,
-        )
-        .bind(test_suite_name)
-        .fetch_one(self.get_conn())
-        .await;
-        match r {
-            Ok(test_suite) => Ok(test_suite),
-            Err(..) => anyhow::bail!("Flow not found"),
-        }
-    }
-
-    pub async fn upsert_test_suite_instance(
-        &self,
-        test_suite_instance: &TestSuiteInstance,
-    ) -> anyhow::Result<()> {
-        let _ = sqlx::query(
-            r#"
-            INSERT INTO test_suite_steps (id, test_suite_name, run_action_args, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5)
-            ON CONFLICT (id)
-            DO UPDATE SET run_action_args = ?3, updated_at = CURRENT_TIMESTAMP;
-            "#,
-        )
-        .bind(test_suite_instance.id)
-        .bind(&test_suite_instance.test_suite_name)
-        .bind(&test_suite_instance.run_action_args)
-        .bind(test_suite_instance.created_at)
-        .bind(test_suite_instance.updated_at)
-        .execute(self.get_conn())
-        .await?
-        .last_insert_rowid();
-        Ok(())
-    }
-
-    pub async fn get_test_suite_instance(
-        &self,
-        test_suite_name: &str,
-    ) -> anyhow::Result<Vec<TestSuiteInstance>> {
-        let r = sqlx::query_as::<_, TestSuiteInstance>(
-            r#"
-            SELECT *
-            FROM test_suite_steps
-            WHERE test_suite_name = ?1
-            "#,
-        )
-        .bind(test_suite_name)
-        .fetch_all(self.get_conn())
-        .await?;
-
-        Ok(r)
-    }
 }
@@ -3,6 +3,7 @@ use crate::commands::project::create::CreateProjectArgs;
 use crate::commands::run::action::RunActionArgs;
 use crate::utils::parse_cli_conf_to_map;
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 use serde_json::to_string;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
@@ -15,16 +16,51 @@ pub struct Project {
     pub(crate) conf: Option<String>,
     pub(crate) created_at: Option<chrono::NaiveDateTime>,
     pub(crate) updated_at: Option<chrono::NaiveDateTime>,
+    /// json of the ProjectSource the project was imported from
+    pub(crate) source: Option<String>,
+}
+
+/// Importers of a project source
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SourceKind {
+    Openapi,
+    Postman,
+    Graphql,
+    Insomnia,
+    Bruno,
+}
+
+/// Spec or collection a project was imported from, to be synced later
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectSource {
+    pub kind: SourceKind,
+    /// path or url
+    pub path: String,
+    /// insomnia or bruno environment, postman environment file
+    pub env: Option<String>,
+    /// names of the imported actions, the other ones being left by syncs
+    #[serde(default)]
+    pub actions: Vec<String>,
 }
 
 impl Project {
+    pub fn get_source(&self) -> anyhow::Result<Option<ProjectSource>> {
+        match self.source.as_deref() {
+            None => Ok(None),
+            Some(source) => Ok(Some(serde_json::from_str(source)?)),
+        }
+    }
+
     pub fn get_project_conf(&self) -> anyhow::Result<HashMap<String, String>> {
         match self.conf {
             None => Ok(HashMap::new()),
             Some(ref conf) => {
-                let r = serde_json::from_str(conf);
+                // projects created without conf store null
+                let r = serde_json::from_str::<Option<HashMap<String, String>>>(conf);
                 match r {
-                    Ok(r) => Ok(r),
+                    Ok(r) => Ok(r.unwrap_or_default()),
                     Err(e) => {
                         anyhow::bail!(e)
                     }
@@ -61,10 +97,11 @@ impl From<&CreateProjectArgs> for Project {
         Project {
             id: None,
             name: args.name.clone(),
-            main_url: args.url.clone(),
+            main_url: args.url.clone().unwrap_or_default(),
             conf: to_string(&project_conf).ok(),
             created_at: None,
             updated_at: None,
+            source: None,
         }
     }
 }
@@ -175,7 +212,7 @@ impl Context {
     }
 }
 
-#[derive(sqlx::FromRow, Debug)]
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone)]
 pub struct History {
     pub(crate) id: Option<i64>,
     pub(crate) action_name: String,
@@ -186,6 +223,7 @@ pub struct History {
     pub(crate) status_code: u16,
     pub(crate) duration: f32,
     pub(crate) created_at: Option<chrono::NaiveDateTime>,
+    pub(crate) verb: Option<String>,
 }
 
 impl Display for History {
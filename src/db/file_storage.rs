@@ -0,0 +1,511 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::run::action::RunActionArgs;
+use crate::db::dto::{Action, Project, ProjectSource, TestSuite, TestSuiteInstance};
+use crate::db::storage::{Storage, PROJECT_NOT_FOUND, TEST_SUITE_NOT_FOUND};
+
+/// directory holding the files of a workspace, usually at the root of a repository
+pub const WORKSPACE_DIR: &str = ".apikrab";
+const EXTENSIONS: [&str; 3] = ["yaml", "yml", "json"];
+
+/// projects/<project>/project.yaml
+#[derive(Serialize, Deserialize)]
+struct ProjectFile {
+    name: String,
+    main_url: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    conf: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<ProjectSource>,
+}
+
+/// projects/<project>/actions/<action>.yaml, or actions/<action>.yaml without project
+#[derive(Serialize, Deserialize)]
+struct ActionFile {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_action_args: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_example: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_example: Option<String>,
+}
+
+/// test_suites/<test suite>.yaml
+#[derive(Serialize, Deserialize)]
+struct TestSuiteFile {
+    name: String,
+    #[serde(default)]
+    steps: Vec<Value>,
+}
+
+/// run action args without their null and false fields, the defaults being restored on read
+pub(crate) fn compact_args(run_action_args: &str) -> anyhow::Result<Value> {
+    let mut value: Value = serde_json::from_str(run_action_args)?;
+    if let Value::Object(fields) = &mut value {
+        fields.retain(|_, v| !matches!(v, Value::Null | Value::Bool(false)));
+    }
+    Ok(value)
+}
+
+pub(crate) fn expand_args(value: Value) -> anyhow::Result<String> {
+    let mut args = serde_json::to_value(RunActionArgs::default())?;
+    if let (Value::Object(args), Value::Object(fields)) = (&mut args, value) {
+        args.extend(fields);
+    }
+    Ok(serde_json::to_string(&args)?)
+}
+
+/// file name of an entity, names being mostly slugs already. Other bytes are percent
+/// encoded, like a leading dot, so two names never share a file
+fn file_stem(name: &str) -> String {
+    name.bytes()
+        .enumerate()
+        .map(|(i, b)| match b {
+            b'.' if i == 0 => "%2E".to_string(),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn read<T: for<'de> Deserialize<'de>>(path: &Path) -> anyhow::Result<T> {
+    // json files are valid yaml
+    serde_yaml::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| anyhow::anyhow!("Invalid file {}: {}", path.display(), e))
+}
+
+/// written next to the file then renamed, a failing write never leaving half a file
+fn write<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("yaml.tmp");
+    std::fs::write(&tmp, serde_yaml::to_string(value)?)?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// yaml or json files of a directory, sorted by name
+fn entity_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| EXTENSIONS.contains(&e))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+/// Projects, actions and test suites saved as one yaml file per entity,
+/// to be reviewed and versioned with the code of the api
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(root: PathBuf) -> Self {
+        FileStorage { root }
+    }
+
+    fn project_dir(&self, project_name: &str) -> PathBuf {
+        self.root.join("projects").join(file_stem(project_name))
+    }
+
+    fn actions_dir(&self, project_name: Option<&str>) -> PathBuf {
+        match project_name {
+            Some(project_name) => self.project_dir(project_name).join("actions"),
+            None => self.root.join("actions"),
+        }
+    }
+
+    fn test_suite_path(&self, test_suite_name: &str) -> PathBuf {
+        self.root
+            .join("test_suites")
+            .join(format!("{}.yaml", file_stem(test_suite_name)))
+    }
+
+    /// existing file of an entity, whatever its extension
+    fn find(dir: &Path, name: &str) -> Option<PathBuf> {
+        let stem = file_stem(name);
+        EXTENSIONS
+            .iter()
+            .map(|e| dir.join(format!("{}.{}", stem, e)))
+            .find(|p| p.is_file())
+    }
+
+    /// directories of the actions of every project, and of the ones without project
+    fn all_actions_dirs(&self) -> anyhow::Result<Vec<(Option<String>, PathBuf)>> {
+        let mut dirs = vec![(None, self.actions_dir(None))];
+        for project in self.read_projects()? {
+            let dir = self.actions_dir(Some(&project.name));
+            dirs.push((Some(project.name), dir));
+        }
+        Ok(dirs)
+    }
+
+    fn read_projects(&self) -> anyhow::Result<Vec<ProjectFile>> {
+        let dir = self.root.join("projects");
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut projects = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            if let Some(path) = Self::find(&entry?.path(), "project") {
+                projects.push(read::<ProjectFile>(&path)?);
+            }
+        }
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+
+    fn to_action(file: ActionFile, project_name: Option<String>) -> anyhow::Result<Action> {
+        Ok(Action {
+            id: None,
+            name: Some(file.name),
+            run_action_args: file.run_action_args.map(expand_args).transpose()?,
+            body_example: file.body_example,
+            response_example: file.response_example,
+            project_name,
+            created_at: None,
+            updated_at: None,
+        })
+    }
+
+    fn read_test_suite(&self, test_suite_name: &str) -> anyhow::Result<TestSuiteFile> {
+        let dir = self.root.join("test_suites");
+        match Self::find(&dir, test_suite_name) {
+            Some(path) => read(&path),
+            None => anyhow::bail!(TEST_SUITE_NOT_FOUND),
+        }
+    }
+}
+
+impl From<ProjectFile> for Project {
+    fn from(file: ProjectFile) -> Self {
+        Project {
+            id: None,
+            name: file.name,
+            main_url: file.main_url,
+            conf: serde_json::to_string(&file.conf).ok(),
+            created_at: None,
+            updated_at: None,
+            source: file
+                .source
+                .and_then(|source| serde_json::to_string(&source).ok()),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for FileStorage {
+    async fn get_project(&self, project_name: &str) -> anyhow::Result<Project> {
+        match Self::find(&self.project_dir(project_name), "project") {
+            Some(path) => Ok(read::<ProjectFile>(&path)?.into()),
+            None => anyhow::bail!(PROJECT_NOT_FOUND.red()),
+        }
+    }
+
+    async fn upsert_project(&self, project: &Project) -> anyhow::Result<i64> {
+        let dir = self.project_dir(&project.name);
+        let existing = Self::find(&dir, "project");
+        // like in the database, the source is kept when none is given
+        let source = match project.get_source()? {
+            Some(source) => Some(source),
+            None => match existing.as_ref() {
+                Some(path) => read::<ProjectFile>(path)?.source,
+                None => None,
+            },
+        };
+        let file = ProjectFile {
+            name: project.name.clone(),
+            main_url: project.main_url.clone(),
+            conf: project.get_project_conf()?.into_iter().collect(),
+            source,
+        };
+        write(&existing.unwrap_or(dir.join("project.yaml")), &file)?;
+        Ok(0)
+    }
+
+    async fn get_projects(&self) -> anyhow::Result<Vec<Project>> {
+        Ok(self
+            .read_projects()?
+            .into_iter()
+            .map(Project::from)
+            .collect())
+    }
+
+    async fn rm_project(&self, project_name: &str) -> anyhow::Result<u64> {
+        let dir = self.project_dir(project_name);
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+        let actions = entity_files(&self.actions_dir(Some(project_name)))?.len();
+        std::fs::remove_dir_all(dir)?;
+        Ok(actions as u64 + 1)
+    }
+
+    async fn upsert_action(&self, action: &Action) -> anyhow::Result<()> {
+        let name = action
+            .name
+            .clone()
+            .ok_or(anyhow::anyhow!("Action without name"))?;
+        let dir = self.actions_dir(action.project_name.as_deref());
+        let file = ActionFile {
+            run_action_args: action
+                .run_action_args
+                .as_deref()
+                .map(compact_args)
+                .transpose()?,
+            body_example: action.body_example.clone(),
+            response_example: action.response_example.clone(),
+            name,
+        };
+        let path = Self::find(&dir, &file.name)
+            .unwrap_or(dir.join(format!("{}.yaml", file_stem(&file.name))));
+        write(&path, &file)
+    }
+
+    async fn get_actions(&self, project_name: Option<&str>) -> anyhow::Result<Vec<Action>> {
+        entity_files(&self.actions_dir(project_name))?
+            .iter()
+            .map(|path| Self::to_action(read(path)?, project_name.map(str::to_string)))
+            .collect()
+    }
+
+    async fn get_action(&self, action_name: &str) -> anyhow::Result<Action> {
+        for (project_name, dir) in self.all_actions_dirs()? {
+            if let Some(path) = Self::find(&dir, action_name) {
+                return Self::to_action(read(&path)?, project_name);
+            }
+        }
+        anyhow::bail!("Action {} not found", action_name)
+    }
+
+    async fn rm_action(&self, action_name: &str) -> anyhow::Result<u64> {
+        let mut removed = 0;
+        for (_, dir) in self.all_actions_dirs()? {
+            if let Some(path) = Self::find(&dir, action_name) {
+                std::fs::remove_file(path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    async fn rm_project_action(
+        &self,
+        project_name: Option<&str>,
+        action_name: &str,
+    ) -> anyhow::Result<u64> {
+        match Self::find(&self.actions_dir(project_name), action_name) {
+            Some(path) => {
+                std::fs::remove_file(path)?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    async fn upsert_test_suite(&self, test_suite: &TestSuite) -> anyhow::Result<()> {
+        if self.read_test_suite(&test_suite.name).is_err() {
+            let file = TestSuiteFile {
+                name: test_suite.name.clone(),
+                steps: vec![],
+            };
+            write(&self.test_suite_path(&test_suite.name), &file)?;
+        }
+        Ok(())
+    }
+
+    async fn get_test_suite(&self, test_suite_name: &str) -> anyhow::Result<TestSuite> {
+        let file = self.read_test_suite(test_suite_name)?;
+        Ok(TestSuite {
+            id: None,
+            name: file.name,
+            created_at: None,
+        })
+    }
+
+    async fn get_test_suites(&self) -> anyhow::Result<Vec<TestSuite>> {
+        entity_files(&self.root.join("test_suites"))?
+            .iter()
+            .map(|path| {
+                Ok(TestSuite {
+                    id: None,
+                    name: read::<TestSuiteFile>(path)?.name,
+                    created_at: None,
+                })
+            })
+            .collect()
+    }
+
+    async fn rm_test_suite(&self, test_suite_name: &str) -> anyhow::Result<u64> {
+        match Self::find(&self.root.join("test_suites"), test_suite_name) {
+            Some(path) => {
+                std::fs::remove_file(path)?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// steps are identified by their position in the test suite, starting at 1
+    async fn upsert_test_suite_instance(
+        &self,
+        test_suite_instance: &TestSuiteInstance,
+    ) -> anyhow::Result<()> {
+        let mut file = self.read_test_suite(&test_suite_instance.test_suite_name)?;
+        let step = compact_args(&test_suite_instance.run_action_args)?;
+        let index = test_suite_instance
+            .id
+            .and_then(|id| usize::try_from(id).ok())
+            .filter(|id| (1..=file.steps.len()).contains(id));
+        match index {
+            Some(id) => file.steps[id - 1] = step,
+            None => file.steps.push(step),
+        }
+        let path = Self::find(&self.root.join("test_suites"), &file.name)
+            .unwrap_or(self.test_suite_path(&file.name));
+        write(&path, &file)
+    }
+
+    async fn get_test_suite_instance(
+        &self,
+        test_suite_name: &str,
+    ) -> anyhow::Result<Vec<TestSuiteInstance>> {
+        let file = self.read_test_suite(test_suite_name)?;
+        file.steps
+            .into_iter()
+            .enumerate()
+            .map(|(i, step)| {
+                Ok(TestSuiteInstance {
+                    id: Some(i as i64 + 1),
+                    test_suite_name: file.name.clone(),
+                    run_action_args: expand_args(step)?,
+                    created_at: None,
+                    updated_at: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem("get-user_v1.2"), "get-user_v1.2");
+        assert_eq!(file_stem("a b"), "a%20b");
+        assert_eq!(file_stem("a/b"), "a%2Fb");
+        assert_eq!(file_stem("100%"), "100%25");
+        assert_eq!(file_stem("../etc"), "%2E.%2Fetc");
+        assert_eq!(file_stem("café"), "caf%C3%A9");
+    }
+
+    #[tokio::test]
+    async fn test_file_storage() {
+        let root = std::env::temp_dir().join("apikrab_file_storage_test");
+        let _ = std::fs::remove_dir_all(&root);
+        let storage = FileStorage::new(root.clone());
+
+        let project = Project {
+            id: None,
+            name: "users".to_string(),
+            main_url: "https://api.example.com".to_string(),
+            conf: Some(r#"{"token": "abc"}"#.to_string()),
+            created_at: None,
+            updated_at: None,
+            source: None,
+        };
+        storage.upsert_project(&project).await.unwrap();
+        let args = RunActionArgs {
+            name: Some("get-user".to_string()),
+            url: Some("users/{id}".to_string()),
+            verb: Some("GET".to_string()),
+            form_data: true,
+            ..Default::default()
+        };
+        let action = Action {
+            name: Some("get-user".to_string()),
+            run_action_args: Some(serde_json::to_string(&args).unwrap()),
+            project_name: Some("users".to_string()),
+            ..Default::default()
+        };
+        storage.upsert_action(&action).await.unwrap();
+
+        // only the set fields are written
+        let content =
+            std::fs::read_to_string(root.join("projects/users/actions/get-user.yaml")).unwrap();
+        assert!(content.contains("url: \"users/{id}\""));
+        assert!(content.contains("form_data: true"));
+        assert!(!content.contains("null") && !content.contains("false"));
+
+        let projects = storage.get_projects().await.unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].get_project_conf().unwrap()["token"], "abc");
+        let actions = storage.get_actions(Some("users")).await.unwrap();
+        assert_eq!(actions.len(), 1);
+        let read_args = actions[0].get_run_action_args().unwrap();
+        assert_eq!(read_args.url, args.url);
+        assert!(read_args.form_data && !read_args.url_encoded);
+        assert_eq!(
+            storage
+                .get_action("get-user")
+                .await
+                .unwrap()
+                .project_name
+                .as_deref(),
+            Some("users")
+        );
+
+        let test_suite = TestSuite {
+            id: None,
+            name: "smoke".to_string(),
+            created_at: None,
+        };
+        storage.upsert_test_suite(&test_suite).await.unwrap();
+        for _ in 0..2 {
+            storage
+                .upsert_test_suite_instance(&TestSuiteInstance {
+                    id: None,
+                    test_suite_name: "smoke".to_string(),
+                    run_action_args: serde_json::to_string(&args).unwrap(),
+                    created_at: None,
+                    updated_at: None,
+                })
+                .await
+                .unwrap();
+        }
+        let steps = storage.get_test_suite_instance("smoke").await.unwrap();
+        assert_eq!(
+            steps.iter().map(|s| s.id).collect::<Vec<_>>(),
+            [Some(1), Some(2)]
+        );
+
+        assert_eq!(
+            storage
+                .rm_project_action(Some("users"), "get-user")
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(storage.get_actions(Some("users")).await.unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
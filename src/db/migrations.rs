@@ -0,0 +1,309 @@
+use std::path::{Path, PathBuf};
+
+use sqlx::{Connection, Executor, SqliteConnection};
+
+/// Change of a migration
+pub enum Step {
+    Sql(&'static str),
+    /// skipped if the column exists, some databases got it before migrations existed
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        kind: &'static str,
+    },
+}
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub steps: &'static [Step],
+}
+
+/// Tables of the databases created before migrations existed
+static BASELINE: &str = r#"
+CREATE TABLE projects (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    main_url TEXT NOT NULL,
+    conf TEXT DEFAULT '{}',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NULLABLE,
+    CONSTRAINT unique_project_name UNIQUE (name)
+);
+
+CREATE TABLE actions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NULLABLE,
+    run_action_args TEXT NULLABLE,
+    body_example TEXT NULLABLE,
+    response_example TEXT NULLABLE,
+    project_name TEXT NULLABLE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NULLABLE,
+    FOREIGN KEY(project_name) REFERENCES projects(name)
+    CONSTRAINT unique_action UNIQUE (name, project_name)
+);
+
+CREATE TABLE history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    action_name TEXT,
+    url TEXT NOT NULL,
+    body TEXT,
+    headers TEXT,
+    response TEXT,
+    status_code INTEGER NOT NULL,
+    duration REAL NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE test_suite (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unique_test_suite_name UNIQUE (name)
+);
+
+CREATE TABLE test_suite_steps(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    test_suite_name TEXT NOT NULL,
+    run_action_args TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NULLABLE,
+    FOREIGN KEY(test_suite_name) REFERENCES test_suite(name)
+);
+
+CREATE TABLE context (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    value TEXT
+);
+"#;
+
+/// Append only, a released migration must never change
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial tables",
+        steps: &[Step::Sql(BASELINE)],
+    },
+    Migration {
+        version: 2,
+        description: "verb of the history",
+        steps: &[Step::AddColumn {
+            table: "history",
+            column: "verb",
+            kind: "TEXT",
+        }],
+    },
+    Migration {
+        version: 3,
+        description: "source of the imported projects",
+        steps: &[Step::AddColumn {
+            table: "projects",
+            column: "source",
+            kind: "TEXT NULLABLE",
+        }],
+    },
+];
+
+static SCHEMA_VERSION_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER NOT NULL,
+    description TEXT,
+    applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+"#;
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or_default()
+}
+
+async fn table_exists(conn: &mut SqliteConnection, table: &str) -> anyhow::Result<bool> {
+    Ok(
+        sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")
+            .bind(table)
+            .fetch_optional(conn)
+            .await?
+            .is_some(),
+    )
+}
+
+async fn column_exists(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+) -> anyhow::Result<bool> {
+    Ok(sqlx::query(&format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1",
+        table
+    ))
+    .bind(column)
+    .fetch_optional(conn)
+    .await?
+    .is_some())
+}
+
+/// 0 for an empty database, 1 for one created before migrations existed
+pub async fn current_version(conn: &mut SqliteConnection) -> anyhow::Result<i64> {
+    if table_exists(conn, "schema_version").await? {
+        let (version,): (i64,) =
+            sqlx::query_as("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+                .fetch_one(conn)
+                .await?;
+        return Ok(version);
+    }
+    match table_exists(conn, "projects").await? {
+        true => Ok(1),
+        false => Ok(0),
+    }
+}
+
+/// copy of the database before migrating it, e.g. qapi.sqlite.v1-20240101120000.bak
+async fn backup(conn: &mut SqliteConnection, path: &Path, version: i64) -> anyhow::Result<PathBuf> {
+    let backup = PathBuf::from(format!(
+        "{}.v{}-{}.bak",
+        path.display(),
+        version,
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    ));
+    sqlx::query("VACUUM INTO ?1")
+        .bind(backup.display().to_string())
+        .execute(conn)
+        .await?;
+    Ok(backup)
+}
+
+/// Upgrade the database in place, each migration in its own transaction.
+/// Returns the backup taken when an existing database is migrated
+pub async fn migrate(conn: &mut SqliteConnection, path: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let current = current_version(conn).await?;
+    if current > latest_version() {
+        anyhow::bail!(
+            "Database {} is at version {} but this apikrab only knows version {}, please upgrade it",
+            path.display(),
+            current,
+            latest_version()
+        );
+    }
+    if current == latest_version() {
+        return Ok(None);
+    }
+    let backup = match current {
+        0 => None,
+        _ => Some(backup(conn, path, current).await?),
+    };
+    if !table_exists(conn, "schema_version").await? {
+        conn.execute(SCHEMA_VERSION_TABLE).await?;
+        if current > 0 {
+            sqlx::query("INSERT INTO schema_version (version, description) VALUES (?1, ?2)")
+                .bind(current)
+                .bind("created before migrations")
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let mut tx = conn.begin().await?;
+        for step in migration.steps {
+            match step {
+                Step::Sql(sql) => {
+                    tx.execute(*sql).await?;
+                }
+                Step::AddColumn {
+                    table,
+                    column,
+                    kind,
+                } => {
+                    if !column_exists(&mut tx, table, column).await? {
+                        tx.execute(
+                            format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, kind)
+                                .as_str(),
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+        sqlx::query("INSERT INTO schema_version (version, description) VALUES (?1, ?2)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqliteConnectOptions;
+
+    async fn connect(name: &str) -> (SqliteConnection, PathBuf) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("qapi.sqlite");
+        let options = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true);
+        (
+            SqliteConnection::connect_with(&options).await.unwrap(),
+            path,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_migrate_baseline_database() {
+        let (mut conn, path) = connect("apikrab_migrations_baseline_test").await;
+        conn.execute(BASELINE).await.unwrap();
+        conn.execute(
+            r#"
+            INSERT INTO projects (name, main_url) VALUES ('users', 'https://api.example.com');
+            INSERT INTO history (action_name, url, status_code, duration) VALUES ('get-user', 'https://api.example.com/users/1', 200, 0.1);
+            "#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(current_version(&mut conn).await.unwrap(), 1);
+
+        let backup = migrate(&mut conn, &path).await.unwrap().unwrap();
+        assert!(backup.is_file());
+        assert_eq!(current_version(&mut conn).await.unwrap(), latest_version());
+        assert!(column_exists(&mut conn, "history", "verb").await.unwrap());
+        assert!(column_exists(&mut conn, "projects", "source")
+            .await
+            .unwrap());
+        let (name, source): (String, Option<String>) =
+            sqlx::query_as("SELECT name, source FROM projects")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap();
+        assert_eq!((name.as_str(), source), ("users", None));
+        let (history,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM history")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(history, 1);
+
+        // the backup is the baseline database
+        let options = SqliteConnectOptions::new().filename(&backup);
+        let mut backup_conn = SqliteConnection::connect_with(&options).await.unwrap();
+        assert_eq!(current_version(&mut backup_conn).await.unwrap(), 1);
+        assert!(!column_exists(&mut backup_conn, "history", "verb")
+            .await
+            .unwrap());
+
+        assert!(migrate(&mut conn, &path).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_new_database() {
+        let (mut conn, path) = connect("apikrab_migrations_new_test").await;
+        assert_eq!(current_version(&mut conn).await.unwrap(), 0);
+        assert!(migrate(&mut conn, &path).await.unwrap().is_none());
+        assert_eq!(current_version(&mut conn).await.unwrap(), latest_version());
+        assert!(column_exists(&mut conn, "history", "verb").await.unwrap());
+        let files = std::fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(files, 1);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod db_handler;
 pub mod dto;
+pub mod file_storage;
+pub mod migrations;
+pub mod sqlite_storage;
+pub mod storage;
+pub mod workspace;
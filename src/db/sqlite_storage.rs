@@ -0,0 +1,256 @@
+use async_trait::async_trait;
+use colored::Colorize;
+use sqlx::sqlite::SqlitePool;
+
+use crate::db::dto::{Action, Project, TestSuite, TestSuiteInstance};
+use crate::db::storage::{Storage, PROJECT_NOT_FOUND, TEST_SUITE_NOT_FOUND};
+
+/// Projects, actions and test suites saved in the sqlite database
+pub struct SqliteStorage {
+    pub conn: SqlitePool,
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    /// Return the project id if it exists for a given project name
+    async fn get_project(&self, project_name: &str) -> anyhow::Result<Project> {
+        let project_opt = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE name = ?1")
+            .bind(project_name)
+            .fetch_optional(&self.conn)
+            .await?;
+        project_opt.ok_or(anyhow::anyhow!(PROJECT_NOT_FOUND.red()))
+    }
+
+    /// create a new project with the given name
+    async fn upsert_project(&self, project: &Project) -> anyhow::Result<i64> {
+        let r = sqlx::query(
+            r#"
+            INSERT INTO projects (id, name, main_url, conf, created_at, updated_at, source)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) ON CONFLICT (name)
+            DO UPDATE SET
+                main_url = ?3,
+                conf = ?4,
+                source = COALESCE(?7, source),
+                updated_at = CURRENT_TIMESTAMP;
+            "#,
+        )
+        .bind(project.id)
+        .bind(&project.name)
+        .bind(&project.main_url)
+        .bind(&project.conf)
+        .bind(project.created_at)
+        .bind(project.updated_at)
+        .bind(&project.source)
+        .execute(&self.conn)
+        .await?
+        .last_insert_rowid();
+        Ok(r)
+    }
+
+    async fn get_projects(&self) -> anyhow::Result<Vec<Project>> {
+        let r = sqlx::query_as::<_, Project>("SELECT * FROM projects")
+            .fetch_all(&self.conn)
+            .await?;
+        Ok(r)
+    }
+
+    async fn rm_project(&self, project_name: &str) -> anyhow::Result<u64> {
+        let r = sqlx::query(
+            r#"
+            DELETE FROM actions WHERE project_name = ?1;
+            DELETE FROM projects WHERE name = ?1;
+            "#,
+        )
+        .bind(project_name)
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+        Ok(r)
+    }
+
+    async fn upsert_action(&self, action: &Action) -> anyhow::Result<()> {
+        let _ = sqlx::query(
+            r#"
+            INSERT INTO actions (
+                id,
+                name,
+                run_action_args,
+                body_example,
+                response_example,
+                project_name,
+                created_at,
+                updated_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (name, project_name)
+            DO UPDATE SET
+                run_action_args = ?3,
+                body_example = ?4,
+                response_example = ?5,
+                updated_at = CURRENT_TIMESTAMP;
+            "#,
+        )
+        .bind(action.id)
+        .bind(&action.name)
+        .bind(&action.run_action_args)
+        .bind(&action.body_example)
+        .bind(&action.response_example)
+        .bind(&action.project_name)
+        .execute(&self.conn)
+        .await?
+        .last_insert_rowid();
+        Ok(())
+    }
+
+    async fn get_actions(&self, project_name: Option<&str>) -> anyhow::Result<Vec<Action>> {
+        let base = r#"SELECT * FROM actions a "#;
+        let request = match project_name {
+            Some(_) => {
+                format!("{} WHERE a.project_name = ?1", base)
+            }
+            None => format!("{} WHERE a.project_name is NULL", base),
+        };
+        let actions = sqlx::query_as::<_, Action>(&request)
+            .bind(project_name)
+            .fetch_all(&self.conn)
+            .await?;
+
+        Ok(actions)
+    }
+
+    async fn get_action(&self, action_name: &str) -> anyhow::Result<Action> {
+        let action = sqlx::query_as::<_, Action>(
+            r#"
+            SELECT *
+            FROM actions a
+            WHERE a.name = ?1
+            "#,
+        )
+        .bind(action_name)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(action)
+    }
+
+    async fn rm_action(&self, action_name: &str) -> anyhow::Result<u64> {
+        let r = sqlx::query("DELETE FROM actions WHERE name = ?1;")
+            .bind(action_name)
+            .execute(&self.conn)
+            .await?
+            .rows_affected();
+        Ok(r)
+    }
+
+    async fn rm_project_action(
+        &self,
+        project_name: Option<&str>,
+        action_name: &str,
+    ) -> anyhow::Result<u64> {
+        // IS matches a null project name too
+        let r = sqlx::query("DELETE FROM actions WHERE project_name IS ?1 AND name = ?2;")
+            .bind(project_name)
+            .bind(action_name)
+            .execute(&self.conn)
+            .await?
+            .rows_affected();
+        Ok(r)
+    }
+
+    async fn upsert_test_suite(&self, test_suite: &TestSuite) -> anyhow::Result<()> {
+        let _ = sqlx::query(
+            r#"
+            INSERT INTO test_suite (id, name, created_at)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (name)
+            DO NOTHING;
+            "#,
+        )
+        .bind(test_suite.id)
+        .bind(test_suite.name.clone())
+        .bind(test_suite.created_at)
+        .execute(&self.conn)
+        .await?
+        .last_insert_rowid();
+        Ok(())
+    }
+
+    async fn get_test_suite(&self, test_suite_name: &str) -> anyhow::Result<TestSuite> {
+        let r = sqlx::query_as::<_, TestSuite>(
+            r#"
+            SELECT *
+            FROM test_suite
+            WHERE name = ?1
+            "#,
+        )
+        .bind(test_suite_name)
+        .fetch_one(&self.conn)
+        .await;
+        match r {
+            Ok(test_suite) => Ok(test_suite),
+            Err(..) => anyhow::bail!(TEST_SUITE_NOT_FOUND),
+        }
+    }
+
+    async fn get_test_suites(&self) -> anyhow::Result<Vec<TestSuite>> {
+        let r = sqlx::query_as::<_, TestSuite>("SELECT * FROM test_suite ORDER BY name")
+            .fetch_all(&self.conn)
+            .await?;
+        Ok(r)
+    }
+
+    async fn rm_test_suite(&self, test_suite_name: &str) -> anyhow::Result<u64> {
+        let r = sqlx::query(
+            r#"
+            DELETE FROM test_suite_steps WHERE test_suite_name = ?1;
+            DELETE FROM test_suite WHERE name = ?1;
+            "#,
+        )
+        .bind(test_suite_name)
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+        Ok(r)
+    }
+
+    async fn upsert_test_suite_instance(
+        &self,
+        test_suite_instance: &TestSuiteInstance,
+    ) -> anyhow::Result<()> {
+        let _ = sqlx::query(
+            r#"
+            INSERT INTO test_suite_steps (id, test_suite_name, run_action_args, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (id)
+            DO UPDATE SET run_action_args = ?3, updated_at = CURRENT_TIMESTAMP;
+            "#,
+        )
+        .bind(test_suite_instance.id)
+        .bind(&test_suite_instance.test_suite_name)
+        .bind(&test_suite_instance.run_action_args)
+        .bind(test_suite_instance.created_at)
+        .bind(test_suite_instance.updated_at)
+        .execute(&self.conn)
+        .await?
+        .last_insert_rowid();
+        Ok(())
+    }
+
+    async fn get_test_suite_instance(
+        &self,
+        test_suite_name: &str,
+    ) -> anyhow::Result<Vec<TestSuiteInstance>> {
+        let r = sqlx::query_as::<_, TestSuiteInstance>(
+            r#"
+            SELECT *
+            FROM test_suite_steps
+            WHERE test_suite_name = ?1
+            "#,
+        )
+        .bind(test_suite_name)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(r)
+    }
+}
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+
+use crate::db::dto::{Action, Project, TestSuite, TestSuiteInstance};
+
+/// Error messages
+pub static PROJECT_NOT_FOUND: &str =
+    "Project not found. Did you forget to create it running `apikrab project new <project_name>`?";
+pub static TEST_SUITE_NOT_FOUND: &str = "Flow not found";
+
+/// Where projects, actions and test suites are saved,
+/// the history and the context always being kept in the sqlite database
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn get_project(&self, project_name: &str) -> anyhow::Result<Project>;
+
+    async fn upsert_project(&self, project: &Project) -> anyhow::Result<i64>;
+
+    async fn get_projects(&self) -> anyhow::Result<Vec<Project>>;
+
+    /// remove a project and its actions
+    async fn rm_project(&self, project_name: &str) -> anyhow::Result<u64>;
+
+    async fn upsert_action(&self, action: &Action) -> anyhow::Result<()>;
+
+    /// actions of a project, or the ones without project
+    async fn get_actions(&self, project_name: Option<&str>) -> anyhow::Result<Vec<Action>>;
+
+    async fn get_action(&self, action_name: &str) -> anyhow::Result<Action>;
+
+    async fn rm_action(&self, action_name: &str) -> anyhow::Result<u64>;
+
+    /// remove an action of a project, or one without project
+    async fn rm_project_action(
+        &self,
+        project_name: Option<&str>,
+        action_name: &str,
+    ) -> anyhow::Result<u64>;
+
+    async fn upsert_test_suite(&self, test_suite: &TestSuite) -> anyhow::Result<()>;
+
+    async fn get_test_suite(&self, test_suite_name: &str) -> anyhow::Result<TestSuite>;
+
+    async fn get_test_suites(&self) -> anyhow::Result<Vec<TestSuite>>;
+
+    /// remove a test suite and its steps
+    async fn rm_test_suite(&self, test_suite_name: &str) -> anyhow::Result<u64>;
+
+    async fn upsert_test_suite_instance(
+        &self,
+        test_suite_instance: &TestSuiteInstance,
+    ) -> anyhow::Result<()>;
+
+    async fn get_test_suite_instance(
+        &self,
+        test_suite_name: &str,
+    ) -> anyhow::Result<Vec<TestSuiteInstance>>;
+}
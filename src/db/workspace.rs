@@ -0,0 +1,125 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::db::file_storage::WORKSPACE_DIR;
+use crate::HOME_DIR;
+
+/// environment variable overriding the workspace, like --db
+pub const DB_ENV_VAR: &str = "APIKRAB_DB";
+
+/// How the active workspace was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// --db or APIKRAB_DB
+    Override,
+    /// nearest .apikrab directory
+    Local,
+    /// ~/.config/qapi/qapi.sqlite
+    Global,
+}
+
+impl Display for Resolution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Resolution::Override => format!("--db or {}", DB_ENV_VAR),
+            Resolution::Local => format!("nearest {} directory", WORKSPACE_DIR),
+            Resolution::Global => "global default".to_string(),
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Where projects, actions, test suites and the history are saved
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    pub resolution: Resolution,
+    /// directory of the yaml files, everything being in the database if none
+    pub files: Option<PathBuf>,
+    /// sqlite database, holding at least the history
+    pub database: PathBuf,
+}
+
+/// nearest .apikrab directory of a directory or of its parents
+fn discover(from: &Path) -> Option<PathBuf> {
+    from.ancestors()
+        .map(|dir| dir.join(WORKSPACE_DIR))
+        .find(|dir| dir.is_dir())
+}
+
+impl Workspace {
+    fn global_database(home: &Path) -> PathBuf {
+        home.join(".config/qapi/qapi.sqlite")
+    }
+
+    /// --db override, then the nearest .apikrab directory, then the global database.
+    /// An overriding directory holds yaml files, any other path is a sqlite database
+    pub fn resolve_from(db: Option<PathBuf>, current_dir: &Path, home: &Path) -> Workspace {
+        match (db, discover(current_dir)) {
+            (Some(db), _) if db.is_dir() => Workspace {
+                resolution: Resolution::Override,
+                files: Some(db),
+                database: Self::global_database(home),
+            },
+            (Some(db), _) => Workspace {
+                resolution: Resolution::Override,
+                files: None,
+                database: db,
+            },
+            (None, Some(dir)) => Workspace {
+                resolution: Resolution::Local,
+                files: Some(dir),
+                database: Self::global_database(home),
+            },
+            (None, None) => Workspace {
+                resolution: Resolution::Global,
+                files: None,
+                database: Self::global_database(home),
+            },
+        }
+    }
+
+    pub fn resolve(db: Option<&PathBuf>) -> anyhow::Result<Workspace> {
+        let db = db.cloned().or_else(|| {
+            std::env::var(DB_ENV_VAR)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        });
+        Ok(Self::resolve_from(db, &std::env::current_dir()?, &HOME_DIR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_workspace() {
+        let root = std::env::temp_dir().join("apikrab_workspace_test");
+        let _ = std::fs::remove_dir_all(&root);
+        let service = root.join("service");
+        let nested = service.join("src/api");
+        std::fs::create_dir_all(&nested).unwrap();
+        let home = root.join("home");
+
+        let global = Workspace::resolve_from(None, &nested, &home);
+        assert_eq!(global.resolution, Resolution::Global);
+        assert_eq!(global.files, None);
+        assert_eq!(global.database, home.join(".config/qapi/qapi.sqlite"));
+
+        std::fs::create_dir(service.join(WORKSPACE_DIR)).unwrap();
+        let local = Workspace::resolve_from(None, &nested, &home);
+        assert_eq!(local.resolution, Resolution::Local);
+        assert_eq!(local.files, Some(service.join(WORKSPACE_DIR)));
+        assert_eq!(local.database, global.database);
+
+        let db = root.join("other.sqlite");
+        let database = Workspace::resolve_from(Some(db.clone()), &nested, &home);
+        assert_eq!(database.resolution, Resolution::Override);
+        assert_eq!((database.files, database.database), (None, db));
+
+        let files = Workspace::resolve_from(Some(root.clone()), &nested, &home);
+        assert_eq!(files.files, Some(root.clone()));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -14,12 +14,16 @@ use crate::{
         db_handler::DBHandler,
         dto::{Action, History, Project},
     },
-    http::{self, Api, FetchResult},
+    grpc,
+    http::{self, Api, FetchResult, Verb},
+    json_path,
+    stream::StopCondition,
     utils::{
         contains_interpolation, format_query, get_full_url, get_str_as_interpolated_map,
         map_contains_interpolation, parse_multiple_conf_as_opt_with_grouping_and_interpolation,
         Interpol,
     },
+    ws,
 };
 
 use futures::future;
@@ -36,9 +40,47 @@ pub struct DomainAction {
     pub(crate) body: (Option<String>, bool, bool),
     pub(crate) extract_path: Option<HashMap<String, Option<String>>>,
     pub(crate) run_action_args: Option<RunActionArgs>,
+    pub(crate) is_graphql: bool,
+    pub(crate) messages: Vec<String>,
+    pub(crate) stream: bool,
+    pub(crate) stop_condition: StopCondition,
+    pub(crate) protos: Vec<String>,
 }
 
 impl DomainAction {
+    pub fn is_websocket(&self) -> bool {
+        self.verb == Verb::Ws.to_string()
+    }
+
+    pub fn is_grpc(&self) -> bool {
+        self.verb == Verb::Grpc.to_string()
+    }
+
+    /// websocket sessions and streamed responses are made of several messages
+    pub fn is_stream(&self) -> bool {
+        self.stream || self.is_websocket()
+    }
+
+    /// format a message received from a stream,
+    /// printing only extracted values if needed
+    fn format_message(&self, message: &str) -> String {
+        match self.extract_path.as_ref() {
+            Some(extract_path) => extract_path
+                .keys()
+                .map(|pattern| {
+                    format!(
+                        "{}: {}",
+                        pattern,
+                        json_path::json_path(message, pattern)
+                            .map(|v| v.to_string())
+                            .unwrap_or("<none>".to_string())
+                    )
+                })
+                .join(", "),
+            None => message.to_string(),
+        }
+    }
+
     /// check if an action can be run
     pub fn can_be_run(&self) -> bool {
         let mut can_be_ran = true;
@@ -68,12 +110,19 @@ impl DomainAction {
         {
             can_be_ran = false
         }
+        if self
+            .messages
+            .iter()
+            .any(|m| contains_interpolation(m, Interpol::MultiInterpol))
+        {
+            can_be_ran = false
+        }
         can_be_ran
     }
 
     async fn insert_history_line(
         &self,
-        computed_url: &str,
+        url: &str,
         fetch_result: anyhow::Result<&FetchResult, &anyhow::Error>,
         db: &DBHandler,
     ) -> anyhow::Result<i64> {
@@ -81,13 +130,14 @@ impl DomainAction {
         db.insert_history(&History {
             id: None,
             action_name: self.name.clone(),
-            url: computed_url.to_string(),
+            url: url.to_string(),
             body: self.body.0.as_ref().map(|s| s.to_string()),
             headers: Some(serde_json::to_string(&self.headers).unwrap()),
             response: f.map(|r| r.response.clone()).ok(),
             status_code: f.map(|r| r.status).unwrap_or(0u16),
             duration: f.map(|r| r.duration.as_secs_f32()).unwrap_or(0f32),
             created_at: None,
+            verb: Some(self.verb.clone()),
         })
         .await
     }
@@ -174,6 +224,11 @@ impl DomainAction {
             ),
             extract_path: get_xtracted_path(xtract_path, true, ctx),
             run_action_args,
+            is_graphql: false,
+            messages: vec![],
+            stream: false,
+            stop_condition: StopCondition::default(),
+            protos: vec![],
         }
     }
 
@@ -196,8 +251,28 @@ impl DomainAction {
                 let action_cloned = action_opt.cloned();
                 async move {
                     // fetch api
-                    let fetch_result = http
-                        .fetch(
+                    let fetch_result = if self.is_websocket() {
+                        ws::session(
+                            &get_full_url(computed_url, query_params.as_ref()),
+                            self.headers.as_ref().unwrap_or(&HashMap::new()),
+                            &self.messages,
+                            &self.stop_condition,
+                            http.timeout,
+                            http.disable_cert_validation,
+                            |frame| pb.println(format!("⬇️  {}", self.format_message(frame))),
+                        )
+                        .await
+                    } else if self.is_grpc() {
+                        grpc::unary(
+                            computed_url,
+                            self.headers.as_ref().unwrap_or(&HashMap::new()),
+                            self.body.0.as_deref(),
+                            &self.protos,
+                            http.timeout,
+                        )
+                        .await
+                    } else if self.stream {
+                        http.fetch_stream(
                             computed_url,
                             &self.verb,
                             self.headers.as_ref().unwrap_or(&HashMap::new()),
@@ -207,11 +282,31 @@ impl DomainAction {
                                 self.body.1,
                                 self.body.2,
                             ),
+                            &self.stop_condition,
+                            |event| pb.println(format!("⬇️  {}", self.format_message(event))),
                         )
-                        .await;
+                        .await
+                    } else {
+                        http.fetch(
+                            computed_url,
+                            &self.verb,
+                            self.headers.as_ref().unwrap_or(&HashMap::new()),
+                            query_params.as_ref(),
+                            (
+                                self.body.0.as_ref().map(Cow::from),
+                                self.body.1,
+                                self.body.2,
+                            ),
+                        )
+                        .await
+                    };
                     // save history line, let it silent if it fails
                     if let Err(e) = self
-                        .insert_history_line(computed_url, fetch_result.as_ref(), db)
+                        .insert_history_line(
+                            &get_full_url(computed_url, query_params.as_ref()),
+                            fetch_result.as_ref(),
+                            db,
+                        )
                         .await
                     {
                         pb.println(format!("[ERROR] {}", e));
@@ -224,6 +319,7 @@ impl DomainAction {
                     finish_progress_bar(
                         &pb,
                         fetch_result.as_ref(),
+                        self.is_graphql,
                         &format_query(&self.verb, computed_url, query_params.as_ref()),
                     );
                     // returning mixed of result etc...
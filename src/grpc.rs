@@ -0,0 +1,401 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+use prost::Message;
+use prost_reflect::prost_types::FileDescriptorProto;
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor, SerializeOptions};
+use reqwest::Url;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::metadata::{MetadataKey, MetadataValue};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Status};
+use tonic_reflection::pb::server_reflection_client::ServerReflectionClient;
+use tonic_reflection::pb::server_reflection_request::MessageRequest;
+use tonic_reflection::pb::server_reflection_response::MessageResponse;
+use tonic_reflection::pb::ServerReflectionRequest;
+
+use crate::http::FetchResult;
+
+/// Encode and decode messages described at runtime
+struct DynamicCodec(MethodDescriptor);
+
+struct DynamicEncoder;
+
+struct DynamicDecoder(MethodDescriptor);
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder(self.0.clone())
+    }
+}
+
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.0.output(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+/// Split a grpc url (http://host:port/pkg.Service/Method) into
+/// the server address, the service and the method
+fn split_url(url: &str) -> anyhow::Result<(String, String, String)> {
+    let parsed = Url::parse(url)?;
+    let mut segments = parsed
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let (Some(method), Some(service)) = (segments.pop(), segments.pop()) else {
+        anyhow::bail!(
+            "No grpc method in {}, expected host:port/pkg.Service/Method",
+            url
+        );
+    };
+    Ok((
+        parsed.origin().ascii_serialization(),
+        service.to_string(),
+        method.to_string(),
+    ))
+}
+
+/// Descriptors of .proto files, each file directory being an include path
+fn proto_pool(protos: &[String]) -> anyhow::Result<DescriptorPool> {
+    let includes = protos
+        .iter()
+        .map(|p| match Path::new(p).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => Path::new(".").to_path_buf(),
+        })
+        .collect::<HashSet<_>>();
+    let parsed = protobuf_parse::Parser::new()
+        .pure()
+        .includes(includes)
+        .inputs(protos)
+        .parse_and_typecheck()?;
+    let mut files = vec![];
+    for file in parsed.file_descriptors {
+        let bytes = protobuf::Message::write_to_bytes(&file)?;
+        files.push(FileDescriptorProto::decode(bytes.as_slice())?);
+    }
+    let mut pool = DescriptorPool::global();
+    pool.add_file_descriptor_protos(files)?;
+    Ok(pool)
+}
+
+/// Descriptors of the service and its dependencies fetched with server reflection
+async fn reflection_pool(channel: Channel, service: &str) -> anyhow::Result<DescriptorPool> {
+    let mut client = ServerReflectionClient::new(channel);
+    let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+    let mut requests = vec![MessageRequest::FileContainingSymbol(service.to_string())];
+
+    while let Some(message_request) = requests.pop() {
+        let request = ServerReflectionRequest {
+            host: "".to_string(),
+            message_request: Some(message_request),
+        };
+        let mut responses = client
+            .server_reflection_info(futures::stream::iter([request]))
+            .await?
+            .into_inner();
+        while let Some(response) = responses.message().await? {
+            match response.message_response {
+                Some(MessageResponse::FileDescriptorResponse(r)) => {
+                    for bytes in r.file_descriptor_proto {
+                        let file = FileDescriptorProto::decode(bytes.as_slice())?;
+                        files.insert(file.name().to_string(), file);
+                    }
+                }
+                Some(MessageResponse::ErrorResponse(e)) => {
+                    anyhow::bail!("Server reflection error: {}", e.error_message)
+                }
+                _ => {}
+            }
+        }
+        // some servers only send the requested file, ask for missing dependencies
+        let pool = DescriptorPool::global();
+        requests.extend(
+            files
+                .values()
+                .flat_map(|f| f.dependency.iter())
+                .filter(|d| !files.contains_key(*d) && pool.get_file_by_name(d).is_none())
+                .unique()
+                .map(|d| MessageRequest::FileByFilename(d.clone())),
+        );
+    }
+
+    let mut pool = DescriptorPool::global();
+    pool.add_file_descriptor_protos(files.into_values())?;
+    Ok(pool)
+}
+
+/// Closest http status of a grpc status code
+fn http_status(code: Code) -> u16 {
+    match code {
+        Code::Ok => 200,
+        Code::Cancelled => 499,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => 400,
+        Code::Unauthenticated => 401,
+        Code::PermissionDenied => 403,
+        Code::NotFound => 404,
+        Code::AlreadyExists | Code::Aborted => 409,
+        Code::ResourceExhausted => 429,
+        Code::Unimplemented => 501,
+        Code::Unavailable => 503,
+        Code::DeadlineExceeded => 504,
+        Code::Unknown | Code::Internal | Code::DataLoss => 500,
+    }
+}
+
+/// Unary grpc call, the json body is transcoded to protobuf and the response back to json.
+/// Descriptors are loaded from .proto files if any, from server reflection otherwise.
+/// A grpc error is returned as a json {code, message} with the closest http status
+pub async fn unary(
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: Option<&str>,
+    protos: &[String],
+    timeout: Duration,
+) -> anyhow::Result<FetchResult> {
+    let (address, service, method) = split_url(url)?;
+    let mut endpoint = Endpoint::from_shared(address)?
+        .timeout(timeout)
+        .connect_timeout(timeout);
+    if url.starts_with("https") {
+        endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+    }
+
+    let start = Instant::now();
+    let channel = endpoint.connect().await?;
+    let pool = if protos.is_empty() {
+        reflection_pool(channel.clone(), &service).await?
+    } else {
+        proto_pool(protos)?
+    };
+    let method_descriptor = pool
+        .get_service_by_name(&service)
+        .ok_or(anyhow::anyhow!("Unknown grpc service {}", service))?
+        .methods()
+        .find(|m| m.name() == method)
+        .ok_or(anyhow::anyhow!(
+            "Unknown grpc method {}/{}",
+            service,
+            method
+        ))?;
+
+    let mut deserializer = serde_json::Deserializer::from_str(body.unwrap_or("{}"));
+    let message = DynamicMessage::deserialize(method_descriptor.input(), &mut deserializer)?;
+    let mut request = tonic::Request::new(message);
+    // content type is set by the grpc transport
+    for (k, v) in headers
+        .iter()
+        .filter(|(k, _)| !k.eq_ignore_ascii_case("content-type"))
+    {
+        request.metadata_mut().insert(
+            MetadataKey::from_str(&k.to_lowercase())?,
+            MetadataValue::from_str(v)?,
+        );
+    }
+
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await?;
+    let path = format!("/{}/{}", service, method).parse()?;
+    let (response, status) = match client
+        .unary(request, path, DynamicCodec(method_descriptor))
+        .await
+    {
+        Ok(response) => {
+            let mut serializer = serde_json::Serializer::new(vec![]);
+            response.into_inner().serialize_with_options(
+                &mut serializer,
+                &SerializeOptions::new().skip_default_fields(false),
+            )?;
+            (String::from_utf8(serializer.into_inner())?, Code::Ok)
+        }
+        Err(status) => (
+            serde_json::json!({
+                "code": format!("{:?}", status.code()),
+                "message": status.message(),
+            })
+            .to_string(),
+            status.code(),
+        ),
+    };
+
+    Ok(FetchResult {
+        response,
+        status: http_status(status),
+        duration: start.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_url() {
+        let (address, service, method) =
+            split_url("http://localhost:50051/helloworld.Greeter/SayHello").unwrap();
+        assert_eq!(address, "http://localhost:50051");
+        assert_eq!(service, "helloworld.Greeter");
+        assert_eq!(method, "SayHello");
+        assert!(split_url("http://localhost:50051").is_err());
+    }
+
+    #[test]
+    fn test_proto_pool_transcoding() {
+        let dir = std::env::temp_dir().join("apikrab_grpc_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let proto = dir.join("greeter.proto");
+        std::fs::write(
+            &proto,
+            r#"syntax = "proto3";
+package helloworld;
+import "google/protobuf/timestamp.proto";
+service Greeter { rpc SayHello (HelloRequest) returns (HelloReply); }
+message HelloRequest { string name = 1; repeated int32 ids = 2; google.protobuf.Timestamp at = 3; }
+message HelloReply { string message = 1; }
+"#,
+        )
+        .unwrap();
+
+        let pool = proto_pool(&[proto.to_string_lossy().to_string()]).unwrap();
+        let method = pool
+            .get_service_by_name("helloworld.Greeter")
+            .unwrap()
+            .methods()
+            .next()
+            .unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(
+            r#"{"name": "Marco", "ids": [1, 2], "at": "2023-01-01T00:00:00Z"}"#,
+        );
+        let message = DynamicMessage::deserialize(method.input(), &mut deserializer).unwrap();
+        let decoded =
+            DynamicMessage::decode(method.input(), message.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::json!({"name": "Marco", "ids": [1, 2], "at": "2023-01-01T00:00:00Z"})
+        );
+    }
+
+    /// echo service implemented as tonic generated code would do
+    #[derive(Clone)]
+    struct Echo;
+
+    impl tonic::server::NamedService for Echo {
+        const NAME: &'static str = "test.Echo";
+    }
+
+    impl<B> tonic::codegen::Service<tonic::codegen::http::Request<B>> for Echo
+    where
+        B: tonic::codegen::Body + Send + 'static,
+        B::Error: Into<tonic::codegen::StdError> + Send + 'static,
+    {
+        type Response = tonic::codegen::http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = tonic::codegen::BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(
+            &mut self,
+            _: &mut tonic::codegen::Context<'_>,
+        ) -> tonic::codegen::Poll<Result<(), Self::Error>> {
+            tonic::codegen::Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: tonic::codegen::http::Request<B>) -> Self::Future {
+            struct Say;
+            impl tonic::server::UnaryService<String> for Say {
+                type Response = String;
+                type Future = tonic::codegen::BoxFuture<tonic::Response<String>, Status>;
+                fn call(&mut self, request: tonic::Request<String>) -> Self::Future {
+                    Box::pin(async move {
+                        match request.into_inner() {
+                            name if name.is_empty() => Err(Status::invalid_argument("no name")),
+                            name => Ok(tonic::Response::new(format!("hello {}", name))),
+                        }
+                    })
+                }
+            }
+            Box::pin(async move {
+                let mut grpc =
+                    tonic::server::Grpc::new(tonic::codec::ProstCodec::<String, String>::default());
+                Ok(grpc.unary(Say, req).await)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unary_with_server_reflection() {
+        let dir = std::env::temp_dir().join("apikrab_grpc_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let proto = dir.join("echo.proto");
+        std::fs::write(
+            &proto,
+            r#"syntax = "proto3";
+package test;
+import "google/protobuf/wrappers.proto";
+service Echo { rpc Say (google.protobuf.StringValue) returns (google.protobuf.StringValue); }
+"#,
+        )
+        .unwrap();
+        let pool = proto_pool(&[proto.to_string_lossy().to_string()]).unwrap();
+        let reflection = tonic_reflection::server::Builder::configure()
+            .register_file_descriptor_set(prost_reflect::prost_types::FileDescriptorSet {
+                file: pool.file_descriptor_protos().cloned().collect(),
+            })
+            .build()
+            .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/test.Echo/Say", listener.local_addr().unwrap());
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(reflection)
+                .add_service(Echo)
+                .serve_with_incoming(
+                    tonic::codegen::tokio_stream::wrappers::TcpListenerStream::new(listener),
+                ),
+        );
+
+        let timeout = Duration::from_secs(5);
+        let result = unary(&url, &HashMap::new(), Some("\"Marco\""), &[], timeout)
+            .await
+            .unwrap();
+        assert_eq!(result.status, 200);
+        assert_eq!(result.response, "\"hello Marco\"");
+
+        let result = unary(&url, &HashMap::new(), Some("\"\""), &[], timeout)
+            .await
+            .unwrap();
+        assert_eq!(result.status, 400);
+        assert_eq!(
+            result.response,
+            r#"{"code":"InvalidArgument","message":"no name"}"#
+        );
+    }
+}
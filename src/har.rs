@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const HAR_VERSION: &str = "1.2";
+pub const HTTP_VERSION: &str = "HTTP/1.1";
+
+/// HAR 1.2 document as exported by browser devtools,
+/// missing fields are defaulted as most tools only fill a subset
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Har {
+    pub log: Log,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Entry {
+    pub started_date_time: String,
+    /// total time in milliseconds
+    pub time: f64,
+    pub request: Request,
+    pub response: Response,
+    pub cache: Value,
+    pub timings: Timings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<Value>,
+    pub headers: Vec<NameValue>,
+    pub query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Response {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<Value>,
+    pub headers: Vec<NameValue>,
+    pub content: Content,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Content {
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// base64 for binary responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Timings {
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+impl Har {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        Har {
+            log: Log {
+                version: HAR_VERSION.to_string(),
+                creator: Creator {
+                    name: env!("CARGO_PKG_NAME").to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries,
+            },
+        }
+    }
+}
@@ -1,13 +1,17 @@
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 use strum::{Display, EnumString};
+use tokio::time::timeout_at;
+
+use crate::cassette::{Cassette, RecordedRequest};
+use crate::stream::{transcript, transcript_messages, StopCondition, StreamDecoder};
 
 use reqwest::multipart::{Form, Part};
-use reqwest::Method;
+use reqwest::{Method, RequestBuilder};
 
 #[derive(Debug, Clone, EnumString, Display)]
 pub enum Verb {
@@ -17,12 +21,23 @@ pub enum Verb {
     Post,
     #[strum(serialize = "PUT")]
     Put,
+    #[strum(serialize = "PATCH")]
+    Patch,
     #[strum(serialize = "DELETE")]
     Delete,
+    #[strum(serialize = "HEAD")]
+    Head,
     #[strum(serialize = "OPTIONS")]
     Options,
+    #[strum(serialize = "WS")]
+    Ws,
+    #[strum(serialize = "GRPC")]
+    Grpc,
 }
 
+/// status of a successful websocket handshake
+const SWITCHING_PROTOCOLS: u16 = 101;
+
 #[derive(Debug, Clone)]
 pub struct FetchResult {
     pub response: String,
@@ -32,44 +47,73 @@ pub struct FetchResult {
 
 impl FetchResult {
     pub fn is_success(&self) -> bool {
-        self.status >= 200 && self.status < 300
+        (self.status >= 200 && self.status < 300) || self.status == SWITCHING_PROTOCOLS
     }
 }
 
+/// Flatten a json object body to form fields,
+/// non string values are sent as their json representation
+pub(crate) fn body_as_form_fields(body: &str) -> anyhow::Result<HashMap<String, String>> {
+    Ok(
+        serde_json::from_str::<HashMap<String, serde_json::Value>>(body)?
+            .into_iter()
+            .map(|(k, v)| match v {
+                serde_json::Value::String(s) => (k, s),
+                v => (k, v.to_string()),
+            })
+            .collect(),
+    )
+}
+
 pub struct Api {
     pub(crate) client: reqwest::Client,
+    pub(crate) timeout: Duration,
+    pub(crate) disable_cert_validation: bool,
+    /// http exchanges recorded or replayed
+    pub(crate) cassette: Option<Cassette>,
 }
 
 impl Api {
     pub fn new(timeout: Option<u64>, disable_cert_validation: bool) -> Self {
+        let timeout = timeout
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(10));
         Self {
             client: reqwest::ClientBuilder::new()
                 .danger_accept_invalid_certs(disable_cert_validation)
-                .timeout(
-                    timeout
-                        .map(Duration::from_secs)
-                        .unwrap_or(Duration::from_secs(10)),
-                )
+                .timeout(timeout)
                 .build()
                 .expect("Error building reqwest client"),
+            timeout,
+            disable_cert_validation,
+            cassette: None,
         }
     }
 
-    pub async fn fetch(
+    pub fn with_cassette(mut self, cassette: Option<Cassette>) -> Self {
+        self.cassette = cassette;
+        self
+    }
+
+    fn build_request(
         &self,
         url: &str,
         verb: &str,
         headers: &HashMap<String, String>,
         query_params: Option<&HashMap<String, String>>,
         body: (Option<Cow<'_, str>>, bool, bool),
-    ) -> anyhow::Result<FetchResult> {
+    ) -> anyhow::Result<RequestBuilder> {
         // building request
         let mut builder = match Verb::from_str(verb)? {
             Verb::Post => self.client.post(url),
             Verb::Put => self.client.put(url),
+            Verb::Patch => self.client.patch(url),
             Verb::Get => self.client.get(url),
             Verb::Delete => self.client.delete(url),
+            Verb::Head => self.client.head(url),
             Verb::Options => self.client.request(Method::OPTIONS, url),
+            Verb::Ws => anyhow::bail!("Websocket actions run in a websocket session"),
+            Verb::Grpc => anyhow::bail!("Grpc actions run with a grpc client"),
         };
         // query params
         if let Some(qp) = query_params.as_ref() {
@@ -93,18 +137,20 @@ impl Api {
         builder = match (is_url_encoded, is_form_data) {
             (true, true) => panic!("Cannot have both url encoded and form data"),
             (false, false) => {
-                if b.is_some() {
-                    builder = builder.body(b.as_ref().unwrap().to_string());
+                if let Some(b) = b.as_ref() {
+                    // binary file reference left by get_body, sending raw bytes
+                    builder = match b.strip_prefix('@').and_then(|path| fs::read(path).ok()) {
+                        Some(bytes) => builder.body(bytes),
+                        None => builder.body(b.to_string()),
+                    };
                 }
                 builder
             }
-            (true, false) => builder.form(&serde_json::from_str::<HashMap<String, String>>(
-                b.as_ref().unwrap(),
-            )?),
+            (true, false) => builder.form(&body_as_form_fields(b.as_ref().unwrap())?),
             (false, true) => {
                 let mut form = Form::new();
                 let body = b.as_ref().unwrap();
-                for (part_name, v) in serde_json::from_str::<HashMap<String, String>>(body)? {
+                for (part_name, v) in body_as_form_fields(body)? {
                     // handle file upload
                     if v.starts_with('@') {
                         let file_path = v.trim_start_matches('@');
@@ -119,9 +165,32 @@ impl Api {
                 builder.multipart(form)
             }
         };
+        Ok(builder)
+    }
+
+    pub async fn fetch(
+        &self,
+        url: &str,
+        verb: &str,
+        headers: &HashMap<String, String>,
+        query_params: Option<&HashMap<String, String>>,
+        body: (Option<Cow<'_, str>>, bool, bool),
+    ) -> anyhow::Result<FetchResult> {
+        let request = self
+            .build_request(url, verb, headers, query_params, body)?
+            .build()?;
+        let recorded_request = self
+            .cassette
+            .as_ref()
+            .map(|_| RecordedRequest::from(&request));
+        if let (Some(cassette), Some(recorded_request)) = (&self.cassette, &recorded_request) {
+            if let Some(fetch_result) = cassette.replay(recorded_request)? {
+                return Ok(fetch_result);
+            }
+        }
         // launching request
         let start = Instant::now();
-        let response = builder.send().await?;
+        let response = self.client.execute(request).await?;
         let duration = start.elapsed();
 
         // getting status and response
@@ -132,8 +201,91 @@ impl Api {
             status: status.as_u16(),
             duration,
         };
+        if let (Some(cassette), Some(recorded_request)) = (&self.cassette, recorded_request) {
+            if cassette.is_recording() {
+                cassette.record(recorded_request, &fetch_result)?;
+            }
+        }
 
         // return results
         Ok(fetch_result)
     }
+
+    /// Consume a streamed response (Server-Sent Events, NDJSON...) event by event
+    /// until the stop condition is reached, the body ends or the timeout is reached.
+    /// The response of the fetch result is the json array of received events
+    #[allow(clippy::too_many_arguments)]
+    pub async fn fetch_stream(
+        &self,
+        url: &str,
+        verb: &str,
+        headers: &HashMap<String, String>,
+        query_params: Option<&HashMap<String, String>>,
+        body: (Option<Cow<'_, str>>, bool, bool),
+        stop: &StopCondition,
+        mut on_event: impl FnMut(&str),
+    ) -> anyhow::Result<FetchResult> {
+        let request = self
+            .build_request(url, verb, headers, query_params, body)?
+            .build()?;
+        let recorded_request = self
+            .cassette
+            .as_ref()
+            .map(|_| RecordedRequest::from(&request));
+        if let (Some(cassette), Some(recorded_request)) = (&self.cassette, &recorded_request) {
+            if let Some(fetch_result) = cassette.replay(recorded_request)? {
+                // the transcript is replayed, events printed as if received
+                for event in transcript_messages(&fetch_result.response) {
+                    on_event(&event);
+                }
+                return Ok(fetch_result);
+            }
+        }
+        let start = Instant::now();
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut response = self.client.execute(request).await?;
+        let status = response.status();
+
+        let mut decoder = StreamDecoder::new(
+            response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or(""),
+        );
+        let mut events = vec![];
+        while !stop.is_reached(&events) {
+            let chunk = match timeout_at(deadline, response.chunk()).await {
+                Ok(Ok(Some(chunk))) => chunk,
+                // end of the body
+                Ok(Ok(None)) => {
+                    events.extend(decoder.finish());
+                    break;
+                }
+                // deadline reached
+                Ok(Err(e)) if e.is_timeout() => break,
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => break,
+            };
+            for event in decoder.push(&chunk) {
+                on_event(&event);
+                events.push(event);
+                if stop.is_reached(&events) {
+                    break;
+                }
+            }
+        }
+
+        let fetch_result = FetchResult {
+            response: transcript(&events),
+            status: status.as_u16(),
+            duration: start.elapsed(),
+        };
+        if let (Some(cassette), Some(recorded_request)) = (&self.cassette, recorded_request) {
+            if cassette.is_recording() {
+                cassette.record(recorded_request, &fetch_result)?;
+            }
+        }
+        Ok(fetch_result)
+    }
 }
@@ -0,0 +1,216 @@
+use std::str::FromStr;
+
+const METHODS: [&str; 9] = [
+    "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+/// Request of a .http file, {{vars}} are not resolved
+#[derive(Debug, Default, PartialEq)]
+pub struct HttpRequest {
+    /// from the ### separator or a @name comment
+    pub name: Option<String>,
+    pub verb: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+/// JetBrains / VS Code .http or .rest file:
+/// requests separated by ###, @var = value definitions and {{var}} interpolation
+#[derive(Debug, Default, PartialEq)]
+pub struct HttpFile {
+    pub variables: Vec<(String, String)>,
+    pub requests: Vec<HttpRequest>,
+}
+
+enum Section {
+    RequestLine,
+    Headers,
+    Body,
+    /// response handler or reference, ignored until the next request
+    Ignored,
+}
+
+fn comment(line: &str) -> Option<&str> {
+    line.strip_prefix('#')
+        .or_else(|| line.strip_prefix("//"))
+        .map(str::trim)
+}
+
+/// @name = value definition
+fn variable(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.strip_prefix('@')?.split_once('=')?;
+    let name = name.trim();
+    (!name.is_empty() && !name.contains(char::is_whitespace))
+        .then(|| (name.to_string(), value.trim().to_string()))
+}
+
+impl HttpFile {
+    /// parse one block between two ### separators
+    fn parse_block(&mut self, name: Option<String>, lines: &[&str]) {
+        let mut request = HttpRequest {
+            name,
+            ..Default::default()
+        };
+        let mut body = vec![];
+        let mut section = Section::RequestLine;
+        for line in lines {
+            let trimmed = line.trim();
+            match section {
+                Section::RequestLine => {
+                    if trimmed.is_empty() {
+                        continue;
+                    }
+                    if let Some(comment) = comment(trimmed) {
+                        if let Some(name) = comment.strip_prefix("@name") {
+                            request.name = Some(name.trim_start_matches([' ', '=']).to_string());
+                        }
+                        continue;
+                    }
+                    if let Some(var) = variable(trimmed) {
+                        self.variables.push(var);
+                        continue;
+                    }
+                    let mut words = trimmed.split_whitespace();
+                    let first = words.next().unwrap_or_default();
+                    (request.verb, request.url) = match METHODS.contains(&first) {
+                        true => (
+                            first.to_string(),
+                            words.next().unwrap_or_default().to_string(),
+                        ),
+                        // the verb may be omitted for GET requests
+                        false => ("GET".to_string(), first.to_string()),
+                    };
+                    section = Section::Headers;
+                }
+                Section::Headers => {
+                    if trimmed.is_empty() {
+                        section = Section::Body;
+                    } else if trimmed.starts_with('?') || trimmed.starts_with('&') {
+                        // query split on several lines
+                        request.url.push_str(trimmed);
+                    } else if comment(trimmed).is_none() {
+                        if let Some((k, v)) = trimmed.split_once(':') {
+                            request
+                                .headers
+                                .push((k.trim().to_string(), v.trim().to_string()));
+                        }
+                    }
+                }
+                Section::Body => {
+                    if trimmed.starts_with('>') || trimmed.starts_with("<>") {
+                        section = Section::Ignored;
+                    } else {
+                        body.push(*line);
+                    }
+                }
+                Section::Ignored => {}
+            }
+        }
+        if request.url.is_empty() {
+            return;
+        }
+        let body = body.join("\n").trim().to_string();
+        request.body = (!body.is_empty()).then_some(body);
+        self.requests.push(request);
+    }
+}
+
+impl FromStr for HttpFile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut http_file = HttpFile::default();
+        let mut name = None;
+        let mut block = vec![];
+        for line in s.lines() {
+            match line.trim_start().strip_prefix("###") {
+                Some(separator) => {
+                    http_file.parse_block(name, &block);
+                    let separator = separator.trim();
+                    name = (!separator.is_empty()).then(|| separator.to_string());
+                    block.clear();
+                }
+                None => block.push(line),
+            }
+        }
+        http_file.parse_block(name, &block);
+        if http_file.requests.is_empty() {
+            anyhow::bail!("No request found in the .http file");
+        }
+        Ok(http_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_http_file() {
+        let http_file = HttpFile::from_str(
+            r#"@baseUrl = http://localhost:8080/api
+@token = xyz
+
+### list users
+GET {{baseUrl}}/users
+    ?page=1
+    &size=20
+Authorization: Bearer {{token}}
+
+###
+# @name create-user
+POST {{baseUrl}}/users HTTP/1.1
+Content-Type: application/json
+
+{
+  "name": "Marco"
+}
+
+> {%
+    client.global.set("id", response.body.id);
+%}
+
+### no verb
+{{baseUrl}}/health
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            http_file.variables,
+            vec![
+                (
+                    "baseUrl".to_string(),
+                    "http://localhost:8080/api".to_string()
+                ),
+                ("token".to_string(), "xyz".to_string())
+            ]
+        );
+        assert_eq!(
+            http_file.requests,
+            vec![
+                HttpRequest {
+                    name: Some("list users".to_string()),
+                    verb: "GET".to_string(),
+                    url: "{{baseUrl}}/users?page=1&size=20".to_string(),
+                    headers: vec![("Authorization".to_string(), "Bearer {{token}}".to_string())],
+                    body: None,
+                },
+                HttpRequest {
+                    name: Some("create-user".to_string()),
+                    verb: "POST".to_string(),
+                    url: "{{baseUrl}}/users".to_string(),
+                    headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+                    body: Some("{\n  \"name\": \"Marco\"\n}".to_string()),
+                },
+                HttpRequest {
+                    name: Some("no verb".to_string()),
+                    verb: "GET".to_string(),
+                    url: "{{baseUrl}}/health".to_string(),
+                    headers: vec![],
+                    body: None,
+                },
+            ]
+        );
+    }
+}
@@ -79,7 +79,7 @@ fn contains_comp_token(json_str: &str) -> Option<CmpToken> {
     if cmp_tokens.is_empty() {
         return None;
     }
-    return cmp_tokens.first().cloned();
+    cmp_tokens.first().cloned()
 }
 
 fn left_and_right<'a>(json_str: &'a str, split_token: &'a str) -> (&'a str, &'a str) {
@@ -570,14 +570,10 @@ pub fn json_path<'a>(json_str: &'a str, search: &'a str) -> Option<Value> {
     }
 
     let dollar_plus_dot = format!("{}.", dollar);
-    let search = search.strip_prefix(&dollar_plus_dot);
-
-    if search.is_none() {
-        eprintln!("Invalid search: {}", search.unwrap());
+    let Some(search) = search.strip_prefix(&dollar_plus_dot) else {
+        eprintln!("Invalid search: {}", search);
         return None;
-    }
-
-    let search = search.unwrap();
+    };
 
     // parse json string as json value using serde_json
     let json: Rc<Value> = Rc::new(serde_json::from_str(json_str).ok()?);
@@ -632,3 +628,13 @@ pub fn json_path<'a>(json_str: &'a str, search: &'a str) -> Option<Value> {
     }
     Some(result.deref().clone())
 }
+
+/// Check if a json path extracts something from a json string,
+/// null, false and empty arrays do not match
+pub fn json_path_matches(json_str: &str, search: &str) -> bool {
+    match json_path(json_str, search) {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(_) => true,
+    }
+}
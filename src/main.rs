@@ -1,12 +1,20 @@
+mod callback;
+mod cassette;
 mod commands;
 mod db;
 pub mod domain;
+mod grpc;
+mod har;
 mod http;
+mod http_file;
 mod json_path;
+mod stream;
 mod ui;
 mod utils;
+mod ws;
 use std::io;
 use std::path::PathBuf;
+use std::process::exit;
 
 use crate::commands::history::{History, HistoryCommands};
 use clap::{CommandFactory, Parser, Subcommand};
@@ -18,11 +26,18 @@ use lazy_static::lazy_static;
 use sqlx::Either::{Left, Right};
 use sqlx::{Column, Executor, Row};
 
+use crate::commands::db::{Db, DbCommands};
+use crate::commands::export::ExportArgs;
+use crate::commands::import::{Import, ImportCommands};
+use crate::commands::mock::MockArgs;
 use crate::commands::project::{Project, ProjectCommands};
+use crate::commands::record::RecordArgs;
 use crate::commands::run::{Run, RunCommands};
 use crate::commands::ts::{TestSuite, TestSuiteCommands};
+use crate::commands::workspace::{Workspace, WorkspaceCommands};
 use crate::db::db_handler::DBHandler;
 use crate::db::dto::Project as DtoProject;
+use crate::db::workspace::Workspace as DbWorkspace;
 use crate::ui::run_ui::UIRunner;
 
 #[derive(Parser)]
@@ -31,6 +46,10 @@ use crate::ui::run_ui::UIRunner;
 struct Cli {
     #[command(subcommand)]
     commands: Commands,
+
+    /// sqlite database or directory of yaml files to use, APIKRAB_DB if missing
+    #[arg(long, global = true, value_hint = clap::ValueHint::AnyPath)]
+    db: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -46,6 +65,19 @@ enum Commands {
     /// List all history call
     #[command(alias = "h")]
     History(History),
+    /// Import requests as project actions
+    #[command(alias = "i")]
+    Import(Import),
+    /// Print an action as a curl, httpie or code snippet
+    Export(ExportArgs),
+    /// Serve the response examples of a project actions
+    Mock(MockArgs),
+    /// Record the traffic of a local proxy into project actions
+    Record(RecordArgs),
+    /// Where projects and history are saved, see --db and .apikrab directories
+    Workspace(Workspace),
+    /// Export or import projects, actions, test suites and context as a json bundle
+    Db(Db),
     /// Print the completion script in stdout
     PrintCompleteScript { shell: Shell },
     /// Exec sql command (for debug purpose)
@@ -59,6 +91,7 @@ lazy_static! {
         name: "DEFAULT".to_string(),
         main_url: "".to_string(),
         conf: None,
+        source: None,
         created_at: None,
         updated_at: None
     };
@@ -69,7 +102,14 @@ async fn run_wrapper(
     v: Option<Verb>,
     db_handler: &DBHandler,
 ) {
-    let requester = http::Api::new(run_action_args.timeout, run_action_args.insecure);
+    let requester = match run_action_args.cassette.cassette() {
+        Ok(cassette) => http::Api::new(run_action_args.timeout, run_action_args.insecure)
+            .with_cassette(cassette),
+        Err(e) => {
+            eprintln!("Error loading cassette: {}", e);
+            exit(1);
+        }
+    };
     run_action_args.verb = v.map(|v| v.to_string());
     let _ = run_action_args
         .run_action(&requester, db_handler, None, None)
@@ -78,13 +118,14 @@ async fn run_wrapper(
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // init database if needed
-    let mut db_handler = DBHandler::default();
-    db_handler.init_db().await?;
-
     // parse cli args
     let mut cli: Cli = Cli::parse();
 
+    // init database if needed
+    let workspace = DbWorkspace::resolve(cli.db.as_ref())?;
+    let mut db_handler = DBHandler::default();
+    db_handler.init_db(&workspace).await?;
+
     match &mut cli.commands {
         Commands::Project(project) => match &mut project.project_commands {
             ProjectCommands::New(create_project_args) => {
@@ -102,6 +143,18 @@ async fn main() -> anyhow::Result<()> {
             ProjectCommands::Info(project_info_args) => {
                 project_info_args.show_info(&db_handler).await?;
             }
+            ProjectCommands::ExportHttp(export_http_args) => {
+                export_http_args.export(&db_handler).await?;
+            }
+            ProjectCommands::ExportOpenapi(export_openapi_args) => {
+                export_openapi_args.export(&db_handler).await?;
+            }
+            ProjectCommands::ExportPostman(export_postman_args) => {
+                export_postman_args.export(&db_handler).await?;
+            }
+            ProjectCommands::Sync(sync_project_args) => {
+                sync_project_args.sync(&db_handler).await?;
+            }
             ProjectCommands::Ui => {
                 let mut projects = db_handler.get_projects().await?;
                 projects.push(DEFAULT_PROJECT.clone());
@@ -115,7 +168,8 @@ async fn main() -> anyhow::Result<()> {
                 run_wrapper(run_action_args, None, &db_handler).await;
             }
             RunCommands::TestSuite(test_suite_args) => {
-                let requester = http::Api::new(Some(10), true);
+                let requester = http::Api::new(Some(10), true)
+                    .with_cassette(test_suite_args.cassette.cassette()?);
                 test_suite_args
                     .run_test_suite(&requester, &db_handler)
                     .await?;
@@ -129,9 +183,31 @@ async fn main() -> anyhow::Result<()> {
             RunCommands::Put(run_action_args) => {
                 run_wrapper(run_action_args, Some(Verb::Put), &db_handler).await;
             }
+            RunCommands::Patch(run_action_args) => {
+                run_wrapper(run_action_args, Some(Verb::Patch), &db_handler).await;
+            }
             RunCommands::Delete(run_action_args) => {
                 run_wrapper(run_action_args, Some(Verb::Delete), &db_handler).await;
             }
+            RunCommands::Graphql(run_action_args) => {
+                run_wrapper(run_action_args, Some(Verb::Post), &db_handler).await;
+            }
+            RunCommands::Ws(run_action_args) => {
+                run_wrapper(run_action_args, Some(Verb::Ws), &db_handler).await;
+            }
+            RunCommands::Grpc(grpc_args) => {
+                grpc_args.run_action_args.url = Some(format!(
+                    "{}/{}",
+                    grpc_args.target.trim_end_matches('/'),
+                    grpc_args.method.trim_start_matches('/')
+                ));
+                run_wrapper(
+                    &mut grpc_args.run_action_args,
+                    Some(Verb::Grpc),
+                    &db_handler,
+                )
+                .await;
+            }
         },
         Commands::TestSuite(test_suite) => match &mut test_suite.ts_commands {
             TestSuiteCommands::New(create_test_suite_args) => {
@@ -149,6 +225,42 @@ async fn main() -> anyhow::Result<()> {
             HistoryCommands::List(list_args) => {
                 list_args.list_history(&db_handler).await?;
             }
+            HistoryCommands::Export(export_args) => {
+                export_args.export(&db_handler).await?;
+            }
+        },
+        Commands::Import(import) => match &import.import_commands {
+            ImportCommands::Curl(curl_import_args) => {
+                curl_import_args.import(&db_handler).await?;
+            }
+            ImportCommands::Har(har_import_args) => {
+                har_import_args.import(&db_handler).await?;
+            }
+            ImportCommands::Http(http_file_import_args) => {
+                http_file_import_args.import(&db_handler).await?;
+            }
+        },
+        Commands::Export(export_args) => {
+            export_args.export(&db_handler).await?;
+        }
+        Commands::Mock(mock_args) => {
+            mock_args.serve(&db_handler).await?;
+        }
+        Commands::Record(record_args) => {
+            record_args.record(&db_handler).await?;
+        }
+        Commands::Workspace(workspace_commands) => match workspace_commands.workspace_commands {
+            WorkspaceCommands::Info => {
+                commands::workspace::info::show_info(&workspace, &db_handler).await?;
+            }
+        },
+        Commands::Db(db) => match &db.db_commands {
+            DbCommands::Export(export_args) => {
+                export_args.export(&db_handler).await?;
+            }
+            DbCommands::Import(import_args) => {
+                import_args.import(&db_handler).await?;
+            }
         },
         &mut Commands::PrintCompleteScript { shell } => {
             generate(
@@ -0,0 +1,151 @@
+use serde_json::Value;
+
+use crate::json_path::json_path_matches;
+
+const SSE_DATA: &str = "data:";
+
+/// When to stop consuming a websocket session or a streamed response,
+/// the deadline being the request timeout
+#[derive(Debug, Clone, Default)]
+pub struct StopCondition {
+    pub max_messages: Option<usize>,
+    /// json path a message must match to stop
+    pub until: Option<String>,
+}
+
+impl StopCondition {
+    pub fn is_reached(&self, messages: &[String]) -> bool {
+        if let Some(max) = self.max_messages {
+            if messages.len() >= max {
+                return true;
+            }
+        }
+        match (self.until.as_ref(), messages.last()) {
+            (Some(until), Some(last)) => json_path_matches(last, until),
+            _ => false,
+        }
+    }
+}
+
+/// Received messages are stored as a json array,
+/// each message being kept as json when possible
+pub fn transcript(messages: &[String]) -> String {
+    Value::Array(
+        messages
+            .iter()
+            .map(|m| serde_json::from_str(m).unwrap_or(Value::String(m.to_string())))
+            .collect(),
+    )
+    .to_string()
+}
+
+/// Messages of a transcript, e.g. to print the events replayed from a cassette
+pub fn transcript_messages(transcript: &str) -> Vec<String> {
+    serde_json::from_str::<Vec<Value>>(transcript)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|m| match m {
+            Value::String(s) => s,
+            m => m.to_string(),
+        })
+        .collect()
+}
+
+/// Split a streamed body into events as chunks arrive.
+/// Server-Sent Events are separated by a blank line and only their data is kept,
+/// any other stream (e.g. NDJSON) yields one event per line.
+pub struct StreamDecoder {
+    is_sse: bool,
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl StreamDecoder {
+    pub fn new(content_type: &str) -> Self {
+        Self {
+            is_sse: content_type.starts_with("text/event-stream"),
+            buffer: vec![],
+            data: vec![],
+        }
+    }
+
+    fn decode_line(&mut self, line: &str) -> Option<String> {
+        let line = line.trim_end_matches('\r');
+        if !self.is_sse {
+            return (!line.trim().is_empty()).then(|| line.to_string());
+        }
+        if line.is_empty() {
+            // end of the event
+            let event = self.data.join("\n");
+            self.data.clear();
+            return (!event.is_empty()).then_some(event);
+        }
+        // event, id, retry fields and comments are ignored
+        if let Some(data) = line.strip_prefix(SSE_DATA) {
+            self.data
+                .push(data.strip_prefix(' ').unwrap_or(data).to_string());
+        }
+        None
+    }
+
+    /// Events completed by this chunk
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = vec![];
+        while let Some(i) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = self.buffer.drain(..=i).collect::<Vec<u8>>();
+            let line = String::from_utf8_lossy(&line[..line.len() - 1]).into_owned();
+            events.extend(self.decode_line(&line));
+        }
+        events
+    }
+
+    /// Remaining event when the stream ends
+    pub fn finish(mut self) -> Option<String> {
+        let line = String::from_utf8_lossy(&self.buffer).into_owned();
+        self.buffer.clear();
+        let event = self.decode_line(&line);
+        event.or_else(|| self.decode_line(""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_decoder() {
+        let mut decoder = StreamDecoder::new("text/event-stream; charset=utf-8");
+        let mut events = decoder.push(b": comment\nevent: message\ndata: {\"a\":");
+        assert!(events.is_empty());
+        events.extend(decoder.push(b"1}\n\ndata: first\ndata: second\n\ndata: last"));
+        events.extend(decoder.finish());
+        assert_eq!(events, vec!["{\"a\":1}", "first\nsecond", "last"]);
+    }
+
+    #[test]
+    fn test_ndjson_decoder() {
+        let mut decoder = StreamDecoder::new("application/x-ndjson");
+        let mut events = decoder.push(b"{\"n\":1}\n\n{\"n\":");
+        events.extend(decoder.push(b"2}\r\n{\"n\":3}"));
+        events.extend(decoder.finish());
+        assert_eq!(events, vec!["{\"n\":1}", "{\"n\":2}", "{\"n\":3}"]);
+    }
+
+    #[test]
+    fn test_transcript_messages() {
+        let messages = vec!["{\"n\":1}".to_string(), "done".to_string()];
+        assert_eq!(transcript_messages(&transcript(&messages)), messages);
+    }
+
+    #[test]
+    fn test_stop_condition() {
+        let stop = StopCondition {
+            max_messages: Some(3),
+            until: Some("$.done".to_string()),
+        };
+        assert!(!stop.is_reached(&["{\"done\":false}".to_string()]));
+        assert!(stop.is_reached(&["{\"done\":true}".to_string()]));
+        assert!(stop.is_reached(&["1".to_string(), "2".to_string(), "3".to_string()]));
+    }
+}
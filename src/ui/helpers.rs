@@ -49,6 +49,7 @@ where
         self.state().select(Some(i));
     }
 
+    #[allow(dead_code)]
     fn unselect(&mut self) {
         self.state().select(None);
     }
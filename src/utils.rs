@@ -1,17 +1,23 @@
 use crossterm::style::Stylize;
 use itertools::Itertools;
 use rand::*;
+use serde_json::{Map, Value};
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fs;
 use strum::{EnumIter, EnumString, IntoEnumIterator};
 
 pub const SINGLE_INTERPOL_START: char = '{';
-const SINGLE_INTERPOL_END: char = '}';
+pub const SINGLE_INTERPOL_END: char = '}';
 const MULTI_INTERPOL_START: &str = "{{";
 const MULTI_INTERPOL_END: &str = "}}";
 
 pub const SEP: &str = ";";
 
+/// typed assignment in body fields, e.g. age:=18
+const RAW_JSON_ASSIGNMENT: char = '=';
+const FILE_REFERENCE: char = '@';
+
 #[derive(Debug, Clone, Copy)]
 pub enum Interpol {
     MultiInterpol,
@@ -41,17 +47,17 @@ pub enum Expandable {
     AcceptJson,
 }
 
-// do not known how to implement ToString for an enum from strum crate
-impl ToString for Expandable {
-    fn to_string(&self) -> String {
+// do not known how to implement Display for an enum from strum crate
+impl std::fmt::Display for Expandable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expandable::AcceptJson => "Accept:application/json".to_string(),
+            Expandable::AcceptJson => write!(f, "Accept:application/json"),
         }
     }
 }
 
 /// expand predefined values
-fn expand(value_to_expand: &str) -> Cow<str> {
+fn expand(value_to_expand: &str) -> Cow<'_, str> {
     let stored_values = Expandable::iter()
         .map(|h| h.to_string())
         .collect::<Vec<_>>();
@@ -92,6 +98,11 @@ pub fn replace_with_conf<'a>(
 /// Parse a configuration key: str, val: str from a vec of str to a hashmap
 /// Used to parse cli commands
 pub fn parse_cli_conf_to_map(conf: Option<&Vec<String>>) -> Option<HashMap<String, String>> {
+    parse_cli_conf_to_pairs(conf).map(|pairs| pairs.into_iter().collect())
+}
+
+/// Same as parse_cli_conf_to_map keeping repeated keys, e.g. several ANY_MESSAGE expectations
+pub fn parse_cli_conf_to_pairs(conf: Option<&Vec<String>>) -> Option<Vec<(String, String)>> {
     conf.map(|conf| {
         conf.iter()
             .map(|s| s.split(':').collect::<Vec<_>>())
@@ -134,6 +145,110 @@ pub fn parse_multiple_conf(conf: &str) -> HashMap<String, String> {
     _parse_multiple_conf(conf, closure)
 }
 
+#[derive(Debug, PartialEq)]
+enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+    Append,
+}
+
+/// Split a field path like `items[0].id` or `tags[]` into segments
+fn parse_field_path(path: &str) -> anyhow::Result<Vec<PathSegment<'_>>> {
+    let mut segments = vec![];
+    for part in path.split('.') {
+        let (key, mut indexes) = match part.find('[') {
+            Some(i) => part.split_at(i),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            segments.push(PathSegment::Key(key));
+        }
+        while let Some(rest) = indexes.strip_prefix('[') {
+            let (index, remaining) = rest
+                .split_once(']')
+                .ok_or(anyhow::anyhow!("Unclosed bracket in field {}", path))?;
+            segments.push(match index {
+                "" => PathSegment::Append,
+                i => PathSegment::Index(i.parse()?),
+            });
+            indexes = remaining;
+        }
+        if !indexes.is_empty() {
+            anyhow::bail!("Invalid field {}", path);
+        }
+    }
+    if segments.is_empty() {
+        anyhow::bail!("Empty field name");
+    }
+    Ok(segments)
+}
+
+/// Set a value in a json document, creating intermediate objects and arrays
+fn insert_at_path(root: &mut Value, segments: &[PathSegment], value: Value) -> anyhow::Result<()> {
+    let Some((segment, rest)) = segments.split_first() else {
+        *root = value;
+        return Ok(());
+    };
+    let next = match segment {
+        PathSegment::Key(key) => {
+            if root.is_null() {
+                *root = Value::Object(Map::new());
+            }
+            root.as_object_mut()
+                .ok_or(anyhow::anyhow!("Expected an object to set key {}", key))?
+                .entry(key.to_string())
+                .or_insert(Value::Null)
+        }
+        PathSegment::Index(_) | PathSegment::Append => {
+            if root.is_null() {
+                *root = Value::Array(vec![]);
+            }
+            let array = root
+                .as_array_mut()
+                .ok_or(anyhow::anyhow!("Expected an array to set an index"))?;
+            let index = match segment {
+                PathSegment::Index(i) => *i,
+                _ => array.len(),
+            };
+            // an existing index or the next one, no holes of nulls
+            if index > array.len() {
+                anyhow::bail!(
+                    "Index {} out of bounds, the array has {} items",
+                    index,
+                    array.len()
+                );
+            }
+            if index == array.len() {
+                array.push(Value::Null);
+            }
+            &mut array[index]
+        }
+    };
+    insert_at_path(next, rest, value)
+}
+
+/// Parse body fields to a json document, HTTPie style
+/// - `name:Marco` string value
+/// - `age:=18`, `tags:=["a"]` raw json value
+/// - `user:=@user.json` raw json value read from a file
+/// - `user.address.city:Paris`, `items[0].id:=1`, `tags[]:a` nested values
+pub fn parse_body_fields(conf: &str) -> anyhow::Result<Value> {
+    let mut root = Value::Object(Map::new());
+    for field in conf.split(SEP).filter(|f| !f.is_empty()) {
+        let (path, value) = field.split_once(':').unwrap_or((field, ""));
+        let value = match value.strip_prefix(RAW_JSON_ASSIGNMENT) {
+            Some(raw) => match raw.strip_prefix(FILE_REFERENCE) {
+                Some(file_path) => serde_json::from_str(&fs::read_to_string(file_path)?)?,
+                None => serde_json::from_str(raw)
+                    .map_err(|e| anyhow::anyhow!("Invalid json value for {}: {}", path, e))?,
+            },
+            None => Value::String(value.to_string()),
+        };
+        insert_at_path(&mut root, &parse_field_path(path)?, value)?;
+    }
+    Ok(root)
+}
+
 /// only for extracted path
 /// it is not json for sure
 pub fn parse_multiple_conf_with_opt(conf: &str) -> HashMap<String, Option<String>> {
@@ -321,4 +436,29 @@ mod tests {
         assert_eq!(parsed.get("a").unwrap(), &vec!["1", "2", "3"]);
         assert_eq!(parsed.get("b").unwrap(), &vec!["4", "5", "6"]);
     }
+
+    #[test]
+    fn test_parse_body_fields() {
+        let body = parse_body_fields(
+            r#"name:Marco;age:=18;active:=true;tags:=["a","b"];user.address.city:Paris;items[0].id:=1;items[1].id:=2;ids[]:=3"#,
+        )
+        .unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "name": "Marco",
+                "age": 18,
+                "active": true,
+                "tags": ["a", "b"],
+                "user": {"address": {"city": "Paris"}},
+                "items": [{"id": 1}, {"id": 2}],
+                "ids": [3]
+            })
+        );
+        assert!(parse_body_fields("age:=eighteen").is_err());
+        assert!(parse_body_fields("items[0:1").is_err());
+        // indexes past the end would fill the array with nulls
+        assert!(parse_body_fields("items[99999999]:=1").is_err());
+        assert!(parse_body_fields("items[0]:=1;items[2]:=3").is_err());
+    }
 }
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use futures::{SinkExt, StreamExt};
+use tokio::time::timeout_at;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async_tls_with_config, Connector};
+
+use crate::http::FetchResult;
+use crate::stream::{transcript, StopCondition};
+
+/// Open a websocket session, send messages in order then collect received frames
+/// until the stop condition is reached, the server closes the connection
+/// or the timeout is reached.
+/// The response of the fetch result is the json array of received frames
+pub async fn session(
+    url: &str,
+    headers: &HashMap<String, String>,
+    messages: &[String],
+    stop: &StopCondition,
+    timeout: Duration,
+    insecure: bool,
+    mut on_frame: impl FnMut(&str),
+) -> anyhow::Result<FetchResult> {
+    let mut request = url.into_client_request()?;
+    for (k, v) in headers {
+        request.headers_mut().insert(
+            HeaderName::from_bytes(k.as_bytes())?,
+            HeaderValue::from_str(v)?,
+        );
+    }
+    let connector = Connector::NativeTls(
+        native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(insecure)
+            .build()?,
+    );
+
+    let start = Instant::now();
+    let deadline = tokio::time::Instant::now() + timeout;
+    let (mut stream, response) = timeout_at(
+        deadline,
+        connect_async_tls_with_config(request, None, false, Some(connector)),
+    )
+    .await??;
+
+    for message in messages {
+        stream.send(Message::Text(message.clone())).await?;
+    }
+
+    let mut received = vec![];
+    while !stop.is_reached(&received) {
+        let frame = match timeout_at(deadline, stream.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => text,
+            Ok(Some(Ok(Message::Binary(bytes)))) => String::from_utf8_lossy(&bytes).into_owned(),
+            // ping, pong and raw frames
+            Ok(Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)))) => continue,
+            Ok(Some(Err(e))) => return Err(e.into()),
+            // closed by the server or deadline reached
+            Ok(Some(Ok(Message::Close(_))) | None) | Err(_) => break,
+        };
+        on_frame(&frame);
+        received.push(frame);
+    }
+    let _ = stream.close(None).await;
+
+    Ok(FetchResult {
+        response: transcript(&received),
+        status: response.status().as_u16(),
+        duration: start.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// echo every text frame back to the client
+    async fn echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (tcp_stream, _) = listener.accept().await.unwrap();
+            let mut ws_stream = tokio_tungstenite::accept_async(tcp_stream).await.unwrap();
+            while let Some(Ok(message)) = ws_stream.next().await {
+                if message.is_text() && ws_stream.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+        format!("ws://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_session_with_echo_server() {
+        let url = echo_server().await;
+        let mut printed = vec![];
+        let result = session(
+            &url,
+            &HashMap::new(),
+            &[r#"{"id": 1}"#.to_string(), "hello".to_string()],
+            &StopCondition {
+                max_messages: Some(2),
+                until: None,
+            },
+            Duration::from_secs(5),
+            false,
+            |frame| printed.push(frame.to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.status, 101);
+        assert!(result.is_success());
+        assert_eq!(result.response, r#"[{"id":1},"hello"]"#);
+        assert_eq!(printed.len(), 2);
+    }
+}
@@ -1,6 +1,8 @@
 use std::str::FromStr;
 
-use apikrab::json_path::{json_path, parse_input_js_path, CmpToken, JspExp, JspToken};
+use apikrab::json_path::{
+    json_path, json_path_matches, parse_input_js_path, CmpToken, JspExp, JspToken,
+};
 use itertools::Itertools;
 use serde_json::{Map, Value};
 
@@ -251,3 +253,12 @@ fn test_machines_join() {
         .collect();
     assert_eq!(v, [", "]);
 }
+
+#[test]
+fn test_json_path_matches() {
+    let json = r#"{"done": false, "items": [], "result": {"id": 1}}"#;
+    assert!(json_path_matches(json, "$.result.id"));
+    assert!(!json_path_matches(json, "$.done"));
+    assert!(!json_path_matches(json, "$.items"));
+    assert!(!json_path_matches(json, "$.missing"));
+}